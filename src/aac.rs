@@ -0,0 +1,468 @@
+use crate::id3::{id3v2_length, ID3};
+use crate::mp4::AAC_SAMPLE_RATES;
+use crate::options::ParseOptions;
+use crate::picture::Picture;
+use crate::properties::{samples_duration, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::read_bits;
+use std::time::Duration;
+
+/// Raw AAC file, a stream of ADTS frames or a single ADIF header followed by raw data blocks,
+/// may be prefixed by ID3v2 tags
+///
+/// Structure
+/// ```text
+/// | ID3v2 * | ADTS frame +                 |
+/// | ID3v2 * | ADIF header | raw data block |
+/// ```
+#[derive(Debug)]
+pub struct Aac<'a> {
+    id3: Option<ID3<'a>>,
+    format: AacFormat,
+    audio_offset: usize,
+    audio_size: usize,
+    /// ADTS frames
+    frames: u64,
+    /// Samples per channel of the ADTS frames
+    samples: u64,
+}
+
+#[derive(Debug)]
+pub enum AacFormat {
+    Adts(AdtsHeader),
+    Adif(AdifHeader),
+}
+
+impl<'a> Reader<'a> for Aac<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
+        if !Aac::is(bytes) {
+            panic!("Invalid aac audio format.");
+        }
+        let offset = id3v2_length(bytes);
+        let id3 = if offset > 0 {
+            Some(ID3::from_bytes_with_options(&bytes[..offset], options))
+        } else {
+            None
+        };
+        if bytes[offset..offset + 4] == *b"ADIF" {
+            return Self {
+                id3,
+                format: AacFormat::Adif(AdifHeader::new(&bytes[offset..])),
+                audio_offset: offset,
+                audio_size: bytes.len() - offset,
+                frames: 0,
+                samples: 0,
+            };
+        }
+        let header = AdtsHeader::new(&bytes[offset..]).unwrap();
+        let mut aac = Self {
+            id3,
+            format: AacFormat::Adts(header),
+            audio_offset: offset,
+            audio_size: 0,
+            frames: 0,
+            samples: 0,
+        };
+        // stops at the first lost sync, e.g. the trailing ID3v1 or APE tag
+        let mut cur = offset;
+        while let Some(header) = bytes.get(cur..).and_then(AdtsHeader::new) {
+            if cur + header.frame_length > bytes.len() {
+                break;
+            }
+            aac.frames += 1;
+            aac.samples += header.samples() as u64;
+            aac.audio_size += header.frame_length;
+            cur += header.frame_length;
+        }
+        aac
+    }
+    fn is(bytes: &[u8]) -> bool {
+        let offset = id3v2_length(bytes);
+        match bytes.get(offset..) {
+            Some(bytes) if bytes.len() >= 4 && bytes[0..4] == *b"ADIF" => true,
+            Some(bytes) => AdtsHeader::new(bytes).is_some(),
+            None => false,
+        }
+    }
+}
+
+impl<'a> Aac<'a> {
+    /// Tag before the audio
+    pub fn id3(&self) -> Option<&ID3<'a>> {
+        self.id3.as_ref()
+    }
+    /// Pictures of the ID3 tag
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.id3.as_ref().map(|it| it.pictures()).unwrap_or_default()
+    }
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        self.id3.as_ref().and_then(|it| it.front_cover())
+    }
+    pub fn format(&self) -> &AacFormat {
+        &self.format
+    }
+    pub fn audio_offset(&self) -> usize {
+        self.audio_offset
+    }
+    pub fn audio_size(&self) -> usize {
+        self.audio_size
+    }
+    /// Count of the ADTS frames
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+    /// MPEG-4 audio object type, e.g. 2 AAC LC
+    pub fn object_type(&self) -> u8 {
+        match &self.format {
+            AacFormat::Adts(header) => header.object_type(),
+            AacFormat::Adif(header) => header.object_type(),
+        }
+    }
+    pub fn sample_rate(&self) -> u32 {
+        match &self.format {
+            AacFormat::Adts(header) => header.sample_rate(),
+            AacFormat::Adif(header) => header.sample_rate(),
+        }
+    }
+    pub fn channels(&self) -> u16 {
+        match &self.format {
+            AacFormat::Adts(header) => header.channels(),
+            AacFormat::Adif(header) => header.channels(),
+        }
+    }
+    /// Counted from the ADTS frames, estimated from the bitrate of ADIF
+    pub fn duration(&self) -> Option<Duration> {
+        match &self.format {
+            AacFormat::Adts(header) => samples_duration(self.samples, header.sample_rate()),
+            AacFormat::Adif(header) if header.bitrate > 0 => Some(Duration::from_secs_f64(
+                self.audio_size as f64 * 8.0 / header.bitrate as f64,
+            )),
+            AacFormat::Adif(_) => None,
+        }
+    }
+    /// Average bitrate in bits per second
+    pub fn bitrate(&self) -> Option<u32> {
+        match &self.format {
+            AacFormat::Adts(_) => {
+                let seconds = self.duration()?.as_secs_f64();
+                (seconds > 0.0).then(|| (self.audio_size as f64 * 8.0 / seconds) as u32)
+            }
+            AacFormat::Adif(header) => (header.bitrate > 0).then_some(header.bitrate),
+        }
+    }
+    pub fn properties(&self) -> AudioProperties {
+        let container = match &self.format {
+            AacFormat::Adts(_) => Container::Adts,
+            AacFormat::Adif(_) => Container::Adif,
+        };
+        AudioProperties::new(container, "AAC")
+            .with_duration(self.duration())
+            .with_sample_rate(self.sample_rate())
+            .with_channels(self.channels())
+            .with_bitrate(self.bitrate().map(|it| it / 1000))
+    }
+}
+
+/// Channels of a channel configuration, 0 means the channels are defined in the stream
+fn configuration_channels(configuration: u8) -> u16 {
+    match configuration {
+        7 => 8,
+        configuration => configuration as u16,
+    }
+}
+
+/// ADTS frame header
+///
+/// Structure
+/// ```text
+/// | sync (0xFFF) | id | layer | protection absent | profile | sampling index | private |
+/// |   12 bits    | 1  |   2   |         1         |    2    |       4        |    1    |
+/// | channel configuration | original | home | copyright id | copyright start |
+/// |           3           |    1     |  1   |      1       |        1        |
+/// | frame length | buffer fullness | raw data blocks - 1 | crc (if not protection absent) |
+/// |      13      |       11        |          2          |             16                 |
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdtsHeader {
+    /// 0 MPEG-4, 1 MPEG-2
+    id: u8,
+    protection_absent: bool,
+    profile: u8,
+    sampling_index: u8,
+    channel_configuration: u8,
+    /// Including the header
+    frame_length: usize,
+    buffer_fullness: u16,
+    raw_data_blocks: u8,
+}
+
+impl AdtsHeader {
+    pub(crate) fn new(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 7 || bytes[0] != 0xFF || bytes[1] & 0xF6 != 0xF0 {
+            return None;
+        }
+        let mut position = 12;
+        let mut read = |count: u32| read_bits(bytes, &mut position, count);
+        let id = read(1) as u8;
+        read(2);
+        let protection_absent = read(1) == 1;
+        let profile = read(2) as u8;
+        let sampling_index = read(4) as u8;
+        read(1);
+        let channel_configuration = read(3) as u8;
+        read(4);
+        let frame_length = read(13) as usize;
+        let buffer_fullness = read(11) as u16;
+        let raw_data_blocks = read(2) as u8 + 1;
+        let header_length = if protection_absent { 7 } else { 9 };
+        if sampling_index as usize >= AAC_SAMPLE_RATES.len() || frame_length < header_length {
+            return None;
+        }
+        Some(Self {
+            id,
+            protection_absent,
+            profile,
+            sampling_index,
+            channel_configuration,
+            frame_length,
+            buffer_fullness,
+            raw_data_blocks,
+        })
+    }
+    /// 2 MPEG-2, 4 MPEG-4
+    pub fn mpeg_version(&self) -> u8 {
+        if self.id == 1 {
+            2
+        } else {
+            4
+        }
+    }
+    pub fn is_protected(&self) -> bool {
+        !self.protection_absent
+    }
+    /// 0 Main, 1 LC, 2 SSR, 3 LTP
+    pub fn profile(&self) -> u8 {
+        self.profile
+    }
+    /// MPEG-4 audio object type, the profile + 1
+    pub fn object_type(&self) -> u8 {
+        self.profile + 1
+    }
+    pub fn sampling_index(&self) -> u8 {
+        self.sampling_index
+    }
+    pub fn sample_rate(&self) -> u32 {
+        AAC_SAMPLE_RATES[self.sampling_index as usize]
+    }
+    /// 0 defined in the stream, 1 to 6 the channels, 7 is 7.1
+    pub fn channel_configuration(&self) -> u8 {
+        self.channel_configuration
+    }
+    pub fn channels(&self) -> u16 {
+        configuration_channels(self.channel_configuration)
+    }
+    /// Including the header
+    pub fn frame_length(&self) -> usize {
+        self.frame_length
+    }
+    /// 0x7FF for VBR
+    pub fn buffer_fullness(&self) -> u16 {
+        self.buffer_fullness
+    }
+    pub fn raw_data_blocks(&self) -> u8 {
+        self.raw_data_blocks
+    }
+    /// Samples per channel of the frame, 1024 per raw data block
+    pub fn samples(&self) -> u32 {
+        self.raw_data_blocks as u32 * 1024
+    }
+}
+
+/// ADIF header, the first program config element describes the stream
+///
+/// Structure
+/// ```text
+/// | "ADIF" | copyright id present | (copyright id) | original | home | bitstream type |
+/// |   32   |          1           |     (72)       |    1     |  1   |       1        |
+/// | bitrate | program config elements - 1 | (buffer fullness, CBR only) | program config element + |
+/// |   23    |              4              |           (20)              |                          |
+/// Program config element
+/// | element tag | object type | sampling index | front | side | back | lfe | assoc data | cc |
+/// |      4      |      2      |       4        |   4   |  4   |  4   |  2  |     3      |  4 |
+/// | mixdowns | front, side and back elements (is cpe 1, tag 4) * | lfe elements (tag 4) * |
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdifHeader {
+    copyright_id: Option<[u8; 9]>,
+    original: bool,
+    home: bool,
+    /// 0 CBR, 1 VBR
+    bitstream_type: u8,
+    /// Bits per second, the peak bitrate of VBR
+    bitrate: u32,
+    profile: u8,
+    sampling_index: u8,
+    channels: u16,
+}
+
+impl AdifHeader {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut position = 32;
+        let mut read = |count: u32| read_bits(bytes, &mut position, count);
+        let copyright_id = if read(1) == 1 {
+            let mut id = [0u8; 9];
+            id.iter_mut().for_each(|it| *it = read(8) as u8);
+            Some(id)
+        } else {
+            None
+        };
+        let original = read(1) == 1;
+        let home = read(1) == 1;
+        let bitstream_type = read(1) as u8;
+        let bitrate = read(23);
+        read(4);
+        if bitstream_type == 0 {
+            read(20);
+        }
+        read(4);
+        let profile = read(2) as u8;
+        let sampling_index = read(4) as u8;
+        let front = read(4);
+        let side = read(4);
+        let back = read(4);
+        let lfe = read(2);
+        read(3 + 4);
+        // mono, stereo and matrix mixdowns
+        for len in [4, 4, 3] {
+            if read(1) == 1 {
+                read(len);
+            }
+        }
+        let mut channels = lfe as u16;
+        for _ in 0..front + side + back {
+            // channel pair element
+            channels += if read(1) == 1 { 2 } else { 1 };
+            read(4);
+        }
+        Self {
+            copyright_id,
+            original,
+            home,
+            bitstream_type,
+            bitrate,
+            profile,
+            sampling_index,
+            channels,
+        }
+    }
+    pub fn copyright_id(&self) -> Option<&[u8; 9]> {
+        self.copyright_id.as_ref()
+    }
+    pub fn is_original(&self) -> bool {
+        self.original
+    }
+    pub fn is_home(&self) -> bool {
+        self.home
+    }
+    pub fn is_vbr(&self) -> bool {
+        self.bitstream_type == 1
+    }
+    /// Bits per second, the peak bitrate of VBR
+    pub fn bitrate(&self) -> u32 {
+        self.bitrate
+    }
+    /// 0 Main, 1 LC, 2 SSR, 3 LTP
+    pub fn profile(&self) -> u8 {
+        self.profile
+    }
+    pub fn object_type(&self) -> u8 {
+        self.profile + 1
+    }
+    pub fn sample_rate(&self) -> u32 {
+        AAC_SAMPLE_RATES
+            .get(self.sampling_index as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// AAC LC, 44100 Hz, stereo
+    fn adts_frame(len: usize) -> Vec<u8> {
+        let mut frame = vec![
+            0xFF,
+            0xF1,
+            0x50,
+            0x80 | (len >> 11) as u8,
+            (len >> 3) as u8,
+            ((len & 0x07) << 5) as u8 | 0x1F,
+            0xFC,
+        ];
+        frame.resize(len, 0x00);
+        frame
+    }
+
+    #[test]
+    fn test_adts() {
+        let mut bytes = b"ID3\x03\x00\x00\x00\x00\x00\x10".to_vec();
+        bytes.extend(b"TIT2\x00\x00\x00\x06\x00\x00\x03Title");
+        // 43 frames of 1024 samples, about 1 second
+        for _ in 0..43 {
+            bytes.extend(adts_frame(400));
+        }
+        bytes.extend(b"TAG");
+        let aac = Aac::from_bytes(&bytes);
+        assert!(aac.id3().is_some());
+        let AacFormat::Adts(header) = aac.format() else {
+            panic!("Expected ADTS");
+        };
+        assert_eq!(header.mpeg_version(), 4);
+        assert_eq!(header.profile(), 1);
+        assert_eq!(header.frame_length(), 400);
+        assert_eq!(aac.sample_rate(), 44100);
+        assert_eq!(aac.channels(), 2);
+        assert_eq!(aac.frames(), 43);
+        assert_eq!(aac.audio_size(), 43 * 400);
+        assert_eq!(
+            aac.duration(),
+            Some(Duration::from_secs_f64(44032.0 / 44100.0))
+        );
+        // 400 bytes every 1024 samples
+        assert_eq!(aac.bitrate(), Some(137812));
+    }
+
+    #[test]
+    fn test_adif() {
+        // no copyright id, VBR, 128 kbps, LC, 48000 Hz, 1 front channel pair element and 1 lfe
+        let bits = "0 0 0 1 00000011111010000000000 0000 0000 01 0011 0001 0000 0000 01 000 0000 0 0 0 1 0000 0000";
+        let bits = bits.replace(' ', "");
+        let mut bytes = b"ADIF".to_vec();
+        for chunk in bits.as_bytes().chunks(8) {
+            let mut byte = 0u8;
+            for (index, bit) in chunk.iter().enumerate() {
+                byte |= (bit - b'0') << (7 - index);
+            }
+            bytes.push(byte);
+        }
+        bytes.resize(16000, 0x00);
+        let aac = Aac::from_bytes(&bytes);
+        let AacFormat::Adif(header) = aac.format() else {
+            panic!("Expected ADIF");
+        };
+        assert!(header.is_vbr());
+        assert_eq!(header.bitrate(), 128000);
+        assert_eq!(aac.object_type(), 2);
+        assert_eq!(aac.sample_rate(), 48000);
+        assert_eq!(aac.channels(), 3);
+        assert_eq!(aac.duration(), Some(Duration::from_millis(1000)));
+    }
+}
@@ -0,0 +1,305 @@
+use crate::id3::id3v2_length;
+use crate::properties::{samples_duration, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::read_bits;
+use std::time::Duration;
+
+/// Bitrates in kbps of the AC-3 frame size codes, every 2 codes share a bitrate
+const BITRATES: [u32; 19] = [
+    32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640,
+];
+const SAMPLE_RATES: [u32; 3] = [48000, 44100, 32000];
+/// Reduced sample rates of E-AC-3 when `fscod` is 3
+const REDUCED_SAMPLE_RATES: [u32; 3] = [24000, 22050, 16000];
+/// Channels of the audio coding modes, without the LFE
+const ACMOD_CHANNELS: [u16; 8] = [2, 1, 2, 3, 3, 4, 4, 5];
+
+/// Raw AC-3 or E-AC-3 file, a stream of sync frames, all fields are big-endian
+///
+/// Structure
+/// ```text
+/// | sync frame +                                      |
+/// | sync info | bit stream info | audio blocks | crc |
+/// ```
+#[derive(Debug)]
+pub struct Ac3 {
+    header: Ac3FrameHeader,
+    audio_offset: usize,
+    audio_size: usize,
+    frames: u64,
+    /// Samples per channel of the independent substream
+    samples: u64,
+}
+
+impl Reader<'_> for Ac3 {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        if !Ac3::is(bytes) {
+            panic!("Invalid ac3 audio format.");
+        }
+        let offset = id3v2_length(bytes);
+        let mut ac3 = Self {
+            header: Ac3FrameHeader::new(&bytes[offset..]).unwrap(),
+            audio_offset: offset,
+            audio_size: 0,
+            frames: 0,
+            samples: 0,
+        };
+        // stops at the first lost sync
+        let mut cur = offset;
+        while let Some(header) = bytes.get(cur..).and_then(Ac3FrameHeader::new) {
+            if cur + header.frame_length > bytes.len() {
+                break;
+            }
+            ac3.frames += 1;
+            // dependent substreams carry extra channels of the same samples
+            if header.is_independent() {
+                ac3.samples += header.samples() as u64;
+            }
+            ac3.audio_size += header.frame_length;
+            cur += header.frame_length;
+        }
+        ac3
+    }
+    fn is(bytes: &[u8]) -> bool {
+        let offset = id3v2_length(bytes);
+        bytes.get(offset..).and_then(Ac3FrameHeader::new).is_some()
+    }
+}
+
+impl Ac3 {
+    /// Header of the first sync frame
+    pub fn header(&self) -> &Ac3FrameHeader {
+        &self.header
+    }
+    pub fn audio_offset(&self) -> usize {
+        self.audio_offset
+    }
+    pub fn audio_size(&self) -> usize {
+        self.audio_size
+    }
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+    pub fn is_eac3(&self) -> bool {
+        self.header.is_eac3()
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        samples_duration(self.samples, self.header.sample_rate)
+    }
+    /// Average bitrate in bits per second
+    pub fn bitrate(&self) -> Option<u32> {
+        let seconds = self.duration()?.as_secs_f64();
+        (seconds > 0.0).then(|| (self.audio_size as f64 * 8.0 / seconds) as u32)
+    }
+    pub fn properties(&self) -> AudioProperties {
+        let codec = if self.is_eac3() { "E-AC-3" } else { "AC-3" };
+        AudioProperties::new(Container::Ac3, codec)
+            .with_duration(self.duration())
+            .with_sample_rate(self.header.sample_rate)
+            .with_channels(self.header.channels())
+            .with_bitrate(self.bitrate().map(|it| it / 1000))
+    }
+}
+
+/// AC-3 / E-AC-3 sync frame header, the `bsid` after the sync word tells them apart,
+/// AC-3 up to 10 and E-AC-3 16
+///
+/// AC-3
+/// ```text
+/// | sync (0x0B77) | crc1 | fscod | frmsizecod | bsid | bsmod | acmod | mix levels | lfeon |
+/// |      16       |  16  |   2   |     6      |  5   |   3   |   3   |   0 - 6    |   1   |
+/// ```
+///
+/// E-AC-3
+/// ```text
+/// | sync (0x0B77) | strmtyp | substreamid | frmsiz | fscod | fscod2 or numblkscod |
+/// |      16       |    2    |      3      |   11   |   2   |          2           |
+/// | acmod | lfeon | bsid |
+/// |   3   |   1   |  5   |
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ac3FrameHeader {
+    bsid: u8,
+    fscod: u8,
+    sample_rate: u32,
+    acmod: u8,
+    lfe: bool,
+    /// Including the header
+    frame_length: usize,
+    /// Audio blocks of 256 samples, 6 for AC-3
+    blocks: u8,
+    /// AC-3 only, kbps of the frame size code
+    bitrate: Option<u32>,
+    /// E-AC-3 only, 0 independent, 1 dependent, 2 AC-3 converted
+    stream_type: Option<u8>,
+    substream_id: u8,
+}
+
+impl Ac3FrameHeader {
+    pub(crate) fn new(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 8 || bytes[0..2] != [0x0B, 0x77] {
+            return None;
+        }
+        let bsid = bytes[5] >> 3;
+        let mut position = 16;
+        let mut read = |count: u32| read_bits(bytes, &mut position, count);
+        match bsid {
+            0..=10 => {
+                read(16);
+                let fscod = read(2) as u8;
+                let frmsizecod = read(6) as u8;
+                read(5 + 3);
+                let acmod = read(3) as u8;
+                if acmod & 0x01 != 0 && acmod != 1 {
+                    read(2);
+                }
+                if acmod & 0x04 != 0 {
+                    read(2);
+                }
+                if acmod == 2 {
+                    read(2);
+                }
+                let lfe = read(1) == 1;
+                let kbps = *BITRATES.get(frmsizecod as usize / 2)?;
+                let words = match fscod {
+                    0 => kbps * 2,
+                    1 => kbps * 96000 / 44100 + (frmsizecod & 0x01) as u32,
+                    2 => kbps * 3,
+                    _ => return None,
+                };
+                // half and quarter sample rates
+                let shift = bsid.saturating_sub(8);
+                Some(Self {
+                    bsid,
+                    fscod,
+                    sample_rate: SAMPLE_RATES[fscod as usize] >> shift,
+                    acmod,
+                    lfe,
+                    frame_length: words as usize * 2,
+                    blocks: 6,
+                    bitrate: Some(kbps >> shift),
+                    stream_type: None,
+                    substream_id: 0,
+                })
+            }
+            16 => {
+                let stream_type = read(2) as u8;
+                let substream_id = read(3) as u8;
+                let frame_size = read(11) as usize;
+                let fscod = read(2) as u8;
+                let (sample_rate, blocks) = if fscod == 3 {
+                    let fscod2 = read(2) as usize;
+                    (*REDUCED_SAMPLE_RATES.get(fscod2)?, 6)
+                } else {
+                    (SAMPLE_RATES[fscod as usize], [1, 2, 3, 6][read(2) as usize])
+                };
+                let acmod = read(3) as u8;
+                let lfe = read(1) == 1;
+                Some(Self {
+                    bsid,
+                    fscod,
+                    sample_rate,
+                    acmod,
+                    lfe,
+                    frame_length: (frame_size + 1) * 2,
+                    blocks,
+                    bitrate: None,
+                    stream_type: Some(stream_type),
+                    substream_id,
+                })
+            }
+            _ => None,
+        }
+    }
+    /// Bit stream identification, 8 AC-3, 16 E-AC-3
+    pub fn bsid(&self) -> u8 {
+        self.bsid
+    }
+    pub fn is_eac3(&self) -> bool {
+        self.bsid == 16
+    }
+    /// 0 48 kHz, 1 44.1 kHz, 2 32 kHz, 3 reduced sample rate of E-AC-3
+    pub fn fscod(&self) -> u8 {
+        self.fscod
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// Audio coding mode, 0 1+1, 1 1/0, 2 2/0, 3 3/0, 4 2/1, 5 3/1, 6 2/2, 7 3/2
+    pub fn acmod(&self) -> u8 {
+        self.acmod
+    }
+    pub fn has_lfe(&self) -> bool {
+        self.lfe
+    }
+    /// Channels including the LFE
+    pub fn channels(&self) -> u16 {
+        ACMOD_CHANNELS[self.acmod as usize] + self.lfe as u16
+    }
+    /// Including the header
+    pub fn frame_length(&self) -> usize {
+        self.frame_length
+    }
+    /// Samples per channel of the frame
+    pub fn samples(&self) -> u32 {
+        self.blocks as u32 * 256
+    }
+    /// kbps, AC-3 only
+    pub fn bitrate(&self) -> Option<u32> {
+        self.bitrate
+    }
+    /// E-AC-3 only, 0 independent, 1 dependent, 2 AC-3 converted
+    pub fn stream_type(&self) -> Option<u8> {
+        self.stream_type
+    }
+    pub fn substream_id(&self) -> u8 {
+        self.substream_id
+    }
+    /// Whether the frame belongs to the first independent substream
+    pub fn is_independent(&self) -> bool {
+        self.stream_type != Some(1) && self.substream_id == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ac3() {
+        // 48 kHz, 192 kbps, bsid 8, 3/2 with LFE
+        let mut frame = vec![0x0B, 0x77, 0x00, 0x00, 0x14, 0x40, 0xE1];
+        frame.resize(768, 0x00);
+        let bytes = frame.repeat(125);
+        let ac3 = Ac3::from_bytes(&bytes);
+        let header = ac3.header();
+        assert!(!ac3.is_eac3());
+        assert_eq!(header.sample_rate(), 48000);
+        assert_eq!(header.bitrate(), Some(192));
+        assert_eq!(header.frame_length(), 768);
+        assert_eq!(header.channels(), 6);
+        assert_eq!(ac3.frames(), 125);
+        assert_eq!(ac3.duration(), Some(Duration::from_secs(4)));
+        assert_eq!(ac3.bitrate(), Some(192000));
+    }
+
+    #[test]
+    fn test_eac3() {
+        // independent, 44.1 kHz, 6 blocks, 2/0, frmsiz 511
+        let mut independent = vec![0x0B, 0x77, 0x01, 0xFF, 0x74, 0x80];
+        independent.resize(1024, 0x00);
+        // dependent substream of the same frame
+        let mut dependent = vec![0x0B, 0x77, 0x41, 0xFF, 0x74, 0x80];
+        dependent.resize(1024, 0x00);
+        let bytes = [independent, dependent].concat().repeat(3);
+        let ac3 = Ac3::from_bytes(&bytes);
+        assert!(ac3.is_eac3());
+        assert_eq!(ac3.header().sample_rate(), 44100);
+        assert_eq!(ac3.header().channels(), 2);
+        assert_eq!(ac3.frames(), 6);
+        assert_eq!(
+            ac3.duration(),
+            Some(Duration::from_secs_f64(3.0 * 1536.0 / 44100.0))
+        );
+    }
+}
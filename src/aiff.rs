@@ -0,0 +1,540 @@
+use crate::id3::ID3;
+use crate::options::ParseOptions;
+use crate::picture::Picture;
+use crate::properties::{average_bitrate, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::ByteReader;
+use std::time::Duration;
+
+/// AIFF / AIFC file (IFF), all fields are big-endian
+///
+/// Structure
+/// ```text
+/// | "FORM" | size | "AIFF" or "AIFC" | chunk * |
+/// |  4 B   | 4 B  |       4 B        |         |
+/// ```
+/// Each chunk is `| id 4 B | size 4 B | data | pad byte if the size is odd |`.
+#[derive(Debug)]
+pub struct Aiff<'a> {
+    is_aifc: bool,
+    common: Option<AiffCommon>,
+    /// Offset of the sample frames in the `SSND` chunk
+    sound_offset: usize,
+    sound_size: usize,
+    name: Option<String>,
+    author: Option<String>,
+    copyright: Option<String>,
+    annotations: Vec<String>,
+    markers: Vec<AiffMarker>,
+    instrument: Option<AiffInstrument>,
+    id3: Option<ID3<'a>>,
+}
+
+impl<'a> Reader<'a> for Aiff<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
+        if !Aiff::is(bytes) {
+            panic!("Invalid aiff audio format.");
+        }
+        let is_aifc = bytes[8..12] == *b"AIFC";
+        let mut aiff = Self {
+            is_aifc,
+            common: None,
+            sound_offset: 0,
+            sound_size: 0,
+            name: None,
+            author: None,
+            copyright: None,
+            annotations: Vec::new(),
+            markers: Vec::new(),
+            instrument: None,
+            id3: None,
+        };
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id: [u8; 4] = bytes[offset..offset + 4].try_into().unwrap();
+            let size =
+                u32::from_be_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            let start = offset + 8;
+            // the last chunk may be truncated
+            let data = &bytes[start..start.saturating_add(size).min(bytes.len())];
+            let text = || {
+                String::from_utf8_lossy(data)
+                    .trim_end_matches('\0')
+                    .to_string()
+            };
+            match &id {
+                b"COMM" if data.len() >= 18 => aiff.common = Some(AiffCommon::new(data, is_aifc)),
+                b"SSND" if data.len() >= 8 => {
+                    // offset to the first sample frame and block size
+                    let data_offset = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+                    aiff.sound_offset = (start + 8 + data_offset).min(start + data.len());
+                    aiff.sound_size = (start + data.len()).saturating_sub(aiff.sound_offset);
+                }
+                b"NAME" => aiff.name = Some(text()),
+                b"AUTH" => aiff.author = Some(text()),
+                b"(c) " => aiff.copyright = Some(text()),
+                b"ANNO" => aiff.annotations.push(text()),
+                b"MARK" if data.len() >= 2 => aiff.markers = AiffMarker::parse(data),
+                b"INST" if data.len() >= 20 => aiff.instrument = Some(AiffInstrument::new(data)),
+                b"ID3 " | b"id3 " if data.len() >= 10 && ID3::is(data) => {
+                    aiff.id3 = Some(ID3::from_bytes_with_options(data, options))
+                }
+                _ => (),
+            }
+            // chunks are word aligned
+            offset = start.saturating_add(size).saturating_add(size & 1);
+        }
+        aiff
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes.len() >= 12 && bytes[0..4] == *b"FORM" && matches!(&bytes[8..12], b"AIFF" | b"AIFC")
+    }
+}
+
+impl<'a> Aiff<'a> {
+    pub fn is_aifc(&self) -> bool {
+        self.is_aifc
+    }
+    pub fn common(&self) -> Option<&AiffCommon> {
+        self.common.as_ref()
+    }
+    /// Offset of the sample frames
+    pub fn sound_offset(&self) -> usize {
+        self.sound_offset
+    }
+    /// Size of the sample frames
+    pub fn sound_size(&self) -> usize {
+        self.sound_size
+    }
+    /// `NAME` chunk
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// `AUTH` chunk
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+    /// `(c) ` chunk
+    pub fn copyright(&self) -> Option<&str> {
+        self.copyright.as_deref()
+    }
+    /// `ANNO` chunks
+    pub fn annotations(&self) -> &[String] {
+        &self.annotations
+    }
+    pub fn markers(&self) -> &[AiffMarker] {
+        &self.markers
+    }
+    pub fn instrument(&self) -> Option<&AiffInstrument> {
+        self.instrument.as_ref()
+    }
+    /// Tag of the `ID3 ` chunk
+    pub fn id3(&self) -> Option<&ID3<'a>> {
+        self.id3.as_ref()
+    }
+    /// Pictures of the ID3 tag
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.id3.as_ref().map(|it| it.pictures()).unwrap_or_default()
+    }
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        self.id3.as_ref().and_then(|it| it.front_cover())
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        self.common.as_ref()?.duration()
+    }
+    /// None if the `COMM` chunk is missing
+    pub fn properties(&self) -> Option<AudioProperties> {
+        let common = self.common.as_ref()?;
+        let (codec, lossless) = match common.compression_type() {
+            None | Some("NONE" | "sowt" | "twos" | "raw " | "in24" | "in32") => ("PCM", true),
+            // floating point samples
+            Some("fl32" | "FL32" | "fl64") => ("PCM", true),
+            Some("ulaw" | "ULAW") => ("µ-law", false),
+            Some("alaw" | "ALAW") => ("A-law", false),
+            Some("ima4") => ("IMA ADPCM", false),
+            Some("MAC3") => ("MACE 3:1", false),
+            Some("MAC6") => ("MACE 6:1", false),
+            Some(compression_type) => (compression_type, false),
+        };
+        Some(
+            AudioProperties::new(Container::Aiff, codec)
+                .with_duration(self.duration())
+                .with_sample_rate(common.sample_rate as u32)
+                .with_channels(common.channels)
+                .with_bits_per_sample(lossless.then_some(common.sample_size))
+                .with_bitrate(average_bitrate(self.sound_size as u64, self.duration()))
+                .with_lossless(lossless),
+        )
+    }
+}
+
+/// Converts an 80-bit IEEE 754 extended precision float
+///
+/// ```text
+/// | sign | exponent, bias 16383 | mantissa with explicit integer bit |
+/// | 1 b  |        15 b          |               64 b                 |
+/// ```
+pub(crate) fn extended_to_f64(bytes: &[u8]) -> f64 {
+    let sign_exponent = u16::from_be_bytes([bytes[0], bytes[1]]);
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap());
+    if mantissa == 0 {
+        return 0.0;
+    }
+    let exponent = (sign_exponent & 0x7fff) as i32 - 16383 - 63;
+    let value = mantissa as f64 * 2f64.powi(exponent);
+    if sign_exponent & 0x8000 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+/// `COMM` chunk
+///
+/// Structure
+/// ```text
+/// | channels | sample frames | sample size | sample rate (80-bit float) |
+/// |   2 B    |      4 B      |     2 B     |           10 B             |
+/// | compression type | compression name (pascal string) |
+/// |       4 B        |         nn B, AIFC only          |
+/// ```
+#[derive(Debug)]
+pub struct AiffCommon {
+    channels: u16,
+    sample_frames: u32,
+    sample_size: u16,
+    sample_rate: f64,
+    compression_type: Option<String>,
+    compression_name: Option<String>,
+}
+
+impl AiffCommon {
+    pub(crate) fn new(bytes: &[u8], is_aifc: bool) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        let channels = reader.read_next_u16(true);
+        let sample_frames = reader.read_next_u32(true);
+        let sample_size = reader.read_next_u16(true);
+        let sample_rate = extended_to_f64(reader.read(10));
+        let (compression_type, compression_name) = if is_aifc && bytes.len() >= 22 {
+            let compression_type = String::from_utf8_lossy(reader.read(4)).into_owned();
+            (
+                Some(compression_type),
+                read_pascal_string(&bytes[22..]).map(|it| it.0),
+            )
+        } else {
+            (None, None)
+        };
+        Self {
+            channels,
+            sample_frames,
+            sample_size,
+            sample_rate,
+            compression_type,
+            compression_name,
+        }
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    pub fn sample_frames(&self) -> u32 {
+        self.sample_frames
+    }
+    /// Bits per sample
+    pub fn sample_size(&self) -> u16 {
+        self.sample_size
+    }
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+    /// AIFC compression type, e.g. `NONE`, `sowt` (little-endian PCM), `fl32`, `ulaw`
+    pub fn compression_type(&self) -> Option<&str> {
+        self.compression_type.as_deref()
+    }
+    /// AIFC human readable compression name, e.g. `not compressed`
+    pub fn compression_name(&self) -> Option<&str> {
+        self.compression_name.as_deref()
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        if self.sample_rate <= 0.0 {
+            return None;
+        }
+        Duration::try_from_secs_f64(self.sample_frames as f64 / self.sample_rate).ok()
+    }
+}
+
+/// Reads a pascal string (count byte and text, padded to an even total), returns the string and
+/// the padded length
+fn read_pascal_string(bytes: &[u8]) -> Option<(String, usize)> {
+    let len = *bytes.first()? as usize;
+    let text = bytes.get(1..1 + len)?;
+    Some((
+        String::from_utf8_lossy(text).into_owned(),
+        (1 + len + 1) & !1,
+    ))
+}
+
+/// Marker of the `MARK` chunk
+///
+/// Structure
+/// ```text
+/// | marker count | marker * (id 2 B | position 4 B | name pascal string) |
+/// |     2 B      |                                                         |
+/// ```
+#[derive(Debug)]
+pub struct AiffMarker {
+    id: i16,
+    /// Sample frame of the marker
+    position: u32,
+    name: String,
+}
+
+impl AiffMarker {
+    pub(crate) fn parse(bytes: &[u8]) -> Vec<AiffMarker> {
+        let count = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let mut markers = Vec::with_capacity(count as usize);
+        let mut offset = 2;
+        for _ in 0..count {
+            if offset + 6 > bytes.len() {
+                break;
+            }
+            let id = i16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
+            let position = u32::from_be_bytes(bytes[offset + 2..offset + 6].try_into().unwrap());
+            let Some((name, len)) = read_pascal_string(&bytes[offset + 6..]) else {
+                break;
+            };
+            markers.push(AiffMarker { id, position, name });
+            offset += 6 + len;
+        }
+        markers
+    }
+    pub fn id(&self) -> i16 {
+        self.id
+    }
+    pub fn position(&self) -> u32 {
+        self.position
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `INST` chunk
+///
+/// Structure
+/// ```text
+/// | base note | detune | low note | high note | low velocity | high velocity | gain |
+/// |    1 B    |  1 B   |   1 B    |    1 B    |     1 B      |      1 B      | 2 B  |
+/// | sustain loop | release loop |
+/// |     6 B      |     6 B      |
+/// ```
+#[derive(Debug)]
+pub struct AiffInstrument {
+    base_note: u8,
+    detune: i8,
+    low_note: u8,
+    high_note: u8,
+    low_velocity: u8,
+    high_velocity: u8,
+    gain: i16,
+    sustain_loop: AiffLoop,
+    release_loop: AiffLoop,
+}
+
+impl AiffInstrument {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        Self {
+            base_note: reader.read_next_u8(),
+            detune: reader.read_next_u8() as i8,
+            low_note: reader.read_next_u8(),
+            high_note: reader.read_next_u8(),
+            low_velocity: reader.read_next_u8(),
+            high_velocity: reader.read_next_u8(),
+            gain: reader.read_next_u16(true) as i16,
+            sustain_loop: AiffLoop::new(&mut reader),
+            release_loop: AiffLoop::new(&mut reader),
+        }
+    }
+    /// MIDI note number
+    pub fn base_note(&self) -> u8 {
+        self.base_note
+    }
+    /// Cents, -50 to 50
+    pub fn detune(&self) -> i8 {
+        self.detune
+    }
+    pub fn low_note(&self) -> u8 {
+        self.low_note
+    }
+    pub fn high_note(&self) -> u8 {
+        self.high_note
+    }
+    pub fn low_velocity(&self) -> u8 {
+        self.low_velocity
+    }
+    pub fn high_velocity(&self) -> u8 {
+        self.high_velocity
+    }
+    /// Gain in dB
+    pub fn gain(&self) -> i16 {
+        self.gain
+    }
+    pub fn sustain_loop(&self) -> &AiffLoop {
+        &self.sustain_loop
+    }
+    pub fn release_loop(&self) -> &AiffLoop {
+        &self.release_loop
+    }
+}
+
+/// Loop of the `INST` chunk, the begin and end refer to marker ids
+///
+/// Structure
+/// ```text
+/// | play mode | begin marker | end marker |
+/// |    2 B    |     2 B      |    2 B     |
+/// ```
+#[derive(Debug)]
+pub struct AiffLoop {
+    /// 0 no looping, 1 forward, 2 forward and backward
+    play_mode: i16,
+    begin_marker: i16,
+    end_marker: i16,
+}
+
+impl AiffLoop {
+    pub(crate) fn new(reader: &mut ByteReader) -> Self {
+        Self {
+            play_mode: reader.read_next_u16(true) as i16,
+            begin_marker: reader.read_next_u16(true) as i16,
+            end_marker: reader.read_next_u16(true) as i16,
+        }
+    }
+    /// 0 no looping, 1 forward, 2 forward and backward
+    pub fn play_mode(&self) -> i16 {
+        self.play_mode
+    }
+    pub fn begin_marker(&self) -> i16 {
+        self.begin_marker
+    }
+    pub fn end_marker(&self) -> i16 {
+        self.end_marker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((data.len() as u32).to_be_bytes());
+        bytes.extend(data);
+        if data.len() % 2 == 1 {
+            bytes.push(0x00);
+        }
+        bytes
+    }
+
+    fn form(form_type: &[u8; 4], chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut bytes = b"FORM".to_vec();
+        bytes.extend(((body.len() + 4) as u32).to_be_bytes());
+        bytes.extend(form_type);
+        bytes.extend(body);
+        bytes
+    }
+
+    #[test]
+    fn test_extended_float() {
+        // 44100 and 48000
+        assert_eq!(
+            extended_to_f64(&[0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]),
+            44100.0
+        );
+        assert_eq!(
+            extended_to_f64(&[0x40, 0x0E, 0xBB, 0x80, 0, 0, 0, 0, 0, 0]),
+            48000.0
+        );
+    }
+
+    #[test]
+    fn test_tiny_sample_rate() {
+        let mut comm = 2u16.to_be_bytes().to_vec();
+        comm.extend(88200u32.to_be_bytes());
+        comm.extend(16u16.to_be_bytes());
+        // 2^-900
+        comm.extend([0x3C, 0x7B, 0x80, 0, 0, 0, 0, 0, 0, 0]);
+        let bytes = form(b"AIFF", &[chunk(b"COMM", &comm)]);
+        let aiff = Aiff::from_bytes(&bytes);
+        assert!(aiff.common().unwrap().sample_rate() > 0.0);
+        assert_eq!(aiff.duration(), None);
+        assert_eq!(aiff.properties().unwrap().duration(), None);
+    }
+
+    #[test]
+    fn test_aifc() {
+        let mut comm = 2u16.to_be_bytes().to_vec();
+        comm.extend(88200u32.to_be_bytes());
+        comm.extend(16u16.to_be_bytes());
+        comm.extend([0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+        comm.extend(b"sowt");
+        comm.push(14);
+        comm.extend(b"not compressed");
+        comm.push(0x00);
+        let mut mark = 2u16.to_be_bytes().to_vec();
+        mark.extend(1u16.to_be_bytes());
+        mark.extend(0u32.to_be_bytes());
+        mark.extend(b"\x05start");
+        mark.extend(2u16.to_be_bytes());
+        mark.extend(44100u32.to_be_bytes());
+        mark.extend(b"\x03end\x00");
+        let mut inst = vec![60, 0xFB, 0, 127, 1, 127];
+        inst.extend(0u16.to_be_bytes());
+        inst.extend([0x00, 0x01, 0x00, 0x01, 0x00, 0x02]);
+        inst.extend([0x00; 6]);
+        let mut ssnd = vec![0x00; 8];
+        ssnd.extend(vec![0x00; 64]);
+        let mut id3 = b"ID3\x03\x00\x00\x00\x00\x00\x10".to_vec();
+        id3.extend(b"TALB\x00\x00\x00\x06\x00\x00\x03Album");
+        let bytes = form(
+            b"AIFC",
+            &[
+                chunk(b"FVER", &0xA2805140u32.to_be_bytes()),
+                chunk(b"COMM", &comm),
+                chunk(b"NAME", b"Title"),
+                chunk(b"AUTH", b"Author"),
+                chunk(b"ANNO", b"first"),
+                chunk(b"ANNO", b"second"),
+                chunk(b"MARK", &mark),
+                chunk(b"INST", &inst),
+                chunk(b"ID3 ", &id3),
+                chunk(b"SSND", &ssnd),
+            ],
+        );
+        let aiff = Aiff::from_bytes(&bytes);
+        assert!(aiff.is_aifc());
+        let common = aiff.common().unwrap();
+        assert_eq!(common.channels(), 2);
+        assert_eq!(common.sample_rate(), 44100.0);
+        assert_eq!(common.compression_type(), Some("sowt"));
+        assert_eq!(common.compression_name(), Some("not compressed"));
+        assert_eq!(aiff.duration(), Some(Duration::from_secs(2)));
+        assert_eq!(aiff.name(), Some("Title"));
+        assert_eq!(aiff.author(), Some("Author"));
+        assert_eq!(aiff.annotations(), ["first", "second"]);
+        assert_eq!(aiff.markers().len(), 2);
+        assert_eq!(aiff.markers()[1].name(), "end");
+        assert_eq!(aiff.markers()[1].position(), 44100);
+        let instrument = aiff.instrument().unwrap();
+        assert_eq!(instrument.base_note(), 60);
+        assert_eq!(instrument.detune(), -5);
+        assert_eq!(instrument.sustain_loop().play_mode(), 1);
+        assert_eq!(instrument.sustain_loop().end_marker(), 2);
+        assert_eq!(aiff.sound_size(), 64);
+        assert!(aiff.id3().is_some());
+    }
+}
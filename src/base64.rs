@@ -75,6 +75,11 @@ pub fn decode_base64(input: &str) -> Vec<u8> {
     }
     bytes
 }
+/// Decodes the groups of 4 characters covering the first `len` bytes, the whole input if shorter
+pub fn decode_base64_prefix(input: &str, len: usize) -> Vec<u8> {
+    let end = len.div_ceil(3).saturating_mul(4);
+    decode_base64(input.get(..end).unwrap_or(input))
+}
 
 #[cfg(test)]
 mod tests {
@@ -84,6 +89,8 @@ mod tests {
     fn it_works() {
         assert_eq!(encode_base64("Hello, World!".as_bytes()), "SGVsbG8sIFdvcmxkIQ==");
         assert_eq!(String::from_utf8_lossy(&decode_base64("SGVsbG8sIFdvcmxkIQ==")), "Hello, World!");
+        assert_eq!(decode_base64_prefix("SGVsbG8sIFdvcmxkIQ==", 4), b"Hello,");
+        assert_eq!(decode_base64_prefix("SGVsbG8sIFdvcmxkIQ==", 100), b"Hello, World!");
         assert_eq!(String::from_utf8_lossy(&decode_base64(&encode_base64("你好，世界！".as_bytes()))), "你好，世界！")
     }
 }
\ No newline at end of file
@@ -0,0 +1,405 @@
+use crate::properties::{average_bitrate, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::ByteReader;
+use std::time::Duration;
+
+/// Apple Core Audio Format file, all fields are big-endian
+///
+/// Structure
+/// ```text
+/// | "caff" | version (1) | flags | chunk * |
+/// |  4 B   |     2 B     |  2 B  |         |
+/// ```
+/// Each chunk is `| type 4 B | size 8 B | data |`, the size of the last `data` chunk may be -1
+/// when it extends to the end of the file.
+#[derive(Debug)]
+pub struct Caf {
+    version: u16,
+    description: Option<CafDescription>,
+    info: Vec<(String, String)>,
+    packet_table: Option<CafPacketTable>,
+    magic_cookie: Option<Vec<u8>>,
+    /// Offset of the audio data, after the edit count
+    data_offset: usize,
+    data_size: usize,
+}
+
+impl Reader<'_> for Caf {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        if !Caf::is(bytes) {
+            panic!("Invalid caf audio format.");
+        }
+        let mut caf = Self {
+            version: u16::from_be_bytes([bytes[4], bytes[5]]),
+            description: None,
+            info: Vec::new(),
+            packet_table: None,
+            magic_cookie: None,
+            data_offset: 0,
+            data_size: 0,
+        };
+        let mut offset = 8;
+        while offset + 12 <= bytes.len() {
+            let mut reader = ByteReader::with_offset(bytes, offset);
+            let id: [u8; 4] = reader.read(4).try_into().unwrap();
+            let size = reader.read_next_i64(true);
+            let start = offset + 12;
+            let end = if size < 0 {
+                bytes.len()
+            } else {
+                start.saturating_add(size as usize).min(bytes.len())
+            };
+            let data = &bytes[start..end];
+            match &id {
+                b"desc" if data.len() >= 32 => caf.description = Some(CafDescription::new(data)),
+                b"info" if data.len() >= 4 => caf.info = Self::parse_info(data),
+                b"pakt" if data.len() >= 24 => {
+                    let mut reader = ByteReader::new(data);
+                    caf.packet_table = Some(CafPacketTable {
+                        packets: reader.read_next_i64(true),
+                        valid_frames: reader.read_next_i64(true),
+                        priming_frames: reader.read_next_i32(true),
+                        remainder_frames: reader.read_next_i32(true),
+                    });
+                }
+                b"kuki" => caf.magic_cookie = Some(data.to_vec()),
+                // edit count 4 B
+                b"data" if data.len() >= 4 => {
+                    caf.data_offset = start + 4;
+                    caf.data_size = data.len() - 4;
+                }
+                _ => (),
+            }
+            if size < 0 {
+                break;
+            }
+            offset = end;
+        }
+        caf
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes.len() >= 8 && bytes[0..4] == *b"caff"
+    }
+}
+
+impl Caf {
+    /// Number of entries 4 B, then the key and value pairs as null-terminated strings
+    fn parse_info(bytes: &[u8]) -> Vec<(String, String)> {
+        let count = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+        let mut strings = bytes[4..]
+            .split(|it| *it == 0x00)
+            .map(|it| String::from_utf8_lossy(it).into_owned());
+        let mut info = Vec::new();
+        while info.len() < count {
+            match (strings.next(), strings.next()) {
+                (Some(key), Some(value)) => info.push((key, value)),
+                _ => break,
+            }
+        }
+        info
+    }
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+    pub fn description(&self) -> Option<&CafDescription> {
+        self.description.as_ref()
+    }
+    /// `info` chunk entries, e.g. `title`, `artist`, `album`, `recorded date`
+    pub fn info(&self) -> &[(String, String)] {
+        &self.info
+    }
+    pub fn info_text(&self, key: &str) -> Option<&str> {
+        self.info
+            .iter()
+            .find(|(it, _)| it.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+    pub fn packet_table(&self) -> Option<&CafPacketTable> {
+        self.packet_table.as_ref()
+    }
+    /// Codec specific configuration, e.g. the esds of AAC or the ALAC specific config
+    pub fn magic_cookie(&self) -> Option<&[u8]> {
+        self.magic_cookie.as_deref()
+    }
+    pub fn has_magic_cookie(&self) -> bool {
+        self.magic_cookie.is_some()
+    }
+    pub fn data_offset(&self) -> usize {
+        self.data_offset
+    }
+    pub fn data_size(&self) -> usize {
+        self.data_size
+    }
+    /// Frames per channel, exact when the packet table is present, otherwise computed from the
+    /// data size of constant bitrate formats
+    pub fn frames(&self) -> Option<u64> {
+        if let Some(table) = &self.packet_table {
+            return u64::try_from(table.valid_frames).ok();
+        }
+        let description = self.description.as_ref()?;
+        if description.bytes_per_packet == 0 || description.frames_per_packet == 0 {
+            return None;
+        }
+        let packets = self.data_size as u64 / description.bytes_per_packet as u64;
+        Some(packets * description.frames_per_packet as u64)
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        let sample_rate = self.description.as_ref()?.sample_rate;
+        if !sample_rate.is_finite() || sample_rate <= 0.0 {
+            return None;
+        }
+        Duration::try_from_secs_f64(self.frames()? as f64 / sample_rate).ok()
+    }
+    pub fn codec(&self) -> Option<&'static str> {
+        self.description.as_ref().map(|it| it.codec())
+    }
+    /// None if the `desc` chunk is missing
+    pub fn properties(&self) -> Option<AudioProperties> {
+        let description = self.description.as_ref()?;
+        Some(
+            AudioProperties::new(Container::Caf, description.codec())
+                .with_duration(self.duration())
+                .with_sample_rate(description.sample_rate as u32)
+                .with_channels(description.channels as u16)
+                .with_bits_per_sample(description.bits_per_sample())
+                .with_bitrate(average_bitrate(self.data_size as u64, self.duration()))
+                .with_lossless(description.is_lossless()),
+        )
+    }
+}
+
+/// `desc` chunk
+///
+/// Structure
+/// ```text
+/// | sample rate (f64) | format id | format flags | bytes per packet | frames per packet |
+/// |        8 B        |    4 B    |     4 B      |       4 B        |        4 B        |
+/// | channels per frame | bits per channel |
+/// |        4 B         |       4 B        |
+/// ```
+/// The bytes or frames per packet is 0 when they vary, the packet table describes them.
+#[derive(Debug)]
+pub struct CafDescription {
+    sample_rate: f64,
+    format_id: String,
+    format_flags: u32,
+    bytes_per_packet: u32,
+    frames_per_packet: u32,
+    channels: u32,
+    bits_per_channel: u32,
+}
+
+impl CafDescription {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        Self {
+            sample_rate: f64::from_bits(reader.read_next_u64(true)),
+            format_id: String::from_utf8_lossy(reader.read(4)).into_owned(),
+            format_flags: reader.read_next_u32(true),
+            bytes_per_packet: reader.read_next_u32(true),
+            frames_per_packet: reader.read_next_u32(true),
+            channels: reader.read_next_u32(true),
+            bits_per_channel: reader.read_next_u32(true),
+        }
+    }
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+    /// e.g. `lpcm`, `aac `, `alac`, `ima4`, `ulaw`, `opus`
+    pub fn format_id(&self) -> &str {
+        &self.format_id
+    }
+    /// For `lpcm`, 0x01 float and 0x02 little-endian, for `alac` the source bit depth
+    pub fn format_flags(&self) -> u32 {
+        self.format_flags
+    }
+    pub fn bytes_per_packet(&self) -> u32 {
+        self.bytes_per_packet
+    }
+    pub fn frames_per_packet(&self) -> u32 {
+        self.frames_per_packet
+    }
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+    pub fn bits_per_channel(&self) -> u32 {
+        self.bits_per_channel
+    }
+    /// Bits per sample of the decoded audio, None for lossy codecs
+    pub fn bits_per_sample(&self) -> Option<u16> {
+        match self.format_id.as_str() {
+            "lpcm" => Some(self.bits_per_channel as u16),
+            // source bit depth flags
+            "alac" => match self.format_flags {
+                1 => Some(16),
+                2 => Some(20),
+                3 => Some(24),
+                4 => Some(32),
+                _ => (self.bits_per_channel > 0).then_some(self.bits_per_channel as u16),
+            },
+            "flac" => (self.bits_per_channel > 0).then_some(self.bits_per_channel as u16),
+            _ => None,
+        }
+    }
+    pub fn is_float(&self) -> bool {
+        self.format_id == "lpcm" && self.format_flags & 0x01 != 0
+    }
+    pub fn is_little_endian(&self) -> bool {
+        self.format_id == "lpcm" && self.format_flags & 0x02 != 0
+    }
+    pub fn is_lossless(&self) -> bool {
+        matches!(self.format_id.as_str(), "lpcm" | "alac" | "flac")
+    }
+    pub fn codec(&self) -> &'static str {
+        match self.format_id.as_str() {
+            "lpcm" => "PCM",
+            "aac " => "AAC",
+            "aach" => "HE-AAC",
+            "aacp" => "HE-AACv2",
+            "aacl" => "AAC LD",
+            "aace" => "AAC ELD",
+            "alac" => "ALAC",
+            "flac" => "FLAC",
+            "opus" => "Opus",
+            "ima4" => "IMA ADPCM",
+            "ulaw" => "µ-law",
+            "alaw" => "A-law",
+            ".mp1" => "MP1",
+            ".mp2" => "MP2",
+            ".mp3" => "MP3",
+            "ac-3" => "AC-3",
+            "ec-3" => "E-AC-3",
+            "MAC3" => "MACE 3:1",
+            "MAC6" => "MACE 6:1",
+            "Qclp" => "QCELP",
+            "QDMC" => "QDesign",
+            "QDM2" => "QDesign 2",
+            "agsm" => "GSM",
+            "ilbc" => "iLBC",
+            "samr" => "AMR",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// `pakt` chunk header, the packet sizes follow as variable length integers
+///
+/// Structure
+/// ```text
+/// | packets | valid frames | priming frames | remainder frames | packet descriptions |
+/// |   8 B   |     8 B      |      4 B       |       4 B        |                     |
+/// ```
+#[derive(Debug)]
+pub struct CafPacketTable {
+    packets: i64,
+    valid_frames: i64,
+    priming_frames: i32,
+    remainder_frames: i32,
+}
+
+impl CafPacketTable {
+    pub fn packets(&self) -> i64 {
+        self.packets
+    }
+    /// Frames without the priming and remainder frames
+    pub fn valid_frames(&self) -> i64 {
+        self.valid_frames
+    }
+    /// Encoder delay
+    pub fn priming_frames(&self) -> i32 {
+        self.priming_frames
+    }
+    /// Padding of the last packet
+    pub fn remainder_frames(&self) -> i32 {
+        self.remainder_frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((data.len() as i64).to_be_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn desc(format_id: &[u8; 4], values: [u32; 5]) -> Vec<u8> {
+        let mut bytes = 44100f64.to_be_bytes().to_vec();
+        bytes.extend(format_id);
+        for value in values {
+            bytes.extend(value.to_be_bytes());
+        }
+        chunk(b"desc", &bytes)
+    }
+
+    #[test]
+    fn test_lpcm() {
+        let mut info = 2u32.to_be_bytes().to_vec();
+        info.extend(b"title\0Memo\0artist\0Someone\0");
+        let mut bytes = b"caff\x00\x01\x00\x00".to_vec();
+        bytes.extend(desc(b"lpcm", [0x02, 4, 1, 2, 16]));
+        bytes.extend(chunk(b"info", &info));
+        // data extends to the end of the file
+        bytes.extend(b"data");
+        bytes.extend((-1i64).to_be_bytes());
+        bytes.extend([0x00; 4]);
+        bytes.extend(vec![0x00; 44100 * 4]);
+        let caf = Caf::from_bytes(&bytes);
+        let description = caf.description().unwrap();
+        assert!(description.is_little_endian());
+        assert_eq!(caf.codec(), Some("PCM"));
+        assert_eq!(caf.info_text("title"), Some("Memo"));
+        assert_eq!(caf.info_text("artist"), Some("Someone"));
+        assert_eq!(caf.data_size(), 44100 * 4);
+        assert_eq!(
+            caf.properties(),
+            Some(
+                AudioProperties::new(Container::Caf, "PCM")
+                    .with_duration(Some(Duration::from_secs(1)))
+                    .with_sample_rate(44100)
+                    .with_channels(2)
+                    .with_bits_per_sample(Some(16))
+                    .with_bitrate(Some(1411))
+                    .with_lossless(true)
+            )
+        );
+    }
+
+    #[test]
+    fn test_aac() {
+        let mut pakt = 216i64.to_be_bytes().to_vec();
+        pakt.extend(220500i64.to_be_bytes());
+        pakt.extend(2112i32.to_be_bytes());
+        pakt.extend(572i32.to_be_bytes());
+        let mut bytes = b"caff\x00\x01\x00\x00".to_vec();
+        bytes.extend(desc(b"aac ", [0, 0, 1024, 2, 0]));
+        bytes.extend(chunk(b"kuki", &[0x03, 0x80, 0x80, 0x80]));
+        bytes.extend(chunk(b"pakt", &pakt));
+        bytes.extend(chunk(b"data", &[0x00; 64]));
+        let caf = Caf::from_bytes(&bytes);
+        assert_eq!(caf.codec(), Some("AAC"));
+        assert!(caf.has_magic_cookie());
+        assert_eq!(caf.packet_table().unwrap().priming_frames(), 2112);
+        assert_eq!(caf.frames(), Some(220500));
+        assert_eq!(caf.duration(), Some(Duration::from_secs(5)));
+        let properties = caf.properties().unwrap();
+        assert_eq!(properties.bits_per_sample(), None);
+        assert!(!properties.is_lossless());
+    }
+
+    #[test]
+    fn test_invalid_sample_rate() {
+        for sample_rate in [f64::NAN, f64::INFINITY, f64::from_bits(1)] {
+            let mut bytes = b"caff\x00\x01\x00\x00".to_vec();
+            bytes.extend(desc(b"lpcm", [0x02, 4, 1, 2, 16]));
+            bytes[20..28].copy_from_slice(&sample_rate.to_be_bytes());
+            bytes.extend(chunk(b"data", &[0x00; 64]));
+            let caf = Caf::from_bytes(&bytes);
+            assert_eq!(caf.duration(), None);
+            assert!(caf.properties().is_some());
+        }
+    }
+}
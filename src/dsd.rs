@@ -0,0 +1,525 @@
+use crate::id3::ID3;
+use crate::options::ParseOptions;
+use crate::picture::Picture;
+use crate::properties::{AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::ByteReader;
+use std::time::Duration;
+
+/// Sony DSF file, all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | "DSD " | size (28) | file size | metadata pointer | "fmt " chunk | "data" chunk | ID3v2 |
+/// |  4 B   |    8 B    |    8 B    |       8 B        |     52 B     |              |       |
+/// ```
+#[derive(Debug)]
+pub struct Dsf<'a> {
+    file_size: u64,
+    /// Offset of the ID3v2 tag, 0 when absent
+    metadata_pointer: u64,
+    format_version: u32,
+    /// 0 DSD raw
+    format_id: u32,
+    channel_type: u32,
+    channels: u32,
+    sample_rate: u32,
+    /// 1 LSB first, 8 MSB first
+    bits_per_sample: u32,
+    /// Samples per channel
+    sample_count: u64,
+    block_size: u32,
+    data_offset: usize,
+    data_size: usize,
+    id3: Option<ID3<'a>>,
+}
+
+impl<'a> Reader<'a> for Dsf<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
+        if !Dsf::is(bytes) {
+            panic!("Invalid dsf audio format.");
+        }
+        let mut reader = ByteReader::with_offset(bytes, 12);
+        let mut dsf = Self {
+            file_size: reader.read_next_u64(false),
+            metadata_pointer: reader.read_next_u64(false),
+            format_version: 0,
+            format_id: 0,
+            channel_type: 0,
+            channels: 0,
+            sample_rate: 0,
+            bits_per_sample: 0,
+            sample_count: 0,
+            block_size: 0,
+            data_offset: 0,
+            data_size: 0,
+            id3: None,
+        };
+        let mut offset = 28;
+        while offset + 12 <= bytes.len() {
+            let mut reader = ByteReader::with_offset(bytes, offset);
+            let id: [u8; 4] = reader.read(4).try_into().unwrap();
+            let size = reader.read_next_u64(false) as usize;
+            if size < 12 {
+                break;
+            }
+            match &id {
+                b"fmt " if offset + 52 <= bytes.len() => {
+                    dsf.format_version = reader.read_next_u32(false);
+                    dsf.format_id = reader.read_next_u32(false);
+                    dsf.channel_type = reader.read_next_u32(false);
+                    dsf.channels = reader.read_next_u32(false);
+                    dsf.sample_rate = reader.read_next_u32(false);
+                    dsf.bits_per_sample = reader.read_next_u32(false);
+                    dsf.sample_count = reader.read_next_u64(false);
+                    dsf.block_size = reader.read_next_u32(false);
+                }
+                b"data" => {
+                    dsf.data_offset = offset + 12;
+                    dsf.data_size = (size - 12).min(bytes.len() - dsf.data_offset);
+                    break;
+                }
+                _ => (),
+            }
+            offset = offset.saturating_add(size);
+        }
+        let pointer = dsf.metadata_pointer as usize;
+        if pointer != 0 && pointer < bytes.len() {
+            let tag = &bytes[pointer..];
+            if tag.len() >= 10 && ID3::is(tag) {
+                dsf.id3 = Some(ID3::from_bytes_with_options(tag, options));
+            }
+        }
+        dsf
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes.len() >= 28 && bytes[0..4] == *b"DSD " && bytes[4] == 28
+    }
+}
+
+impl<'a> Dsf<'a> {
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+    pub fn metadata_pointer(&self) -> u64 {
+        self.metadata_pointer
+    }
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+    /// 0 DSD raw
+    pub fn format_id(&self) -> u32 {
+        self.format_id
+    }
+    /// 1 mono, 2 stereo, 3 3 channels, 4 quad, 5 4 channels, 6 5 channels, 7 5.1 channels
+    pub fn channel_type(&self) -> u32 {
+        self.channel_type
+    }
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+    /// e.g. 2822400 (DSD64), 5644800 (DSD128)
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// 1 LSB first, 8 MSB first
+    pub fn bits_per_sample(&self) -> u32 {
+        self.bits_per_sample
+    }
+    /// Samples per channel
+    pub fn sample_count(&self) -> u64 {
+        self.sample_count
+    }
+    /// Bytes per channel of a block
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+    pub fn data_offset(&self) -> usize {
+        self.data_offset
+    }
+    pub fn data_size(&self) -> usize {
+        self.data_size
+    }
+    /// Tag at the metadata pointer
+    pub fn id3(&self) -> Option<&ID3<'a>> {
+        self.id3.as_ref()
+    }
+    /// Pictures of the ID3 tag
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.id3.as_ref().map(|it| it.pictures()).unwrap_or_default()
+    }
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        self.id3.as_ref().and_then(|it| it.front_cover())
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        if self.sample_rate == 0 {
+            return None;
+        }
+        Duration::try_from_secs_f64(self.sample_count as f64 / self.sample_rate as f64).ok()
+    }
+    pub fn properties(&self) -> AudioProperties {
+        let bitrate = self.sample_rate as u64 * self.channels as u64 / 1000;
+        AudioProperties::new(Container::Dsf, "DSD")
+            .with_duration(self.duration())
+            .with_sample_rate(self.sample_rate)
+            .with_channels(self.channels as u16)
+            .with_bits_per_sample(Some(1))
+            .with_bitrate(u32::try_from(bitrate).ok())
+            .with_lossless(true)
+    }
+}
+
+/// Philips DSDIFF file, all fields are big-endian
+///
+/// Structure
+/// ```text
+/// | "FRM8" | size | "DSD " | chunk * |
+/// |  4 B   | 8 B  |  4 B   |         |
+/// ```
+/// Each chunk is `| id 4 B | size 8 B | data | pad byte if the size is odd |`, the `PROP`, `DST `
+/// and `DIIN` chunks contain sub chunks.
+#[derive(Debug)]
+pub struct Dff<'a> {
+    version: u32,
+    sample_rate: u32,
+    /// Channel ids, e.g. `SLFT`, `SRGT`, `C   `, `LFE `
+    channel_ids: Vec<String>,
+    /// `DSD ` uncompressed or `DST ` Direct Stream Transfer
+    compression_type: String,
+    compression_name: String,
+    /// Size of the `DSD ` sound data
+    sound_size: usize,
+    /// Frame count and frames per second of the `DST ` sound data
+    dst_frames: Option<(u32, u16)>,
+    artist: Option<String>,
+    title: Option<String>,
+    id3: Option<ID3<'a>>,
+}
+
+impl<'a> Reader<'a> for Dff<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
+        if !Dff::is(bytes) {
+            panic!("Invalid dff audio format.");
+        }
+        let mut dff = Self {
+            version: 0,
+            sample_rate: 0,
+            channel_ids: Vec::new(),
+            compression_type: String::from("DSD "),
+            compression_name: String::new(),
+            sound_size: 0,
+            dst_frames: None,
+            artist: None,
+            title: None,
+            id3: None,
+        };
+        for (id, data) in DffChunks::new(&bytes[16..]) {
+            match &id {
+                b"FVER" if data.len() >= 4 => {
+                    dff.version = u32::from_be_bytes(data[0..4].try_into().unwrap())
+                }
+                b"PROP" if data.len() >= 4 && data[0..4] == *b"SND " => dff.parse_prop(&data[4..]),
+                b"DSD " => dff.sound_size = data.len(),
+                b"DST " => {
+                    if let Some((_, frte)) = DffChunks::new(data).find(|it| it.0 == *b"FRTE") {
+                        if frte.len() >= 6 {
+                            let mut reader = ByteReader::new(frte);
+                            dff.dst_frames =
+                                Some((reader.read_next_u32(true), reader.read_next_u16(true)));
+                        }
+                    }
+                }
+                b"DIIN" => dff.parse_diin(data),
+                b"ID3 " if data.len() >= 10 && ID3::is(data) => {
+                    dff.id3 = Some(ID3::from_bytes_with_options(data, options))
+                }
+                _ => (),
+            }
+        }
+        dff
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes.len() >= 16 && bytes[0..4] == *b"FRM8" && bytes[12..16] == *b"DSD "
+    }
+}
+
+impl<'a> Dff<'a> {
+    fn parse_prop(&mut self, bytes: &[u8]) {
+        for (id, data) in DffChunks::new(bytes) {
+            match &id {
+                b"FS  " if data.len() >= 4 => {
+                    self.sample_rate = u32::from_be_bytes(data[0..4].try_into().unwrap())
+                }
+                b"CHNL" if data.len() >= 2 => {
+                    let count = u16::from_be_bytes([data[0], data[1]]) as usize;
+                    self.channel_ids = data[2..]
+                        .chunks_exact(4)
+                        .take(count)
+                        .map(|it| String::from_utf8_lossy(it).into_owned())
+                        .collect();
+                }
+                b"CMPR" if data.len() >= 5 => {
+                    self.compression_type = String::from_utf8_lossy(&data[0..4]).into_owned();
+                    let len = data[4] as usize;
+                    self.compression_name =
+                        String::from_utf8_lossy(&data[5..(5 + len).min(data.len())]).into_owned();
+                }
+                _ => (),
+            }
+        }
+    }
+    fn parse_diin(&mut self, bytes: &[u8]) {
+        // count 4 B and text
+        let text = |data: &[u8]| {
+            let len = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+            String::from_utf8_lossy(&data[4..(4 + len).min(data.len())]).into_owned()
+        };
+        for (id, data) in DffChunks::new(bytes) {
+            match &id {
+                b"DIAR" if data.len() >= 4 => self.artist = Some(text(data)),
+                b"DITI" if data.len() >= 4 => self.title = Some(text(data)),
+                _ => (),
+            }
+        }
+    }
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+    /// e.g. 2822400 (DSD64)
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn channels(&self) -> usize {
+        self.channel_ids.len()
+    }
+    /// Channel ids, e.g. `SLFT`, `SRGT`, `C   `, `LFE `
+    pub fn channel_ids(&self) -> &[String] {
+        &self.channel_ids
+    }
+    /// `DSD ` uncompressed or `DST ` Direct Stream Transfer
+    pub fn compression_type(&self) -> &str {
+        &self.compression_type
+    }
+    pub fn is_dst(&self) -> bool {
+        self.compression_type == "DST "
+    }
+    pub fn compression_name(&self) -> &str {
+        &self.compression_name
+    }
+    /// Size of the uncompressed sound data
+    pub fn sound_size(&self) -> usize {
+        self.sound_size
+    }
+    /// Frame count and frames per second of the DST sound data
+    pub fn dst_frames(&self) -> Option<(u32, u16)> {
+        self.dst_frames
+    }
+    /// `DIAR` chunk
+    pub fn artist(&self) -> Option<&str> {
+        self.artist.as_deref()
+    }
+    /// `DITI` chunk
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+    /// Tag of the `ID3 ` chunk
+    pub fn id3(&self) -> Option<&ID3<'a>> {
+        self.id3.as_ref()
+    }
+    /// Pictures of the ID3 tag
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.id3.as_ref().map(|it| it.pictures()).unwrap_or_default()
+    }
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        self.id3.as_ref().and_then(|it| it.front_cover())
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        match self.dst_frames {
+            Some((frames, rate)) if rate > 0 => {
+                Duration::try_from_secs_f64(frames as f64 / rate as f64).ok()
+            }
+            _ if self.sample_rate > 0 && !self.channel_ids.is_empty() => {
+                // 1 bit per sample
+                let samples = self.sound_size as f64 * 8.0 / self.channel_ids.len() as f64;
+                Duration::try_from_secs_f64(samples / self.sample_rate as f64).ok()
+            }
+            _ => None,
+        }
+    }
+    pub fn properties(&self) -> AudioProperties {
+        let (codec, bitrate) = if self.is_dst() {
+            ("DST", None)
+        } else {
+            let bitrate = self.sample_rate as u64 * self.channels() as u64 / 1000;
+            ("DSD", u32::try_from(bitrate).ok())
+        };
+        AudioProperties::new(Container::Dff, codec)
+            .with_duration(self.duration())
+            .with_sample_rate(self.sample_rate)
+            .with_channels(self.channels() as u16)
+            .with_bits_per_sample(Some(1))
+            .with_bitrate(bitrate)
+            .with_lossless(true)
+    }
+}
+
+/// Iterates the DSDIFF chunks laid out one after another, the last chunk may be truncated
+struct DffChunks<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> DffChunks<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for DffChunks<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        if offset + 12 > self.bytes.len() {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(self.bytes, offset);
+        let id: [u8; 4] = reader.read(4).try_into().unwrap();
+        let size = reader.read_next_u64(true) as usize;
+        let start = offset + 12;
+        let end = start.saturating_add(size).min(self.bytes.len());
+        self.offset = end.saturating_add(size & 1);
+        Some((id, &self.bytes[start..end]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id3() -> Vec<u8> {
+        let mut bytes = b"ID3\x03\x00\x00\x00\x00\x00\x10".to_vec();
+        bytes.extend(b"TIT2\x00\x00\x00\x06\x00\x00\x03Title");
+        bytes
+    }
+
+    fn dsf_bytes(channels: u32, sample_rate: u32, sample_count: u64) -> Vec<u8> {
+        let data = vec![0x69; 4096 * 2];
+        let metadata_pointer = 28 + 52 + 12 + data.len();
+        let mut bytes = b"DSD ".to_vec();
+        bytes.extend(28u64.to_le_bytes());
+        bytes.extend(((metadata_pointer + id3().len()) as u64).to_le_bytes());
+        bytes.extend((metadata_pointer as u64).to_le_bytes());
+        bytes.extend(b"fmt ");
+        bytes.extend(52u64.to_le_bytes());
+        for value in [1u32, 0, 2, channels, sample_rate, 1] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(sample_count.to_le_bytes());
+        bytes.extend(4096u32.to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend(((data.len() + 12) as u64).to_le_bytes());
+        bytes.extend(data);
+        bytes.extend(id3());
+        bytes
+    }
+
+    #[test]
+    fn test_dsf() {
+        let bytes = dsf_bytes(2, 2822400, 2822400 * 3);
+        let dsf = Dsf::from_bytes(&bytes);
+        assert_eq!(dsf.channels(), 2);
+        assert_eq!(dsf.channel_type(), 2);
+        assert_eq!(dsf.sample_rate(), 2822400);
+        assert_eq!(dsf.block_size(), 4096);
+        assert_eq!(dsf.data_size(), 8192);
+        assert_eq!(dsf.duration(), Some(Duration::from_secs(3)));
+        assert!(dsf.id3().is_some());
+    }
+
+    #[test]
+    fn test_dsf_overflow() {
+        let bytes = dsf_bytes(u32::MAX, 2822400, u64::MAX);
+        let dsf = Dsf::from_bytes(&bytes);
+        assert_eq!(dsf.properties().bitrate(), None);
+    }
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((data.len() as u64).to_be_bytes());
+        bytes.extend(data);
+        if data.len() % 2 == 1 {
+            bytes.push(0x00);
+        }
+        bytes
+    }
+
+    fn frm8(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut bytes = b"FRM8".to_vec();
+        bytes.extend(((body.len() + 4) as u64).to_be_bytes());
+        bytes.extend(b"DSD ");
+        bytes.extend(body);
+        bytes
+    }
+
+    fn prop(compression: &[u8; 4], name: &[u8]) -> Vec<u8> {
+        let mut cmpr = compression.to_vec();
+        cmpr.push(name.len() as u8);
+        cmpr.extend(name);
+        let mut chnl = 2u16.to_be_bytes().to_vec();
+        chnl.extend(b"SLFTSRGT");
+        let mut data = b"SND ".to_vec();
+        data.extend(chunk(b"FS  ", &2822400u32.to_be_bytes()));
+        data.extend(chunk(b"CHNL", &chnl));
+        data.extend(chunk(b"CMPR", &cmpr));
+        chunk(b"PROP", &data)
+    }
+
+    #[test]
+    fn test_dff() {
+        let mut diar = 6u32.to_be_bytes().to_vec();
+        diar.extend(b"Artist");
+        let mut diti = 5u32.to_be_bytes().to_vec();
+        diti.extend(b"Title");
+        let bytes = frm8(&[
+            chunk(b"FVER", &0x01050000u32.to_be_bytes()),
+            prop(b"DSD ", b"not compressed"),
+            // 2 seconds of 2 channels
+            chunk(b"DSD ", &vec![0x69; 2822400 / 8 * 2 * 2]),
+            chunk(
+                b"DIIN",
+                &[chunk(b"DIAR", &diar), chunk(b"DITI", &diti)].concat(),
+            ),
+            chunk(b"ID3 ", &id3()),
+        ]);
+        let dff = Dff::from_bytes(&bytes);
+        assert_eq!(dff.version(), 0x01050000);
+        assert_eq!(dff.sample_rate(), 2822400);
+        assert_eq!(dff.channel_ids(), ["SLFT", "SRGT"]);
+        assert!(!dff.is_dst());
+        assert_eq!(dff.compression_name(), "not compressed");
+        assert_eq!(dff.duration(), Some(Duration::from_secs(2)));
+        assert_eq!(dff.artist(), Some("Artist"));
+        assert_eq!(dff.title(), Some("Title"));
+        assert!(dff.id3().is_some());
+
+        let mut frte = 300u32.to_be_bytes().to_vec();
+        frte.extend(75u16.to_be_bytes());
+        let bytes = frm8(&[
+            prop(b"DST ", b"DST Encoded"),
+            chunk(b"DST ", &chunk(b"FRTE", &frte)),
+        ]);
+        let dff = Dff::from_bytes(&bytes);
+        assert!(dff.is_dst());
+        assert_eq!(dff.dst_frames(), Some((300, 75)));
+        assert_eq!(dff.duration(), Some(Duration::from_secs(4)));
+    }
+}
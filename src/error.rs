@@ -0,0 +1,42 @@
+use std::fmt::{Display, Formatter};
+
+/// Error found while parsing, returned by the strict parsing mode, see [`ParseOptions::strict`](crate::ParseOptions::strict)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The CRC of an Ogg page does not match its checksum field
+    CorruptedPage {
+        offset: usize,
+        serial_number: u32,
+        sequence_number: u32,
+        checksum: u32,
+        computed_checksum: u32,
+    },
+    /// A header violates a constraint of its specification
+    InvalidHeader {
+        header: &'static str,
+        reason: String,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::CorruptedPage {
+                offset,
+                serial_number,
+                sequence_number,
+                checksum,
+                computed_checksum,
+            } => write!(
+                f,
+                "corrupted ogg page {} of stream {:#010x} at offset {}: checksum {:#010x}, computed {:#010x}",
+                sequence_number, serial_number, offset, checksum, computed_checksum
+            ),
+            Error::InvalidHeader { header, reason } => {
+                write!(f, "invalid {} header: {}", header, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,17 +1,32 @@
+use crate::flac_frame::FlacFrameScan;
+use crate::options::ParseOptions;
+use crate::picture::{front_cover, Picture};
+use crate::properties::{AudioProperties, Container};
 use crate::reader::Reader;
 use crate::utils::{debug_vec, ByteReader};
 use crate::vorbis_comment::VorbisComment;
+use std::borrow::Cow;
 use std::fmt::{Debug, Formatter};
+use std::time::Duration;
 
 const FLAC_SIGNATURE: [u8; 4] = [0x66, 0x4c, 0x61, 0x43];
 
 #[derive(Debug)]
-pub struct Flac {
-    blocks: Vec<FlacParsedBlock>,
+pub struct Flac<'a> {
+    blocks: Vec<FlacParsedBlock<'a>>,
+    /// Size of the audio frames following the metadata blocks
+    audio_size: usize,
+    scan: Option<FlacFrameScan>,
 }
 
-impl Reader for Flac {
-    fn from_bytes(bytes: &[u8]) -> Self {
+impl<'a> Reader<'a> for Flac<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes[0..4] == FLAC_SIGNATURE
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
         if !Flac::is(bytes) {
             panic!("Invalid flac audio format.");
         }
@@ -20,43 +35,81 @@ impl Reader for Flac {
         loop {
             let block = Block::new(&mut reader);
             let is_last = block.is_last;
-            match block {
-                block if StreamInfo::is_stream_info(&block) => {
-                    blocks.push(FlacParsedBlock::StreamInfo(StreamInfo::new(block)))
-                }
-                block if Picture::is_picture(&block) => {
-                    blocks.push(FlacParsedBlock::Picture(Picture::new(block)))
-                }
-                block if Comments::is_comment(&block) => {
-                    blocks.push(FlacParsedBlock::Comment(Comments::new(block).inner))
-                }
-                _ => blocks.push(FlacParsedBlock::Raw(block)),
-            }
+            blocks.push(FlacParsedBlock::with_options(block, options));
             if is_last || reader.is_end() {
                 break;
             }
         }
-        Self { blocks }
-    }
-    fn is(bytes: &[u8]) -> bool {
-        bytes[0..4] == FLAC_SIGNATURE
+        #[cfg(feature = "flac-verify")]
+        let (scan_frames, verify_md5) = (options.scan_frames || options.verify_md5, options.verify_md5);
+        #[cfg(not(feature = "flac-verify"))]
+        let (scan_frames, verify_md5) = (options.scan_frames, false);
+        let mut flac = Self {
+            blocks,
+            audio_size: bytes.len() - reader.offset(),
+            scan: None,
+        };
+        if scan_frames {
+            flac.scan = flac
+                .stream_info()
+                .map(|it| FlacFrameScan::new(bytes, reader.offset(), it, verify_md5));
+        }
+        flac
     }
 }
 
-impl Flac {
-    pub fn blocks(&self) -> &[FlacParsedBlock] {
+impl<'a> Flac<'a> {
+    pub fn blocks(&self) -> &[FlacParsedBlock<'a>] {
         &self.blocks
     }
+    pub fn stream_info(&self) -> Option<&StreamInfo> {
+        self.blocks.iter().find_map(|it| match it {
+            FlacParsedBlock::StreamInfo(stream_info) => Some(stream_info),
+            _ => None,
+        })
+    }
+    /// Result of walking every audio frame, only present if `ParseOptions::scan_frames` is set
+    pub fn frame_scan(&self) -> Option<&FlacFrameScan> {
+        self.scan.as_ref()
+    }
+    pub fn audio_size(&self) -> usize {
+        self.audio_size
+    }
+    /// Average bitrate in kbps of the audio frames
+    pub fn average_bitrate(&self) -> Option<u32> {
+        self.stream_info()
+            .and_then(|it| it.average_bitrate(self.audio_size as u64))
+    }
+    /// Pictures of all PICTURE blocks
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.blocks
+            .iter()
+            .filter_map(|it| match it {
+                FlacParsedBlock::Picture(picture) => Some(picture.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+    /// The best front cover among the pictures, see [`crate::PictureType`]
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        front_cover(self.pictures())
+    }
+    /// None if the STREAMINFO block is missing
+    pub fn properties(&self) -> Option<AudioProperties> {
+        let stream_info = self.stream_info()?;
+        Some(stream_info.properties(Container::Flac).with_bitrate(self.average_bitrate()))
+    }
 }
 
-pub(crate) struct Block {
+/// Metadata block, the data borrows from the parsed file except in Ogg FLAC streams
+pub struct Block<'a> {
     id: u8,
     is_last: bool,
     len: usize,
-    data: Vec<u8>,
+    data: Cow<'a, [u8]>,
 }
 
-impl Debug for Block {
+impl Debug for Block<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Block")
             .field("id", &self.id)
@@ -67,11 +120,11 @@ impl Debug for Block {
     }
 }
 
-impl Block {
-    pub(crate) fn new(reader: &mut ByteReader) -> Self {
+impl<'a> Block<'a> {
+    pub(crate) fn new(reader: &mut ByteReader<'a>) -> Self {
         let (is_last, id) = if reader.peek(1)[0] >> 7 == 1 {
             // 去掉标志位
-            (true, reader.read_next_u8() & 0xf)
+            (true, reader.read_next_u8() & 0x7f)
         } else {
             (false, reader.read_next_u8())
         };
@@ -83,17 +136,83 @@ impl Block {
             id,
             is_last,
             len,
-            data: data.to_vec(),
+            data: Cow::Borrowed(data),
+        }
+    }
+    pub(crate) fn into_owned(self) -> Block<'static> {
+        Block {
+            id: self.id,
+            is_last: self.is_last,
+            len: self.len,
+            data: Cow::Owned(self.data.into_owned()),
         }
     }
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+    pub fn is_last(&self) -> bool {
+        self.is_last
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
 }
 
 #[derive(Debug)]
-pub enum FlacParsedBlock {
+pub enum FlacParsedBlock<'a> {
     StreamInfo(StreamInfo),
     Comment(VorbisComment),
-    Picture(Picture),
-    Raw(Block),
+    Picture(Picture<'a>),
+    SeekTable(SeekTable),
+    CueSheet(CueSheet),
+    Application(Application<'a>),
+    Raw(Block<'a>),
+}
+
+impl<'a> FlacParsedBlock<'a> {
+    pub(crate) fn with_options(block: Block<'a>, options: &ParseOptions) -> Self {
+        match block {
+            block if StreamInfo::is_stream_info(&block) => {
+                FlacParsedBlock::StreamInfo(StreamInfo::new(block))
+            }
+            block if Picture::is_picture(&block) => match Picture::from_block(block) {
+                picture if options.skip_picture_data => {
+                    FlacParsedBlock::Picture(picture.without_data())
+                }
+                picture => FlacParsedBlock::Picture(picture),
+            },
+            block if Comments::is_comment(&block) => {
+                FlacParsedBlock::Comment(Comments::new(block).inner)
+            }
+            block if SeekTable::is_seek_table(&block) => {
+                FlacParsedBlock::SeekTable(SeekTable::new(block))
+            }
+            block if CueSheet::is_cue_sheet(&block) => {
+                FlacParsedBlock::CueSheet(CueSheet::new(block))
+            }
+            block if Application::is_application(&block) => {
+                FlacParsedBlock::Application(Application::new(block))
+            }
+            _ => FlacParsedBlock::Raw(block),
+        }
+    }
+    pub(crate) fn into_owned(self) -> FlacParsedBlock<'static> {
+        match self {
+            FlacParsedBlock::StreamInfo(it) => FlacParsedBlock::StreamInfo(it),
+            FlacParsedBlock::Comment(it) => FlacParsedBlock::Comment(it),
+            FlacParsedBlock::Picture(it) => FlacParsedBlock::Picture(it.into_owned()),
+            FlacParsedBlock::SeekTable(it) => FlacParsedBlock::SeekTable(it),
+            FlacParsedBlock::CueSheet(it) => FlacParsedBlock::CueSheet(it),
+            FlacParsedBlock::Application(it) => FlacParsedBlock::Application(it.into_owned()),
+            FlacParsedBlock::Raw(it) => FlacParsedBlock::Raw(it.into_owned()),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -106,7 +225,7 @@ pub struct StreamInfo {
     channels: u8,
     bits_per_sample: u8,
     total_samples: u64,
-    md5: String,
+    md5: [u8; 16],
 }
 
 impl StreamInfo {
@@ -135,11 +254,7 @@ impl StreamInfo {
                 | (bytes[7] as u64);
             (sample_rate, channels, bits_per_sample, total_samples)
         };
-        let md5 = reader
-            .read(16)
-            .iter()
-            .map(|it| format!("{:x}", it))
-            .collect::<String>();
+        let md5 = reader.read(16).try_into().unwrap();
         Self {
             minimum_block_size,
             maximum_block_size,
@@ -155,81 +270,80 @@ impl StreamInfo {
     pub(crate) fn is_stream_info(block: &Block) -> bool {
         block.id == 0x00
     }
-}
-
-pub struct Picture {
-    r#type: u8,
-    mime: String,
-    desc: String,
-    len: u32,
-    width: u32,
-    height: u32,
-    color_depth: u32,
-    indexed_color: u32,
-    data: Vec<u8>,
-}
-
-impl Debug for Picture {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Picture")
-            .field("type", &self.r#type)
-            .field("mime", &self.mime)
-            .field("desc", &self.desc)
-            .field("width", &self.width)
-            .field("height", &self.height)
-            .field("color_depth", &self.color_depth)
-            .field("indexed_color", &self.indexed_color)
-            .field("len", &self.len)
-            .field("data", &format!("[..]({})", self.data.len()))
-            .finish()
+    pub fn minimum_block_size(&self) -> u32 {
+        self.minimum_block_size
+    }
+    pub fn maximum_block_size(&self) -> u32 {
+        self.maximum_block_size
+    }
+    /// Minimum frame size in bytes, `0` if unknown
+    pub fn minimum_frame_size(&self) -> u32 {
+        self.minimum_frame_size
+    }
+    /// Maximum frame size in bytes, `0` if unknown
+    pub fn maximum_frame_size(&self) -> u32 {
+        self.maximum_frame_size
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+    pub fn bits_per_sample(&self) -> u8 {
+        self.bits_per_sample
+    }
+    /// Total samples per channel, `0` if unknown
+    pub fn total_samples(&self) -> u64 {
+        self.total_samples
+    }
+    /// MD5 of the unencoded audio data, all zero if not computed by the encoder
+    pub fn md5(&self) -> &[u8; 16] {
+        &self.md5
+    }
+    pub fn md5_hex(&self) -> String {
+        self.md5.iter().map(|it| format!("{:02x}", it)).collect()
+    }
+    /// `None` if the total samples or the sample rate is unknown
+    pub fn duration(&self) -> Option<Duration> {
+        if self.total_samples == 0 || self.sample_rate == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(
+            self.total_samples * 1_000_000 / self.sample_rate as u64,
+        ))
+    }
+    /// Average bitrate in kbps for the given size of the audio frames
+    pub fn average_bitrate(&self, audio_size: u64) -> Option<u32> {
+        if self.total_samples == 0 || self.sample_rate == 0 {
+            return None;
+        }
+        Some((audio_size * 8 * self.sample_rate as u64 / self.total_samples / 1000) as u32)
+    }
+    pub(crate) fn properties(&self, container: Container) -> AudioProperties {
+        AudioProperties::new(container, "FLAC")
+            .with_duration(self.duration())
+            .with_sample_rate(self.sample_rate)
+            .with_channels(self.channels as u16)
+            .with_bits_per_sample(Some(self.bits_per_sample as u16))
+            .with_lossless(true)
+    }
+    /// More than 16 bits per sample or a sample rate above 48 kHz
+    pub fn is_hi_res(&self) -> bool {
+        self.bits_per_sample > 16 || self.sample_rate > 48000
     }
 }
 
-impl Picture {
-    pub(crate) fn new(block: Block) -> Self {
-        Self::from_bytes(&block.data)
-    }
-    pub(crate) fn from_bytes(bytes: &[u8]) -> Self {
-        let mut reader = ByteReader::new(bytes);
-        // type
-        let r#type = reader.read_next_u32(true) as u8;
-        // mime
-        let mime_length = reader.read_next_u32(true) as usize;
-        let mime = reader.read_uft8_string(mime_length);
-        // desc
-        let desc_length = reader.read_next_u32(true) as usize;
-        let desc = reader.read_uft8_string(desc_length);
-        // width
-        let width = reader.read_next_u32(true);
-        let height = reader.read_next_u32(true);
-        let color_depth = reader.read_next_u32(true);
-        let indexed_color = reader.read_next_u32(true);
-        let len = reader.read_next_u32(true);
-        let data = reader.read_remaining().to_vec();
-        Picture {
-            r#type,
-            mime,
-            len,
-            desc,
-            width,
-            height,
-            color_depth,
-            indexed_color,
-            data,
+impl<'a> Picture<'a> {
+    pub(crate) fn from_block(block: Block<'a>) -> Self {
+        match block.data {
+            Cow::Borrowed(data) => Picture::from_bytes(data),
+            Cow::Owned(data) => Picture::from_owned_bytes(data),
         }
     }
     pub(crate) fn is_picture(block: &Block) -> bool {
         block.id == 0x06
     }
-    pub fn mime(&self) -> &str {
-        &self.mime
-    }
-    pub fn description(&self) -> &str {
-        &self.desc
-    }
-    pub fn data(&self) -> &[u8] {
-        &self.data
-    }
 }
 
 #[derive(Debug)]
@@ -247,3 +361,381 @@ impl Comments {
         block.id == 0x04
     }
 }
+
+/// Seek table
+///
+/// Structure
+/// ```text
+/// | S | O | N |+
+/// ```
+/// - S: Sample number of the first sample in the target frame, 8 Bytes, all bits set for a
+///   placeholder point.
+/// - O: Offset in bytes from the first frame to the target frame, 8 Bytes.
+/// - N: Number of samples in the target frame, 2 Bytes.
+#[derive(Debug)]
+pub struct SeekTable {
+    points: Vec<SeekPoint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeekPoint {
+    sample_number: u64,
+    offset: u64,
+    samples: u16,
+}
+
+impl SeekTable {
+    pub(crate) fn new(block: Block) -> Self {
+        let points = block
+            .data
+            .chunks_exact(18)
+            .map(|chunk| {
+                let mut reader = ByteReader::new(chunk);
+                SeekPoint {
+                    sample_number: reader.read_next_u64(true),
+                    offset: reader.read_next_u64(true),
+                    samples: reader.read_next_u16(true),
+                }
+            })
+            .collect();
+        Self { points }
+    }
+    pub(crate) fn is_seek_table(block: &Block) -> bool {
+        block.id == 0x03
+    }
+    /// Seek points, including placeholders
+    pub fn points(&self) -> &[SeekPoint] {
+        &self.points
+    }
+}
+
+impl SeekPoint {
+    pub fn sample_number(&self) -> u64 {
+        self.sample_number
+    }
+    /// Offset in bytes from the first frame
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    pub fn samples(&self) -> u16 {
+        self.samples
+    }
+    pub fn is_placeholder(&self) -> bool {
+        self.sample_number == u64::MAX
+    }
+}
+
+/// Cue sheet
+///
+/// Structure
+/// ```text
+/// | C | L | F | ...R | N | T+
+/// ```
+/// - C: Media catalog number, 128 Bytes, ASCII padded with 0x00.
+/// - L: Number of lead-in samples, 8 Bytes.
+/// - F: Bit 7 is set if the cue sheet corresponds to a CD.
+/// - R: Reserved, 258 Bytes.
+/// - N: Number of tracks, 1 Byte, including the lead-out track.
+/// - T: Tracks, see [`CueSheetTrack`].
+#[derive(Debug)]
+pub struct CueSheet {
+    catalog_number: String,
+    lead_in: u64,
+    is_cd: bool,
+    tracks: Vec<CueSheetTrack>,
+}
+
+/// Cue sheet track
+///
+/// Structure
+/// ```text
+/// | O | N | I | F | ...R | P | X+
+/// ```
+/// - O: Offset in samples relative to the beginning of the audio, 8 Bytes.
+/// - N: Track number, 1 Byte, 170(CD) or 255 for the lead-out track.
+/// - I: ISRC, 12 Bytes, ASCII.
+/// - F: Bit 7 is set for non-audio tracks, bit 6 is the pre-emphasis flag.
+/// - R: Reserved, 13 Bytes.
+/// - P: Number of index points, 1 Byte.
+/// - X: Index points, 12 Bytes each: offset(8 Bytes), number(1 Byte), reserved(3 Bytes).
+#[derive(Debug)]
+pub struct CueSheetTrack {
+    offset: u64,
+    number: u8,
+    isrc: String,
+    is_audio: bool,
+    pre_emphasis: bool,
+    indices: Vec<CueSheetIndex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CueSheetIndex {
+    offset: u64,
+    number: u8,
+}
+
+impl CueSheet {
+    pub(crate) fn new(block: Block) -> Self {
+        let mut reader = ByteReader::new(&block.data);
+        let catalog_number = reader
+            .read_uft8_string(128)
+            .trim_end_matches('\u{0000}')
+            .to_string();
+        let lead_in = reader.read_next_u64(true);
+        let is_cd = reader.read_next_u8() >> 7 == 1;
+        reader.skip(258);
+        let count = reader.read_next_u8() as usize;
+        let mut tracks = Vec::with_capacity(count);
+        for _ in 0..count {
+            let offset = reader.read_next_u64(true);
+            let number = reader.read_next_u8();
+            let isrc = reader
+                .read_uft8_string(12)
+                .trim_end_matches('\u{0000}')
+                .to_string();
+            let flags = reader.read_next_u8();
+            reader.skip(13);
+            let points = reader.read_next_u8() as usize;
+            let indices = (0..points)
+                .map(|_| {
+                    let offset = reader.read_next_u64(true);
+                    let number = reader.read_next_u8();
+                    reader.skip(3);
+                    CueSheetIndex { offset, number }
+                })
+                .collect();
+            tracks.push(CueSheetTrack {
+                offset,
+                number,
+                isrc,
+                is_audio: flags >> 7 == 0,
+                pre_emphasis: (flags >> 6) & 0x01 == 1,
+                indices,
+            })
+        }
+        Self {
+            catalog_number,
+            lead_in,
+            is_cd,
+            tracks,
+        }
+    }
+    pub(crate) fn is_cue_sheet(block: &Block) -> bool {
+        block.id == 0x05
+    }
+    pub fn catalog_number(&self) -> &str {
+        &self.catalog_number
+    }
+    /// Number of lead-in samples, only meaningful for CD-DA cue sheets
+    pub fn lead_in(&self) -> u64 {
+        self.lead_in
+    }
+    pub fn is_cd(&self) -> bool {
+        self.is_cd
+    }
+    /// Tracks, the last one is the lead-out track
+    pub fn tracks(&self) -> &[CueSheetTrack] {
+        &self.tracks
+    }
+}
+
+impl CueSheetTrack {
+    /// Offset in samples relative to the beginning of the audio
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+    pub fn is_lead_out(&self) -> bool {
+        self.number == 170 || self.number == 255
+    }
+    pub fn isrc(&self) -> &str {
+        &self.isrc
+    }
+    pub fn is_audio(&self) -> bool {
+        self.is_audio
+    }
+    pub fn pre_emphasis(&self) -> bool {
+        self.pre_emphasis
+    }
+    pub fn indices(&self) -> &[CueSheetIndex] {
+        &self.indices
+    }
+}
+
+impl CueSheetIndex {
+    /// Offset in samples relative to the track offset
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    /// Index number, `0` is the pre-gap and `1` the start of the track
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+}
+
+/// Application block
+///
+/// Structure
+/// ```text
+/// | I | ...D
+/// ```
+/// - I: Registered application ID, 4 Bytes.
+/// - D: Application data.
+pub struct Application<'a> {
+    id: [u8; 4],
+    data: Cow<'a, [u8]>,
+}
+
+impl Debug for Application<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Application")
+            .field("id", &String::from_utf8_lossy(&self.id))
+            .field("data", &format!("[..]({})", self.data.len()))
+            .finish()
+    }
+}
+
+impl<'a> Application<'a> {
+    pub(crate) fn new(block: Block<'a>) -> Self {
+        let id = block.data[0..4].try_into().unwrap();
+        let data = match block.data {
+            Cow::Borrowed(data) => Cow::Borrowed(&data[4..]),
+            Cow::Owned(mut data) => {
+                data.drain(..4);
+                Cow::Owned(data)
+            }
+        };
+        Self { id, data }
+    }
+    pub(crate) fn into_owned(self) -> Application<'static> {
+        Application {
+            id: self.id,
+            data: Cow::Owned(self.data.into_owned()),
+        }
+    }
+    pub(crate) fn is_application(block: &Block) -> bool {
+        block.id == 0x02 && block.data.len() >= 4
+    }
+    /// Registered application ID, e.g. `riff` or `aiff` for foreign metadata
+    pub fn id(&self) -> &[u8; 4] {
+        &self.id
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::picture::tests::png;
+    use crate::picture::PictureType;
+
+    /// 44.1 kHz, 2 channels, 16 bits, 10 seconds
+    fn stream_info_block() -> Vec<u8> {
+        let mut bytes = vec![0x80, 0x00, 0x00, 0x22, 0x10, 0x00, 0x10, 0x00];
+        bytes.extend([0x00; 6]);
+        bytes.extend([0x0A, 0xC4, 0x42, 0xF0, 0x00, 0x06, 0xBA, 0xA8]);
+        bytes.extend(0x00..0x10u8);
+        bytes
+    }
+
+    #[test]
+    fn test_stream_info() {
+        let mut bytes = FLAC_SIGNATURE.to_vec();
+        bytes.extend(stream_info_block());
+        bytes.extend(vec![0x00; 125_000]);
+        let flac = Flac::from_bytes(&bytes);
+        let stream_info = flac.stream_info().unwrap();
+        assert_eq!(stream_info.sample_rate(), 44100);
+        assert_eq!(stream_info.channels(), 2);
+        assert_eq!(stream_info.bits_per_sample(), 16);
+        assert_eq!(stream_info.total_samples(), 441000);
+        assert_eq!(stream_info.duration(), Some(Duration::from_secs(10)));
+        assert_eq!(stream_info.md5_hex(), "000102030405060708090a0b0c0d0e0f");
+        assert!(!stream_info.is_hi_res());
+        assert_eq!(flac.audio_size(), 125_000);
+        assert_eq!(flac.average_bitrate(), Some(100));
+    }
+
+    #[test]
+    fn test_picture_data() {
+        let png = png(500);
+        let mut picture = 3u32.to_be_bytes().to_vec();
+        picture.extend(9u32.to_be_bytes());
+        picture.extend(b"image/png");
+        picture.extend(5u32.to_be_bytes());
+        picture.extend(b"cover");
+        picture.extend([0x00; 16]);
+        picture.extend((png.len() as u32).to_be_bytes());
+        picture.extend(&png);
+        let mut bytes = FLAC_SIGNATURE.to_vec();
+        let mut stream_info = stream_info_block();
+        // not the last block
+        stream_info[0] = 0x00;
+        bytes.extend(stream_info);
+        bytes.extend([0x86, 0x00, 0x00, picture.len() as u8]);
+        bytes.extend(&picture);
+
+        let flac = Flac::from_bytes(&bytes);
+        let cover = flac.front_cover().unwrap();
+        assert_eq!(cover.data(), png);
+        // borrowed from the file
+        assert_eq!(cover.data().as_ptr(), bytes[bytes.len() - png.len()..].as_ptr());
+
+        let options = ParseOptions::new().skip_picture_data(true);
+        let flac = Flac::from_bytes_with_options(&bytes, &options);
+        let cover = flac.front_cover().unwrap();
+        assert!(cover.data().is_empty());
+        assert_eq!(cover.picture_type(), PictureType::FrontCover);
+        assert_eq!(cover.mime(), "image/png");
+        assert_eq!(cover.description(), "cover");
+        assert_eq!((cover.width(), cover.height()), (500, 500));
+    }
+
+    #[test]
+    fn test_cue_sheet() {
+        let mut data = b"1234567890123".to_vec();
+        data.resize(128, 0x00);
+        data.extend(88200u64.to_be_bytes());
+        data.push(0x80);
+        data.extend([0x00; 258]);
+        data.push(2);
+        // track 1 with pre-gap
+        data.extend(0u64.to_be_bytes());
+        data.push(1);
+        data.extend(b"USRC17607839");
+        data.push(0x40);
+        data.extend([0x00; 13]);
+        data.push(2);
+        data.extend(0u64.to_be_bytes());
+        data.extend([0, 0, 0, 0]);
+        data.extend(588u64.to_be_bytes());
+        data.extend([1, 0, 0, 0]);
+        // lead-out
+        data.extend(441000u64.to_be_bytes());
+        data.push(170);
+        data.extend([0x00; 12 + 1 + 13 + 1]);
+        let block = Block {
+            id: 0x05,
+            is_last: true,
+            len: data.len(),
+            data: Cow::Borrowed(&data),
+        };
+        let cue_sheet = CueSheet::new(block);
+        assert_eq!(cue_sheet.catalog_number(), "1234567890123");
+        assert_eq!(cue_sheet.lead_in(), 88200);
+        assert!(cue_sheet.is_cd());
+        let tracks = cue_sheet.tracks();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].isrc(), "USRC17607839");
+        assert!(tracks[0].is_audio());
+        assert!(tracks[0].pre_emphasis());
+        assert_eq!(tracks[0].indices()[1].offset(), 588);
+        assert_eq!(tracks[0].indices()[1].number(), 1);
+        assert!(tracks[1].is_lead_out());
+        assert_eq!(tracks[1].offset(), 441000);
+    }
+}
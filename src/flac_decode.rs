@@ -0,0 +1,357 @@
+use crate::flac_frame::{ChannelAssignment, FlacFrameHeader};
+
+/// MSB first bit reader over a single frame
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    /// Position in bits
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8], offset: usize) -> Self {
+        Self {
+            bytes,
+            position: offset * 8,
+        }
+    }
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = *self.bytes.get(self.position / 8)?;
+        let bit = (byte >> (7 - self.position % 8)) & 0x01;
+        self.position += 1;
+        Some(bit == 1)
+    }
+    fn read_bits(&mut self, len: u32) -> Option<u64> {
+        let mut value = 0u64;
+        let mut remaining = len;
+        while remaining > 0 {
+            let byte = *self.bytes.get(self.position / 8)? as u64;
+            let available = 8 - (self.position % 8) as u32;
+            let take = available.min(remaining);
+            value = (value << take) | ((byte >> (available - take)) & ((1 << take) - 1));
+            remaining -= take;
+            self.position += take as usize;
+        }
+        Some(value)
+    }
+    fn read_signed(&mut self, len: u32) -> Option<i64> {
+        if len == 0 {
+            return Some(0);
+        }
+        let value = self.read_bits(len)?;
+        Some(((value << (64 - len)) as i64) >> (64 - len))
+    }
+    fn read_unary(&mut self) -> Option<u64> {
+        let mut count = 0;
+        loop {
+            let offset = (self.position % 8) as u32;
+            // remaining bits of the current byte, aligned to the most significant bit
+            let byte = *self.bytes.get(self.position / 8)? << offset;
+            if byte == 0 {
+                count += 8 - offset as u64;
+                self.position += 8 - offset as usize;
+                continue;
+            }
+            let zeros = byte.leading_zeros();
+            count += zeros as u64;
+            self.position += zeros as usize + 1;
+            return Some(count);
+        }
+    }
+    fn read_rice(&mut self, parameter: u32) -> Option<i64> {
+        let value = (self.read_unary()? << parameter) | self.read_bits(parameter)?;
+        Some((value >> 1) as i64 ^ -((value & 0x01) as i64))
+    }
+}
+
+/// Decode a frame into samples per channel, returns `None` if the frame is malformed.
+#[cfg(feature = "flac-verify")]
+pub(crate) fn decode_frame(frame: &[u8], header: &FlacFrameHeader) -> Option<Vec<Vec<i64>>> {
+    decode_channels(&mut BitReader::new(frame, header.header_length()), header)
+}
+
+/// Whether the bytes end before the subframes and the CRC-16 footer of the frame, a malformed
+/// subframe stops the decoding before the end of the bytes instead.
+pub(crate) fn is_truncated(frame: &[u8], header: &FlacFrameHeader) -> bool {
+    let mut reader = BitReader::new(frame, header.header_length());
+    match decode_channels(&mut reader, header) {
+        // the footer follows the zero padding to the byte boundary
+        Some(_) => frame.len() < reader.position.div_ceil(8) + 2,
+        None => reader.position >= frame.len() * 8,
+    }
+}
+
+fn decode_channels(reader: &mut BitReader, header: &FlacFrameHeader) -> Option<Vec<Vec<i64>>> {
+    let assignment = header.channel_assignment();
+    let bits_per_sample = header.bits_per_sample() as u32;
+    let mut channels = (0..assignment.channels())
+        .map(|channel| {
+            // the side channel has one extra bit
+            let side = matches!(
+                (assignment, channel),
+                (ChannelAssignment::LeftSide, 1)
+                    | (ChannelAssignment::SideRight, 0)
+                    | (ChannelAssignment::MidSide, 1)
+            );
+            decode_subframe(
+                reader,
+                header.block_size() as usize,
+                bits_per_sample + side as u32,
+            )
+        })
+        .collect::<Option<Vec<_>>>()?;
+    // the samples of a corrupted frame may overflow, it is malformed then
+    match assignment {
+        ChannelAssignment::Independent(_) => {}
+        ChannelAssignment::LeftSide => {
+            for i in 0..channels[0].len() {
+                channels[1][i] = channels[0][i].checked_sub(channels[1][i])?;
+            }
+        }
+        ChannelAssignment::SideRight => {
+            for i in 0..channels[0].len() {
+                channels[0][i] = channels[0][i].checked_add(channels[1][i])?;
+            }
+        }
+        ChannelAssignment::MidSide => {
+            for i in 0..channels[0].len() {
+                let side = channels[1][i];
+                let mid = (channels[0][i] << 1) | (side & 0x01);
+                channels[0][i] = mid.checked_add(side)? >> 1;
+                channels[1][i] = mid.checked_sub(side)? >> 1;
+            }
+        }
+    }
+    Some(channels)
+}
+
+/// Subframe
+///
+/// Structure
+/// ```text
+/// | P | T | W | ...D
+/// ```
+/// - P: Zero padding, 1 bit.
+/// - T: Type, 6 bits, `000000` constant, `000001` verbatim, `001xxx` fixed of order `xxx`,
+///   `1xxxxx` LPC of order `xxxxx + 1`.
+/// - W: Wasted bits flag, 1 bit, followed by the unary coded count - 1 if set.
+/// - D: Subframe data.
+fn decode_subframe(
+    reader: &mut BitReader,
+    block_size: usize,
+    bits_per_sample: u32,
+) -> Option<Vec<i64>> {
+    if reader.read_bit()? {
+        return None;
+    }
+    let r#type = reader.read_bits(6)? as u32;
+    let wasted = if reader.read_bit()? {
+        reader.read_unary()? as u32 + 1
+    } else {
+        0
+    };
+    let bits_per_sample = bits_per_sample.checked_sub(wasted)?;
+    let mut samples = match r#type {
+        0b000000 => vec![reader.read_signed(bits_per_sample)?; block_size],
+        0b000001 => (0..block_size)
+            .map(|_| reader.read_signed(bits_per_sample))
+            .collect::<Option<Vec<_>>>()?,
+        0b001000..=0b001100 => {
+            let order = (r#type & 0x07) as usize;
+            let mut samples = read_warm_up(reader, order, bits_per_sample)?;
+            read_residual(reader, block_size, order, &mut samples)?;
+            const COEFFICIENTS: [&[i64]; 5] = [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+            predict(&mut samples, order, COEFFICIENTS[order], 0)?;
+            samples
+        }
+        0b100000..=0b111111 => {
+            let order = (r#type & 0x1F) as usize + 1;
+            let mut samples = read_warm_up(reader, order, bits_per_sample)?;
+            let precision = reader.read_bits(4)? as u32 + 1;
+            if precision == 16 {
+                return None;
+            }
+            let shift = reader.read_signed(5)?;
+            if shift < 0 {
+                return None;
+            }
+            let coefficients = (0..order)
+                .map(|_| reader.read_signed(precision))
+                .collect::<Option<Vec<_>>>()?;
+            read_residual(reader, block_size, order, &mut samples)?;
+            predict(&mut samples, order, &coefficients, shift as u32)?;
+            samples
+        }
+        _ => return None,
+    };
+    if wasted > 0 {
+        samples.iter_mut().for_each(|it| *it <<= wasted);
+    }
+    Some(samples)
+}
+
+fn read_warm_up(reader: &mut BitReader, order: usize, bits_per_sample: u32) -> Option<Vec<i64>> {
+    (0..order)
+        .map(|_| reader.read_signed(bits_per_sample))
+        .collect()
+}
+
+/// Rice coded residual, appended to the warm-up samples
+fn read_residual(
+    reader: &mut BitReader,
+    block_size: usize,
+    order: usize,
+    samples: &mut Vec<i64>,
+) -> Option<()> {
+    let (parameter_bits, escape) = match reader.read_bits(2)? {
+        0 => (4, 0x0F),
+        1 => (5, 0x1F),
+        _ => return None,
+    };
+    let partition_order = reader.read_bits(4)? as u32;
+    let partitions = 1usize << partition_order;
+    if !block_size.is_multiple_of(partitions) || block_size >> partition_order < order {
+        return None;
+    }
+    for partition in 0..partitions {
+        let count = (block_size >> partition_order) - if partition == 0 { order } else { 0 };
+        let parameter = reader.read_bits(parameter_bits)? as u32;
+        if parameter == escape {
+            let bits = reader.read_bits(5)? as u32;
+            for _ in 0..count {
+                samples.push(reader.read_signed(bits)?);
+            }
+        } else {
+            for _ in 0..count {
+                samples.push(reader.read_rice(parameter)?);
+            }
+        }
+    }
+    Some(())
+}
+
+/// Restore the samples from the residual in place, `None` if a corrupted residual overflows
+fn predict(samples: &mut [i64], order: usize, coefficients: &[i64], shift: u32) -> Option<()> {
+    for i in order..samples.len() {
+        let mut prediction = 0i64;
+        for (j, coefficient) in coefficients.iter().enumerate() {
+            prediction = prediction.checked_add(coefficient.checked_mul(samples[i - 1 - j])?)?;
+        }
+        samples[i] = samples[i].checked_add(prediction >> shift)?;
+    }
+    Some(())
+}
+
+/// Interleave the samples as little-endian signed integers, the input of the STREAMINFO MD5.
+#[cfg(feature = "flac-verify")]
+pub(crate) fn interleave(channels: &[Vec<i64>], bits_per_sample: u8) -> Vec<u8> {
+    let bytes_per_sample = (bits_per_sample as usize).div_ceil(8);
+    let len = channels.first().map(|it| it.len()).unwrap_or(0);
+    let mut buf = Vec::with_capacity(len * channels.len() * bytes_per_sample);
+    for i in 0..len {
+        for channel in channels {
+            buf.extend_from_slice(&channel[i].to_le_bytes()[..bytes_per_sample]);
+        }
+    }
+    buf
+}
+
+#[cfg(all(test, feature = "flac-verify"))]
+mod tests {
+    use super::*;
+    use crate::flac::Flac;
+    use crate::flac_frame::tests::{flac_bytes, frame, samples};
+    use crate::md5::Md5;
+    use crate::options::ParseOptions;
+    use crate::reader::Reader;
+    use crate::utils::{crc16, crc8};
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bits: u32,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u64, len: u32) {
+            for i in (0..len).rev() {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                *self.bytes.last_mut().unwrap() |=
+                    (((value >> i) & 0x01) as u8) << (7 - self.bits % 8);
+                self.bits += 1;
+            }
+        }
+        fn write_rice(&mut self, value: i64, parameter: u32) {
+            let value = if value >= 0 {
+                value << 1
+            } else {
+                (-value << 1) - 1
+            } as u64;
+            self.write(0, (value >> parameter) as u32);
+            self.write(1, 1);
+            self.write(value, parameter);
+        }
+    }
+
+    #[test]
+    fn test_verify_md5() {
+        let channels = (0..3).map(|it| samples(it * 100 + 1)).collect::<Vec<_>>();
+        let frames = channels
+            .iter()
+            .enumerate()
+            .map(|(i, it)| frame(i as u8, it))
+            .collect::<Vec<_>>();
+        let mut md5 = Md5::new();
+        for channels in &channels {
+            for (left, right) in channels[0].iter().zip(&channels[1]) {
+                md5.update(&left.to_le_bytes());
+                md5.update(&right.to_le_bytes());
+            }
+        }
+        let digest = md5.finalize();
+        let options = ParseOptions::new().verify_md5(true);
+        let bytes = flac_bytes(&frames, 48, digest);
+        let flac = Flac::from_bytes_with_options(&bytes, &options);
+        assert_eq!(flac.frame_scan().unwrap().md5_valid(), Some(true));
+        let mut digest = digest;
+        digest[0] ^= 0xFF;
+        let bytes = flac_bytes(&frames, 48, digest);
+        let flac = Flac::from_bytes_with_options(&bytes, &options);
+        assert_eq!(flac.frame_scan().unwrap().md5_valid(), Some(false));
+    }
+
+    #[test]
+    fn test_decode_fixed_mid_side() {
+        let left: Vec<i64> = (0..16).map(|i| i * i - 40).collect();
+        let right: Vec<i64> = (0..16).map(|i| 30 - 3 * i).collect();
+        let mid: Vec<i64> = left.iter().zip(&right).map(|(l, r)| (l + r) >> 1).collect();
+        let side: Vec<i64> = left.iter().zip(&right).map(|(l, r)| l - r).collect();
+        let mut writer = BitWriter::default();
+        // 16 samples, 44.1 kHz, mid/side, 16 bits, frame 0
+        writer.bytes.extend([0xFF, 0xF8, 0x69, 0xA8, 0x00, 15]);
+        writer.bytes.push(crc8(&writer.bytes));
+        writer.bits = writer.bytes.len() as u32 * 8;
+        for (samples, bits) in [(&mid, 16), (&side, 17)] {
+            // fixed predictor of order 2
+            writer.write(0b0001_0100, 8);
+            writer.write(samples[0] as u64 & ((1 << bits) - 1), bits);
+            writer.write(samples[1] as u64 & ((1 << bits) - 1), bits);
+            writer.write(0, 2);
+            writer.write(0, 4);
+            writer.write(3, 4);
+            for i in 2..16 {
+                writer.write_rice(samples[i] - (2 * samples[i - 1] - samples[i - 2]), 3);
+            }
+        }
+        let crc = crc16(&writer.bytes, 0);
+        writer.bytes.extend(crc.to_be_bytes());
+        let bytes = flac_bytes(&[writer.bytes], 16, [0; 16]);
+        let flac = Flac::from_bytes(&bytes);
+        let stream_info = flac.stream_info().unwrap();
+        let header = FlacFrameHeader::new(&bytes, 42, stream_info).unwrap();
+        let channels = decode_frame(&bytes[42..], &header).unwrap();
+        assert_eq!(channels[0], left);
+        assert_eq!(channels[1], right);
+    }
+}
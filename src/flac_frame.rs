@@ -0,0 +1,505 @@
+use crate::flac::StreamInfo;
+use crate::flac_decode::is_truncated;
+#[cfg(feature = "flac-verify")]
+use crate::flac_decode::{decode_frame, interleave};
+#[cfg(feature = "flac-verify")]
+use crate::md5::Md5;
+use crate::utils::{crc16, crc8};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelAssignment {
+    /// Channels are coded independently
+    Independent(u8),
+    LeftSide,
+    SideRight,
+    MidSide,
+}
+
+impl ChannelAssignment {
+    pub fn channels(&self) -> u8 {
+        match self {
+            ChannelAssignment::Independent(channels) => *channels,
+            _ => 2,
+        }
+    }
+}
+
+/// FLAC audio frame header
+///
+/// Structure
+/// ```text
+/// | S | B | R | C | Z | ...N | [...BS] | [...SR] | CRC
+/// ```
+/// - S: Sync code `11111111111110`(14 bits), reserved(1 bit), blocking strategy(1 bit).
+/// - B: Block size code(4 bits).
+/// - R: Sample rate code(4 bits).
+/// - C: Channel assignment(4 bits).
+/// - Z: Sample size code(3 bits), reserved(1 bit).
+/// - N: Frame number(fixed blocking) or sample number(variable blocking), UTF-8 coded.
+/// - BS: Block size - 1, 8 or 16 bits, depends on the block size code.
+/// - SR: Sample rate in kHz(8 bits), Hz(16 bits) or 10 Hz(16 bits), depends on the sample rate code.
+/// - CRC: CRC-8 of the header, 1 Byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlacFrameHeader {
+    offset: usize,
+    length: usize,
+    header_length: usize,
+    variable_block_size: bool,
+    block_size: u32,
+    sample_rate: u32,
+    channel_assignment: ChannelAssignment,
+    bits_per_sample: u8,
+    number: u64,
+    crc8: u8,
+}
+
+impl FlacFrameHeader {
+    /// Decode the frame header at `offset`, returns `None` if the sync code, a reserved value or
+    /// the CRC-8 is invalid. Values taken from STREAMINFO are resolved with `stream_info`.
+    pub(crate) fn new(bytes: &[u8], offset: usize, stream_info: &StreamInfo) -> Option<Self> {
+        let header = bytes.get(offset..)?;
+        if header.len() < 6 || header[0] != 0xFF || header[1] & 0xFE != 0xF8 {
+            return None;
+        }
+        let variable_block_size = header[1] & 0x01 == 1;
+        let block_size_code = header[2] >> 4;
+        let sample_rate_code = header[2] & 0x0F;
+        let channel_assignment = match header[3] >> 4 {
+            channels @ 0..=7 => ChannelAssignment::Independent(channels + 1),
+            8 => ChannelAssignment::LeftSide,
+            9 => ChannelAssignment::SideRight,
+            10 => ChannelAssignment::MidSide,
+            _ => return None,
+        };
+        let bits_per_sample = match (header[3] >> 1) & 0x07 {
+            0 => stream_info.bits_per_sample(),
+            1 => 8,
+            2 => 12,
+            4 => 16,
+            5 => 20,
+            6 => 24,
+            7 => 32,
+            _ => return None,
+        };
+        if header[3] & 0x01 != 0 || block_size_code == 0 || sample_rate_code == 0x0F {
+            return None;
+        }
+        let mut cur = 4;
+        // UTF-8 like coded number, up to 36 bits
+        let leading = header[cur].leading_ones() as usize;
+        let (mut number, extra) = match leading {
+            0 => (header[cur] as u64, 0),
+            2..=7 => ((header[cur] & (0x7F >> leading)) as u64, leading - 1),
+            _ => return None,
+        };
+        cur += 1;
+        for _ in 0..extra {
+            let byte = *header.get(cur)?;
+            if byte & 0xC0 != 0x80 {
+                return None;
+            }
+            number = (number << 6) | (byte & 0x3F) as u64;
+            cur += 1;
+        }
+        let mut read_extra = |len: usize| -> Option<u32> {
+            let bytes = header.get(cur..cur + len)?;
+            cur += len;
+            Some(bytes.iter().fold(0u32, |a, b| (a << 8) | *b as u32))
+        };
+        let block_size = match block_size_code {
+            1 => 192,
+            2..=5 => 576 << (block_size_code - 2),
+            6 => read_extra(1)? + 1,
+            7 => read_extra(2)? + 1,
+            _ => 256 << (block_size_code - 8),
+        };
+        let sample_rate = match sample_rate_code {
+            0 => stream_info.sample_rate(),
+            1 => 88200,
+            2 => 176400,
+            3 => 192000,
+            4 => 8000,
+            5 => 16000,
+            6 => 22050,
+            7 => 24000,
+            8 => 32000,
+            9 => 44100,
+            10 => 48000,
+            11 => 96000,
+            12 => read_extra(1)? * 1000,
+            13 => read_extra(2)?,
+            _ => read_extra(2)? * 10,
+        };
+        let crc = *header.get(cur)?;
+        if crc8(&header[..cur]) != crc {
+            return None;
+        }
+        Some(Self {
+            offset,
+            length: 0,
+            header_length: cur + 1,
+            variable_block_size,
+            block_size,
+            sample_rate,
+            channel_assignment,
+            bits_per_sample,
+            number,
+            crc8: crc,
+        })
+    }
+    /// Offset of the frame
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+    /// Length of the frame, including the header and the CRC-16 footer
+    pub fn length(&self) -> usize {
+        self.length
+    }
+    /// Length of the header, including the CRC-8
+    pub fn header_length(&self) -> usize {
+        self.header_length
+    }
+    pub fn is_variable_block_size(&self) -> bool {
+        self.variable_block_size
+    }
+    /// Number of samples per channel
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn channel_assignment(&self) -> ChannelAssignment {
+        self.channel_assignment
+    }
+    pub fn bits_per_sample(&self) -> u8 {
+        self.bits_per_sample
+    }
+    /// Frame number for fixed block size streams, otherwise the first sample number
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn crc8(&self) -> u8 {
+        self.crc8
+    }
+    /// Upper bound of the frame length: the maximum frame size of STREAMINFO if known, else the
+    /// length of a frame of verbatim subframes
+    fn max_length(&self, stream_info: &StreamInfo) -> usize {
+        match stream_info.maximum_frame_size() {
+            0 => {
+                let channels = self.channel_assignment.channels() as usize;
+                let bits_per_sample = self.bits_per_sample as usize;
+                // the side channel has one extra bit, each subframe header may be followed by a
+                // unary coded wasted bits count
+                let bits = self.block_size as usize * channels * (bits_per_sample + 1)
+                    + channels * (8 + bits_per_sample);
+                self.header_length + bits.div_ceil(8) + 2
+            }
+            size => size as usize,
+        }
+    }
+    /// Whether the other header may follow this one
+    fn is_followed_by(&self, other: &FlacFrameHeader) -> bool {
+        other.variable_block_size == self.variable_block_size
+            && other.number
+                == if self.variable_block_size {
+                    self.number + self.block_size as u64
+                } else {
+                    self.number + 1
+                }
+    }
+}
+
+/// Find the next frame header starting in `offset..end`
+fn find_frame(
+    bytes: &[u8],
+    offset: usize,
+    end: usize,
+    stream_info: &StreamInfo,
+) -> Option<FlacFrameHeader> {
+    (offset..end.min(bytes.len().saturating_sub(1)))
+        .filter(|it| bytes[*it] == 0xFF && bytes[*it + 1] & 0xFE == 0xF8)
+        .find_map(|it| FlacFrameHeader::new(bytes, it, stream_info))
+}
+
+/// Result of walking every FLAC audio frame
+///
+/// Frame boundaries are found by searching the next header with a valid CRC-8 and the expected
+/// frame or sample number, the frame in between is then checked with its CRC-16.
+#[derive(Debug, Default)]
+pub struct FlacFrameScan {
+    frames: Vec<FlacFrameHeader>,
+    samples: u64,
+    expected_samples: u64,
+    crc16_failures: Vec<usize>,
+    junk_bytes: usize,
+    truncated_frame: Option<usize>,
+    #[cfg(feature = "flac-verify")]
+    md5_valid: Option<bool>,
+}
+
+impl FlacFrameScan {
+    /// Walk the frames starting at `offset`. With `verify_md5` every frame is decoded and the MD5
+    /// of the decoded audio is compared against STREAMINFO.
+    #[cfg_attr(not(feature = "flac-verify"), allow(unused_variables))]
+    pub(crate) fn new(
+        bytes: &[u8],
+        offset: usize,
+        stream_info: &StreamInfo,
+        verify_md5: bool,
+    ) -> Self {
+        #[cfg(feature = "flac-verify")]
+        let mut md5 = (verify_md5 && stream_info.md5() != &[0u8; 16]).then(Md5::new);
+        let mut scan = FlacFrameScan {
+            expected_samples: stream_info.total_samples(),
+            ..Default::default()
+        };
+        let mut current = match FlacFrameHeader::new(bytes, offset, stream_info) {
+            Some(header) => header,
+            None => match find_frame(bytes, offset, bytes.len(), stream_info) {
+                Some(header) => {
+                    scan.junk_bytes += header.offset - offset;
+                    header
+                }
+                None => {
+                    scan.junk_bytes += bytes.len() - offset;
+                    return scan;
+                }
+            },
+        };
+        loop {
+            // the sync code may appear inside the audio data, skip candidates out of sequence
+            // and fall back to the first candidate if a frame is missing. The next header can
+            // not start past the longest possible frame, only the first candidate is taken there.
+            let mut cur = current.offset + 2;
+            let limit = current.offset + current.max_length(stream_info) + 1;
+            let mut fallback = None;
+            let next = loop {
+                match find_frame(bytes, cur, limit, stream_info) {
+                    Some(next) if current.is_followed_by(&next) => break Some(next),
+                    Some(next) => {
+                        fallback.get_or_insert(next);
+                        cur = next.offset + 1;
+                    }
+                    None => {
+                        break fallback
+                            .or_else(|| find_frame(bytes, limit, bytes.len(), stream_info))
+                    }
+                }
+            };
+            let end = next.map(|it| it.offset).unwrap_or(bytes.len());
+            current.length = end - current.offset;
+            let valid = current.length >= 2
+                && crc16(&bytes[current.offset..end - 2], 0)
+                    == u16::from_be_bytes([bytes[end - 2], bytes[end - 1]]);
+            #[cfg(feature = "flac-verify")]
+            if let Some(hasher) = md5.as_mut() {
+                match valid
+                    .then(|| decode_frame(&bytes[current.offset..end], &current))
+                    .flatten()
+                {
+                    Some(channels) => {
+                        hasher.update(&interleave(&channels, current.bits_per_sample))
+                    }
+                    // the decoded audio is incomplete, the MD5 can not match
+                    None => md5 = None,
+                }
+            }
+            if valid {
+                scan.samples += current.block_size as u64;
+            } else if next.is_none() && is_truncated(&bytes[current.offset..end], &current) {
+                scan.truncated_frame = Some(current.offset);
+            } else {
+                scan.crc16_failures.push(current.offset);
+            }
+            scan.frames.push(current);
+            match next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        #[cfg(feature = "flac-verify")]
+        if verify_md5 {
+            scan.md5_valid = Some(
+                md5.map(|it| &it.finalize() == stream_info.md5())
+                    .unwrap_or(false),
+            );
+        }
+        scan
+    }
+    pub fn frames(&self) -> &[FlacFrameHeader] {
+        &self.frames
+    }
+    /// Number of samples per channel in frames with a valid CRC-16
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+    /// Total samples declared by STREAMINFO, `0` if unknown
+    pub fn expected_samples(&self) -> u64 {
+        self.expected_samples
+    }
+    /// Offsets of frames whose CRC-16 does not match
+    pub fn crc16_failures(&self) -> &[usize] {
+        &self.crc16_failures
+    }
+    /// Number of bytes before the first frame header
+    pub fn junk_bytes(&self) -> usize {
+        self.junk_bytes
+    }
+    /// Offset of the final frame if it is cut short
+    pub fn truncated_frame(&self) -> Option<usize> {
+        self.truncated_frame
+    }
+    /// Whether the MD5 of the decoded audio matches STREAMINFO, only present if
+    /// `ParseOptions::verify_md5` is set. `Some(false)` if a frame can not be decoded or
+    /// STREAMINFO has no MD5.
+    #[cfg(feature = "flac-verify")]
+    pub fn md5_valid(&self) -> Option<bool> {
+        self.md5_valid
+    }
+    /// Whether every frame is valid and the sample count matches STREAMINFO
+    pub fn is_intact(&self) -> bool {
+        self.crc16_failures.is_empty()
+            && self.junk_bytes == 0
+            && self.truncated_frame.is_none()
+            && (self.expected_samples == 0 || self.samples == self.expected_samples)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::flac::Flac;
+    use crate::options::ParseOptions;
+    use crate::reader::Reader;
+
+    /// FLAC with 2 channels, 16 bits and 16 samples per frame, frames use verbatim subframes.
+    pub(crate) fn flac_bytes(frames: &[Vec<u8>], total_samples: u64, md5: [u8; 16]) -> Vec<u8> {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend([0x80, 0x00, 0x00, 0x22, 0x00, 0x10, 0x00, 0x10]);
+        bytes.extend([0x00; 6]);
+        bytes.extend([0x0A, 0xC4, 0x42, 0xF0 | (total_samples >> 32) as u8]);
+        bytes.extend((total_samples as u32).to_be_bytes());
+        bytes.extend(md5);
+        frames.iter().for_each(|it| bytes.extend(it));
+        bytes
+    }
+
+    pub(crate) fn frame(number: u8, channels: &[[i16; 16]; 2]) -> Vec<u8> {
+        let mut frame = vec![0xFF, 0xF8, 0x69, 0x18, number, 15];
+        frame.push(crc8(&frame));
+        for channel in channels {
+            frame.push(0x02);
+            channel.iter().for_each(|it| frame.extend(it.to_be_bytes()));
+        }
+        let crc = crc16(&frame, 0);
+        frame.extend(crc.to_be_bytes());
+        frame
+    }
+
+    pub(crate) fn samples(seed: i16) -> [[i16; 16]; 2] {
+        let mut channels = [[0i16; 16]; 2];
+        for (i, it) in channels[0].iter_mut().enumerate() {
+            *it = seed.wrapping_mul(i as i16 + 1);
+        }
+        for (i, it) in channels[1].iter_mut().enumerate() {
+            *it = -seed.wrapping_add(i as i16 * 7);
+        }
+        channels
+    }
+
+    #[test]
+    fn test_frame_scan() {
+        let frames = (0..3)
+            .map(|it| frame(it, &samples(it as i16 * 100 + 1)))
+            .collect::<Vec<_>>();
+        let bytes = flac_bytes(&frames, 48, [0; 16]);
+        let flac = Flac::from_bytes_with_options(&bytes, &ParseOptions::new().scan_frames(true));
+        let scan = flac.frame_scan().unwrap();
+        assert_eq!(scan.frames().len(), 3);
+        assert_eq!(scan.frames()[1].number(), 1);
+        assert_eq!(scan.frames()[1].block_size(), 16);
+        assert_eq!(scan.frames()[1].length(), 7 + 2 * 33 + 2);
+        assert_eq!(scan.samples(), 48);
+        assert!(scan.is_intact());
+
+        let mut corrupted = frames.clone();
+        corrupted[1][20] ^= 0xFF;
+        corrupted[2].truncate(40);
+        let bytes = flac_bytes(&corrupted, 48, [0; 16]);
+        let flac = Flac::from_bytes_with_options(&bytes, &ParseOptions::new().scan_frames(true));
+        let scan = flac.frame_scan().unwrap();
+        let offset = 42 + frames[0].len();
+        assert_eq!(scan.crc16_failures(), &[offset]);
+        assert_eq!(scan.truncated_frame(), Some(offset + frames[1].len()));
+        assert_eq!(scan.samples(), 16);
+        assert!(!scan.is_intact());
+    }
+
+    #[test]
+    fn test_frame_scan_last_frame() {
+        let frames = (0..3)
+            .map(|it| frame(it, &samples(it as i16 * 100 + 1)))
+            .collect::<Vec<_>>();
+        let offset = 42 + frames[0].len() + frames[1].len();
+        let options = ParseOptions::new().scan_frames(true);
+        // complete frame with a corrupted sample
+        let mut corrupted = frames.clone();
+        corrupted[2][20] ^= 0xFF;
+        let bytes = flac_bytes(&corrupted, 48, [0; 16]);
+        let flac = Flac::from_bytes_with_options(&bytes, &options);
+        let scan = flac.frame_scan().unwrap();
+        assert_eq!(scan.crc16_failures(), &[offset]);
+        assert_eq!(scan.truncated_frame(), None);
+        // only the footer is missing
+        let mut truncated = frames.clone();
+        truncated[2].truncate(frames[2].len() - 2);
+        let bytes = flac_bytes(&truncated, 48, [0; 16]);
+        let flac = Flac::from_bytes_with_options(&bytes, &options);
+        let scan = flac.frame_scan().unwrap();
+        assert!(scan.crc16_failures().is_empty());
+        assert_eq!(scan.truncated_frame(), Some(offset));
+    }
+
+    #[test]
+    fn test_frame_scan_overflow() {
+        let mut frame1 = vec![0xFF, 0xF8, 0x69, 0x18, 1, 15];
+        frame1.push(crc8(&frame1));
+        // LPC of order 1 with the coefficient 16383 and escaped residuals of 16 bits, the
+        // prediction overflows after a few samples; followed by a constant subframe
+        let mut bits = format!("01000000{:016b}111000000{:015b}000000111110000", 0x7FFF, 16383);
+        (0..15).for_each(|_| bits += &format!("{:016b}", 0x7FFF));
+        bits += &format!("00000000{:016b}", 0);
+        let bits = bits.as_bytes();
+        frame1.extend(bits.chunks(8).map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, bit)| byte | (bit - b'0') << (7 - i))
+        }));
+        let crc = crc16(&frame1, 0) ^ 0xFFFF;
+        frame1.extend(crc.to_be_bytes());
+        let frames = [frame(0, &samples(1)), frame1];
+        let bytes = flac_bytes(&frames, 32, [0; 16]);
+        let flac = Flac::from_bytes_with_options(&bytes, &ParseOptions::new().scan_frames(true));
+        let scan = flac.frame_scan().unwrap();
+        assert_eq!(scan.crc16_failures(), &[42 + frames[0].len()]);
+        assert_eq!(scan.truncated_frame(), None);
+    }
+
+    #[test]
+    fn test_frame_scan_gap() {
+        let mut frames = (0..3)
+            .map(|it| frame(it, &samples(it as i16 * 100 + 1)))
+            .collect::<Vec<_>>();
+        // longer than any frame of the stream, the next header is searched past it
+        frames[0].extend([0x55; 1000]);
+        let bytes = flac_bytes(&frames, 48, [0; 16]);
+        let flac = Flac::from_bytes_with_options(&bytes, &ParseOptions::new().scan_frames(true));
+        let scan = flac.frame_scan().unwrap();
+        assert_eq!(scan.frames().len(), 3);
+        assert_eq!(scan.frames()[1].offset(), 42 + frames[0].len());
+        assert_eq!(scan.crc16_failures(), &[42]);
+        assert_eq!(scan.samples(), 32);
+    }
+}
@@ -1,3 +1,7 @@
+use crate::mpeg::MpegAudio;
+use crate::options::ParseOptions;
+use crate::picture::{front_cover, ImageInfo, Picture, PictureType};
+use crate::properties::AudioProperties;
 use crate::reader::Reader;
 use crate::utils::{ByteReader, CharacterEncoding};
 use std::fmt::{Debug, Formatter};
@@ -6,16 +10,23 @@ use std::io::SeekFrom;
 const ID3_SIGNATURE: [u8; 3] = [0x49, 0x44, 0x33];
 
 #[derive(Debug)]
-pub struct ID3 {
+pub struct ID3<'a> {
     version: u8,
     revision: u8,
     flags: u8,
     frames_size: usize,
-    tags: Vec<ID3ParsedTag>,
+    tags: Vec<ID3ParsedTag<'a>>,
+    mpeg: Option<MpegAudio>,
 }
 
-impl Reader for ID3 {
-    fn from_bytes(bytes: &[u8]) -> Self {
+impl<'a> Reader<'a> for ID3<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes[0..3] == ID3_SIGNATURE
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
         if !ID3::is(bytes) {
             panic!("Invalid id3 audio format")
         }
@@ -52,14 +63,26 @@ impl Reader for ID3 {
             } else if Comments::is_comments(&frame) {
                 tags.push(ID3ParsedTag::Comments(Comments::new(frame)))
             } else if AttachedPicture::is_attached_picture(&frame) {
-                tags.push(ID3ParsedTag::AttachedPicture(AttachedPicture::new(frame)))
+                let picture = match AttachedPicture::new(frame) {
+                    picture if options.skip_picture_data => picture.without_data(),
+                    picture => picture,
+                };
+                tags.push(ID3ParsedTag::AttachedPicture(picture))
             } else {
                 tags.push(ID3ParsedTag::Raw(frame))
             }
             parsed_bytes += 10 + size; // header + payload
         }
-        reader.seek(SeekFrom::End(128));
-        if reader.read(3) == [0x54, 0x41, 0x47] && reader.peek(1) != [0x00] {
+        // header + frames + footer
+        let audio_start = 10 + frames_size + if flags & 0x10 != 0 { 10 } else { 0 };
+        let mut audio_end = bytes.len();
+        // embedded tags (e.g. the `ID3 ` chunk of WAV and AIFF files) may be shorter than ID3v1
+        let has_v1_space = bytes.len() >= audio_start + 128;
+        if has_v1_space {
+            reader.seek(SeekFrom::End(128));
+        }
+        if has_v1_space && reader.read(3) == [0x54, 0x41, 0x47] && reader.peek(1) != [0x00] {
+            audio_end -= 128;
             let reserved = reader.peek_range(reader.len() - 3, reader.len() - 2)[0] == 0x00;
             tags.push(ID3ParsedTag::V1Tag(V1Tag {
                 title: reader
@@ -101,32 +124,70 @@ impl Reader for ID3 {
             flags,
             frames_size,
             tags,
+            mpeg: if audio_start < audio_end {
+                MpegAudio::new(bytes, audio_start, audio_end, options.scan_frames)
+            } else {
+                None
+            },
         }
     }
-    fn is(bytes: &[u8]) -> bool {
-        bytes[0..3] == ID3_SIGNATURE
-    }
 }
 
-impl ID3 {
-    pub fn tags(&self) -> &[ID3ParsedTag] {
+impl<'a> ID3<'a> {
+    /// Major version, e.g. 3 for ID3v2.3
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+    pub fn revision(&self) -> u8 {
+        self.revision
+    }
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+    /// Size of the frames, excluding the header and footer
+    pub fn frames_size(&self) -> usize {
+        self.frames_size
+    }
+    pub fn tags(&self) -> &[ID3ParsedTag<'a>] {
         &self.tags
     }
+    /// MPEG audio stream following the tag
+    pub fn mpeg(&self) -> Option<&MpegAudio> {
+        self.mpeg.as_ref()
+    }
+    /// Properties of the MPEG audio stream, None if the tag is not followed by any frame
+    pub fn properties(&self) -> Option<AudioProperties> {
+        self.mpeg.as_ref().map(|it| it.properties())
+    }
+    /// Pictures of all `APIC` frames
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.tags
+            .iter()
+            .filter_map(|it| match it {
+                ID3ParsedTag::AttachedPicture(picture) => Some(picture.to_picture()),
+                _ => None,
+            })
+            .collect()
+    }
+    /// The best front cover among the pictures, see [`PictureType`]
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        front_cover(self.pictures())
+    }
 }
 
 #[derive(Debug)]
-pub enum ID3ParsedTag {
+pub enum ID3ParsedTag<'a> {
     // id3 v1
     V1Tag(V1Tag),
     // id3 v2
     Text((String, String)),
     Comments(Comments),
-    AttachedPicture(AttachedPicture),
-    Raw(Frame),
+    AttachedPicture(AttachedPicture<'a>),
+    Raw(Frame<'a>),
 }
 
-/// ID3 V2
-pub(crate) struct Frame {
+/// ID3 V2, the data borrows from the parsed file
+pub struct Frame<'a> {
     id: String,
     /// Data size
     size: usize,
@@ -148,10 +209,10 @@ pub(crate) struct Frame {
     /// - 0x02 UTF-16BE
     /// - 0x03 UTF-8
     encoding: Option<FrameEncoding>,
-    data: Vec<u8>,
+    data: &'a [u8],
 }
 
-impl Debug for Frame {
+impl Debug for Frame<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Frame")
             .field("id", &self.id)
@@ -163,8 +224,8 @@ impl Debug for Frame {
     }
 }
 
-impl Frame {
-    pub(crate) fn new(reader: &mut ByteReader) -> Self {
+impl<'a> Frame<'a> {
+    pub(crate) fn new(reader: &mut ByteReader<'a>) -> Self {
         let id = reader.read_uft8_string(4);
         // size excluded 1 byte of encoding
         let size = reader.read_next_u32(true) as usize - 1;
@@ -181,9 +242,18 @@ impl Frame {
             size,
             flags,
             encoding,
-            data: reader.read(size).to_vec(),
+            data: reader.read(size),
         }
     }
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    pub fn flags(&self) -> (u8, u8) {
+        self.flags
+    }
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
 }
 
 #[derive(Debug, Default)]
@@ -195,9 +265,51 @@ enum FrameEncoding {
     Utf8 = 0x03,
 }
 
+/// ID3v1 genres, including the Winamp extensions up to 125
+const GENRES: [&str; 126] = [
+    "Blues", "Classic Rock", "Country", "Dance", "Disco", "Funk", "Grunge", "Hip-Hop", "Jazz",
+    "Metal", "New Age", "Oldies", "Other", "Pop", "R&B", "Rap", "Reggae", "Rock", "Techno",
+    "Industrial", "Alternative", "Ska", "Death Metal", "Pranks", "Soundtrack", "Euro-Techno",
+    "Ambient", "Trip-Hop", "Vocal", "Jazz+Funk", "Fusion", "Trance", "Classical", "Instrumental",
+    "Acid", "House", "Game", "Sound Clip", "Gospel", "Noise", "AlternRock", "Bass", "Soul",
+    "Punk", "Space", "Meditative", "Instrumental Pop", "Instrumental Rock", "Ethnic", "Gothic",
+    "Darkwave", "Techno-Industrial", "Electronic", "Pop-Folk", "Eurodance", "Dream",
+    "Southern Rock", "Comedy", "Cult", "Gangsta", "Top 40", "Christian Rap", "Pop/Funk",
+    "Jungle", "Native American", "Cabaret", "New Wave", "Psychadelic", "Rave", "Showtunes",
+    "Trailer", "Lo-Fi", "Tribal", "Acid Punk", "Acid Jazz", "Polka", "Retro", "Musical",
+    "Rock & Roll", "Hard Rock", "Folk", "Folk-Rock", "National Folk", "Swing", "Fast Fusion",
+    "Bebop", "Latin", "Revival", "Celtic", "Bluegrass", "Avantgarde", "Gothic Rock",
+    "Progressive Rock", "Psychedelic Rock", "Symphonic Rock", "Slow Rock", "Big Band", "Chorus",
+    "Easy Listening", "Acoustic", "Humour", "Speech", "Chanson", "Opera", "Chamber Music",
+    "Sonata", "Symphony", "Booty Bass", "Primus", "Porn Groove", "Satire", "Slow Jam", "Club",
+    "Tango", "Samba", "Folklore", "Ballad", "Power Ballad", "Rhythmic Soul", "Freestyle", "Duet",
+    "Punk Rock", "Drum Solo", "A capella", "Euro-House", "Dance Hall",
+];
+
+/// Length of the ID3v2 tags (header, frames and footer) at the start of `bytes`, 0 when absent,
+/// used by the formats that may be prefixed by a tag (e.g. TTA, raw AAC)
+pub(crate) fn id3v2_length(bytes: &[u8]) -> usize {
+    let mut offset = 0;
+    while bytes.len() >= offset + 10 && bytes[offset..offset + 3] == ID3_SIGNATURE {
+        let size = &bytes[offset + 6..offset + 10];
+        let frames_size = (size[3] as usize)
+            | ((size[2] as usize) << 7)
+            | ((size[1] as usize) << 14)
+            | ((size[0] as usize) << 21);
+        let footer = if bytes[offset + 5] & 0x10 != 0 { 10 } else { 0 };
+        offset += 10 + frames_size + footer;
+    }
+    offset.min(bytes.len())
+}
+
+/// Name of an ID3v1 genre index
+pub(crate) fn genre_name(index: u8) -> Option<&'static str> {
+    GENRES.get(index as usize).copied()
+}
+
 #[derive(Debug)]
 /// ID3 V1
-pub(crate) struct V1Tag {
+pub struct V1Tag {
     title: String,
     artist: String,
     album: String,
@@ -207,6 +319,35 @@ pub(crate) struct V1Tag {
     genre: u8,
 }
 
+impl V1Tag {
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    pub fn artist(&self) -> &str {
+        &self.artist
+    }
+    pub fn album(&self) -> &str {
+        &self.album
+    }
+    pub fn year(&self) -> u32 {
+        self.year
+    }
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+    /// Track number of ID3v1.1, None for ID3v1.0
+    pub fn track(&self) -> Option<u8> {
+        self.track
+    }
+    pub fn genre(&self) -> u8 {
+        self.genre
+    }
+    /// Name of the genre, None for unknown indices (e.g. 255)
+    pub fn genre_name(&self) -> Option<&'static str> {
+        genre_name(self.genre)
+    }
+}
+
 /// Attached Picture
 ///
 /// Structure
@@ -217,48 +358,65 @@ pub(crate) struct V1Tag {
 /// - D: Description string, Unknown length.
 /// - T: Image type, 1 Byte.
 /// - B: Image binary data.
-pub struct AttachedPicture {
+pub struct AttachedPicture<'a> {
     r#type: u8,
     mime: String,
     description: String,
-    data: Vec<u8>,
+    /// Sniffed before the data may be dropped
+    image: Option<ImageInfo>,
+    data: &'a [u8],
 }
 
-impl Debug for AttachedPicture {
+impl Debug for AttachedPicture<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AttachedPicture")
             .field("type", &self.r#type)
             .field("mime", &self.mime)
             .field("description", &self.description)
+            .field("image", &self.image)
             .field("data", &format!("[..]({})", self.data.len()))
             .finish()
     }
 }
 
-impl AttachedPicture {
-    pub(crate) fn new(frame: Frame) -> Self {
-        let mut reader = ByteReader::new(&frame.data);
+impl<'a> AttachedPicture<'a> {
+    pub(crate) fn new(frame: Frame<'a>) -> Self {
+        let mut reader = ByteReader::new(frame.data);
         let mime = reader.read_uft8_variant_string();
         let r#type = reader.read_next_u8();
         let description = reader.read_uft8_variant_string();
+        let data = reader.read_remaining();
         Self {
             r#type,
             mime,
             description,
-            data: reader.read_remaining().to_vec(),
+            image: ImageInfo::sniff(data),
+            data,
         }
     }
+    pub(crate) fn without_data(self) -> Self {
+        Self { data: &[], ..self }
+    }
     pub(crate) fn is_attached_picture(frame: &Frame) -> bool {
         frame.id == "APIC"
     }
+    pub fn picture_type(&self) -> PictureType {
+        PictureType::new(self.r#type as u32)
+    }
     pub fn mime(&self) -> &str {
         &self.mime
     }
     pub fn description(&self) -> &str {
         &self.description
     }
-    pub fn data(&self) -> &[u8] {
-        &self.data
+    /// Empty when parsed with [`crate::ParseOptions::skip_picture_data`]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+    /// Common picture with the dimensions and MIME read from the image header
+    pub fn to_picture(&self) -> Picture<'a> {
+        let image = self.image.clone();
+        Picture::new(self.picture_type(), &self.mime, &self.description, self.data, image)
     }
 }
 
@@ -267,7 +425,7 @@ pub(crate) struct Text((String, String));
 
 impl Text {
     pub(crate) fn new(frame: Frame) -> Self {
-        let mut reader = ByteReader::new(&frame.data);
+        let mut reader = ByteReader::new(frame.data);
         Self((
             frame.id,
             match frame.encoding.unwrap_or_default() {
@@ -287,7 +445,7 @@ impl Text {
 }
 
 #[derive(Debug)]
-pub(crate) struct Comments {
+pub struct Comments {
     language: String,
     excerpt: String,
     content: String,
@@ -295,7 +453,7 @@ pub(crate) struct Comments {
 
 impl Comments {
     pub(crate) fn new(frame: Frame) -> Self {
-        let mut reader = ByteReader::new(&frame.data);
+        let mut reader = ByteReader::new(frame.data);
         let language = reader.read_uft8_string(3);
         let encoding = frame.encoding.unwrap_or_default();
         let mut read_next_string = || match encoding {
@@ -314,4 +472,90 @@ impl Comments {
     pub(crate) fn is_comments(frame: &Frame) -> bool {
         frame.id == "COMM"
     }
+    /// ISO 639-2 language code
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+    /// Short content description
+    pub fn excerpt(&self) -> &str {
+        &self.excerpt
+    }
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::picture::tests::png;
+
+    #[test]
+    fn test_attached_picture() {
+        let png = png(600);
+        let mut apic = b"\x00image/png\x00\x03cover\x00".to_vec();
+        apic.extend(&png);
+        let mut bytes = b"ID3\x03\x00\x00\x00\x00\x00".to_vec();
+        bytes.push(10 + apic.len() as u8);
+        bytes.extend(b"APIC");
+        bytes.extend((apic.len() as u32).to_be_bytes());
+        bytes.extend([0x00; 2]);
+        bytes.extend(&apic);
+
+        let id3 = ID3::from_bytes(&bytes);
+        let cover = id3.front_cover().unwrap();
+        assert_eq!(cover.data(), png);
+        assert_eq!(cover.data().as_ptr(), bytes[bytes.len() - png.len()..].as_ptr());
+
+        let options = ParseOptions::new().skip_picture_data(true);
+        let id3 = ID3::from_bytes_with_options(&bytes, &options);
+        let cover = id3.front_cover().unwrap();
+        assert!(cover.data().is_empty());
+        assert_eq!(cover.picture_type(), PictureType::FrontCover);
+        assert_eq!(cover.mime(), "image/png");
+        assert_eq!(cover.description(), "cover");
+        assert_eq!((cover.width(), cover.height()), (600, 600));
+    }
+
+    #[test]
+    fn test_comments_and_v1_tag() {
+        let comm = b"\x00engnote\x00hello\x00";
+        let mut bytes = b"ID3\x03\x00\x00\x00\x00\x00".to_vec();
+        bytes.push(10 + comm.len() as u8 + 4);
+        bytes.extend(b"COMM");
+        bytes.extend((comm.len() as u32).to_be_bytes());
+        bytes.extend([0x00; 2]);
+        bytes.extend(comm);
+        // padding
+        bytes.extend([0x00; 4]);
+        let field = |text: &str, len: usize| {
+            let mut field = text.as_bytes().to_vec();
+            field.resize(len, 0);
+            field
+        };
+        bytes.extend(b"TAG");
+        bytes.extend(field("Title", 30));
+        bytes.extend(field("Artist", 30));
+        bytes.extend(field("Album", 30));
+        bytes.extend(b"1999");
+        bytes.extend(field("Comment", 28));
+        bytes.extend([0, 7, 17]);
+
+        let id3 = ID3::from_bytes(&bytes);
+        assert_eq!((id3.version(), id3.revision(), id3.flags()), (3, 0, 0));
+        assert_eq!(id3.frames_size(), 10 + comm.len() + 4);
+        let ID3ParsedTag::Comments(comments) = &id3.tags()[0] else {
+            panic!("expected a COMM frame");
+        };
+        assert_eq!(comments.language(), "eng");
+        assert_eq!(comments.excerpt(), "note");
+        assert_eq!(comments.content(), "hello");
+        let ID3ParsedTag::V1Tag(tag) = &id3.tags()[1] else {
+            panic!("expected an ID3v1 tag");
+        };
+        assert_eq!((tag.title(), tag.artist(), tag.album()), ("Title", "Artist", "Album"));
+        assert_eq!((tag.year(), tag.comment()), (1999, "Comment"));
+        assert_eq!(tag.track(), Some(7));
+        assert_eq!((tag.genre(), tag.genre_name()), (17, Some("Rock")));
+    }
 }
@@ -1,16 +1,69 @@
+mod aac;
+mod ac3;
+mod aiff;
+mod caf;
+mod dsd;
+mod error;
 mod flac;
+mod flac_decode;
+mod flac_frame;
 mod id3;
+#[cfg(feature = "flac-verify")]
+mod md5;
+mod matroska;
+mod monkeys_audio;
+mod mp4;
+mod mpeg;
 mod ogg;
+mod options;
+mod picture;
+mod properties;
 mod reader;
+mod tta;
 mod utils;
 mod vorbis_comment;
+mod wav;
+mod wavpack;
 mod base64;
 
-pub use id3::*;
-pub use flac::{Flac, FlacParsedBlock};
-pub use ogg::*;
+pub use aac::{Aac, AacFormat, AdifHeader, AdtsHeader};
+pub use ac3::{Ac3, Ac3FrameHeader};
+pub use aiff::{Aiff, AiffCommon, AiffInstrument, AiffLoop, AiffMarker};
+pub use caf::{Caf, CafDescription, CafPacketTable};
+pub use dsd::{Dff, Dsf};
+pub use error::Error;
+pub use id3::{AttachedPicture, Comments as ID3Comments, Frame, ID3ParsedTag, V1Tag, ID3};
+pub use flac::{
+    Application, Block, CueSheet, CueSheetIndex, CueSheetTrack, Flac, FlacParsedBlock, SeekPoint,
+    SeekTable, StreamInfo,
+};
+pub use flac_frame::{ChannelAssignment, FlacFrameHeader, FlacFrameScan};
+pub use matroska::{
+    Matroska, MatroskaAttachment, MatroskaChapter, MatroskaEdition, MatroskaInfo, MatroskaSimpleTag,
+    MatroskaTag, MatroskaTargets, MatroskaTrack,
+};
+pub use monkeys_audio::MonkeysAudio;
+pub use mp4::{
+    AlacConfig, AudioSampleEntry, CoverArt, DecoderConfig, Freeform, Mp4, Mp4ParsedItem, Mp4Track,
+};
+pub use mpeg::{
+    ChannelMode, FrameHeader, FrameScan, LameReplayGain, LameTag, MpegAudio, MpegLayer, MpegVersion,
+    VbriHeader, XingHeader,
+};
+pub use ogg::{
+    ChainLink, ChannelMappingTable, Comments, FlacIdentification, LogicalStream, Ogg, OggParsedPage,
+    OpusIdentification, Packet, Packets, PageIntegrity, R128Gain, SkeletonBone, SkeletonHead,
+    SpeexIdentification, TheoraIdentification, VorbisIdentification,
+};
+pub use options::ParseOptions;
+pub use picture::{ImageFormat, ImageInfo, Picture, PictureType};
+pub use properties::{audio_properties, AudioProperties, Container};
 pub use reader::Reader;
+pub use tta::Tta;
+pub use wav::{BroadcastExtension, RiffFormat, Wav, WaveFormat};
+pub use wavpack::WavPack;
 
+#[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
@@ -0,0 +1,1161 @@
+use crate::options::ParseOptions;
+use crate::properties::{AudioProperties, Container};
+use crate::picture::{front_cover, ImageInfo, Picture, PictureType};
+use crate::reader::Reader;
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+const EBML: u32 = 0x1A45DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x18538067;
+const SEEK_HEAD: u32 = 0x114D9B74;
+const SEEK: u32 = 0x4DBB;
+const SEEK_ID: u32 = 0x53AB;
+const SEEK_POSITION: u32 = 0x53AC;
+const INFO: u32 = 0x1549A966;
+const TIMECODE_SCALE: u32 = 0x2AD7B1;
+const DURATION: u32 = 0x4489;
+const TITLE: u32 = 0x7BA9;
+const MUXING_APP: u32 = 0x4D80;
+const WRITING_APP: u32 = 0x5741;
+const TRACKS: u32 = 0x1654AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_UID: u32 = 0x73C5;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const CODEC_PRIVATE: u32 = 0x63A2;
+const NAME: u32 = 0x536E;
+const LANGUAGE: u32 = 0x22B59C;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const OUTPUT_SAMPLING_FREQUENCY: u32 = 0x78B5;
+const CHANNELS: u32 = 0x9F;
+const BIT_DEPTH: u32 = 0x6264;
+const CLUSTER: u32 = 0x1F43B675;
+const TAGS: u32 = 0x1254C367;
+const TAG: u32 = 0x7373;
+const TARGETS: u32 = 0x63C0;
+const TARGET_TYPE_VALUE: u32 = 0x68CA;
+const TARGET_TYPE: u32 = 0x63CA;
+const TAG_TRACK_UID: u32 = 0x63C5;
+const TAG_CHAPTER_UID: u32 = 0x63C4;
+const TAG_ATTACHMENT_UID: u32 = 0x63C6;
+const SIMPLE_TAG: u32 = 0x67C8;
+const TAG_NAME: u32 = 0x45A3;
+const TAG_LANGUAGE: u32 = 0x447A;
+const TAG_DEFAULT: u32 = 0x4484;
+const TAG_STRING: u32 = 0x4487;
+const TAG_BINARY: u32 = 0x4485;
+const ATTACHMENTS: u32 = 0x1941A469;
+const ATTACHED_FILE: u32 = 0x61A7;
+const FILE_DESCRIPTION: u32 = 0x467E;
+const FILE_NAME: u32 = 0x466E;
+const FILE_MIME_TYPE: u32 = 0x4660;
+const FILE_DATA: u32 = 0x465C;
+const FILE_UID: u32 = 0x46AE;
+const CHAPTERS: u32 = 0x1043A770;
+const EDITION_ENTRY: u32 = 0x45B9;
+const EDITION_UID: u32 = 0x45BC;
+const CHAPTER_ATOM: u32 = 0xB6;
+const CHAPTER_UID: u32 = 0x73C4;
+const CHAPTER_TIME_START: u32 = 0x91;
+const CHAPTER_TIME_END: u32 = 0x92;
+const CHAPTER_DISPLAY: u32 = 0x80;
+const CHAP_STRING: u32 = 0x85;
+const CHAP_LANGUAGE: u32 = 0x437C;
+
+/// `TargetTypeValue` of album level tags, also the default when `Targets` is absent
+const TARGET_ALBUM: u64 = 50;
+/// `TargetTypeValue` of track level tags
+const TARGET_TRACK: u64 = 30;
+
+/// Matroska / WebM file (EBML)
+///
+/// Structure
+/// ```text
+/// | EBML header | Segment                                                          |
+/// |             | SeekHead | Info | Tracks | Cluster * | Cues | Tags | Attachments |
+/// ```
+/// Only the level 1 elements before the first `Cluster` are read in order, the ones after the
+/// clusters are located by the `SeekHead`, so the cluster data is never scanned.
+#[derive(Debug)]
+pub struct Matroska<'a> {
+    doc_type: String,
+    info: MatroskaInfo,
+    tracks: Vec<MatroskaTrack>,
+    tags: Vec<MatroskaTag>,
+    attachments: Vec<MatroskaAttachment<'a>>,
+    editions: Vec<MatroskaEdition>,
+}
+
+impl<'a> Reader<'a> for Matroska<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
+        if !Matroska::is(bytes) {
+            panic!("Invalid matroska audio format.");
+        }
+        let mut matroska = Self {
+            doc_type: String::from("matroska"),
+            info: MatroskaInfo::default(),
+            tracks: Vec::new(),
+            tags: Vec::new(),
+            attachments: Vec::new(),
+            editions: Vec::new(),
+        };
+        for element in Elements::new(bytes) {
+            match element.id {
+                EBML => {
+                    if let Some(it) = Elements::new(element.data).find(|it| it.id == DOC_TYPE) {
+                        matroska.doc_type = read_string(it.data);
+                    }
+                }
+                SEGMENT => {
+                    matroska.parse_segment(element.data, options);
+                    break;
+                }
+                _ => (),
+            }
+        }
+        matroska
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes.len() >= 4 && bytes[0..4] == EBML.to_be_bytes()
+    }
+}
+
+impl<'a> Matroska<'a> {
+    fn parse_segment(&mut self, bytes: &'a [u8], options: &ParseOptions) {
+        let mut seen = Vec::new();
+        let mut seeks = Vec::new();
+        for element in Elements::new(bytes) {
+            if element.id == CLUSTER {
+                break;
+            }
+            seen.push(element.offset);
+            self.parse_level1(&element, &mut seeks, options);
+        }
+        // the elements behind the clusters, seek positions are relative to the segment data
+        let mut index = 0;
+        while index < seeks.len() {
+            let (id, position) = seeks[index];
+            index += 1;
+            if seen.contains(&position) || position >= bytes.len() {
+                continue;
+            }
+            let Some(element) = Elements::with_offset(bytes, position).next() else {
+                continue;
+            };
+            if element.id != id {
+                continue;
+            }
+            seen.push(position);
+            self.parse_level1(&element, &mut seeks, options);
+        }
+    }
+    fn parse_level1(
+        &mut self,
+        element: &Element<'a>,
+        seeks: &mut Vec<(u32, usize)>,
+        options: &ParseOptions,
+    ) {
+        match element.id {
+            SEEK_HEAD => {
+                for seek in Elements::new(element.data).filter(|it| it.id == SEEK) {
+                    let mut id = None;
+                    let mut position = None;
+                    for child in Elements::new(seek.data) {
+                        match child.id {
+                            SEEK_ID => id = Some(read_uint(child.data) as u32),
+                            SEEK_POSITION => position = Some(read_uint(child.data) as usize),
+                            _ => (),
+                        }
+                    }
+                    if let (Some(id), Some(position)) = (id, position) {
+                        seeks.push((id, position));
+                    }
+                }
+            }
+            INFO => self.info = MatroskaInfo::new(element.data),
+            TRACKS => self.tracks.extend(
+                Elements::new(element.data)
+                    .filter(|it| it.id == TRACK_ENTRY)
+                    .map(|it| MatroskaTrack::new(it.data)),
+            ),
+            TAGS => self.tags.extend(
+                Elements::new(element.data)
+                    .filter(|it| it.id == TAG)
+                    .map(|it| MatroskaTag::new(it.data)),
+            ),
+            ATTACHMENTS => self.attachments.extend(
+                Elements::new(element.data)
+                    .filter(|it| it.id == ATTACHED_FILE)
+                    .map(|it| match MatroskaAttachment::new(it.data) {
+                        it if options.skip_picture_data && it.is_picture() => it.without_data(),
+                        it => it,
+                    }),
+            ),
+            CHAPTERS => self.editions.extend(
+                Elements::new(element.data)
+                    .filter(|it| it.id == EDITION_ENTRY)
+                    .map(|it| MatroskaEdition::new(it.data)),
+            ),
+            _ => (),
+        }
+    }
+    /// `matroska` or `webm`
+    pub fn doc_type(&self) -> &str {
+        &self.doc_type
+    }
+    pub fn info(&self) -> &MatroskaInfo {
+        &self.info
+    }
+    pub fn tracks(&self) -> &[MatroskaTrack] {
+        &self.tracks
+    }
+    /// The first audio track
+    pub fn audio_track(&self) -> Option<&MatroskaTrack> {
+        self.tracks.iter().find(|it| it.is_audio())
+    }
+    pub fn tags(&self) -> &[MatroskaTag] {
+        &self.tags
+    }
+    pub fn attachments(&self) -> &[MatroskaAttachment<'a>] {
+        &self.attachments
+    }
+    pub fn editions(&self) -> &[MatroskaEdition] {
+        &self.editions
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        self.info.duration()
+    }
+    /// Properties of the first audio track, None if the file has no audio track
+    pub fn properties(&self) -> Option<AudioProperties> {
+        let track = self.audio_track()?;
+        let codec_id = track.codec_id();
+        let (codec, lossless) = match codec_id {
+            "A_OPUS" => ("Opus", false),
+            "A_VORBIS" => ("Vorbis", false),
+            "A_FLAC" => ("FLAC", true),
+            "A_ALAC" => ("ALAC", true),
+            "A_WAVPACK4" => ("WavPack", true),
+            "A_TTA1" => ("TTA", true),
+            "A_AC3" => ("AC-3", false),
+            "A_EAC3" => ("E-AC-3", false),
+            "A_DTS" => ("DTS", false),
+            "A_MPEG/L1" => ("MP1", false),
+            "A_MPEG/L2" => ("MP2", false),
+            "A_MPEG/L3" => ("MP3", false),
+            id if id.starts_with("A_AAC") => ("AAC", false),
+            id if id.starts_with("A_PCM/") => ("PCM", true),
+            id => (id, false),
+        };
+        let container = if self.doc_type == "webm" {
+            Container::WebM
+        } else {
+            Container::Matroska
+        };
+        Some(
+            AudioProperties::new(container, codec)
+                .with_duration(self.duration())
+                .with_sample_rate(track.sample_rate() as u32)
+                .with_channels(track.channels() as u16)
+                .with_bits_per_sample(track.bit_depth().filter(|_| lossless).map(|it| it as u16))
+                .with_lossless(lossless),
+        )
+    }
+    /// Value of the first simple tag named `name` at the target level, e.g. 50 album, 30 track
+    pub fn tag(&self, target_type_value: u64, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .filter(|it| it.targets.type_value == target_type_value)
+            .flat_map(|it| it.simple_tags.iter())
+            .find(|it| it.name.eq_ignore_ascii_case(name))
+            .and_then(|it| it.value())
+    }
+    /// `TITLE` of the track level, files with a single track usually only have the album level
+    pub fn title(&self) -> Option<&str> {
+        self.tag(TARGET_TRACK, "TITLE")
+            .or_else(|| self.tag(TARGET_ALBUM, "TITLE"))
+    }
+    pub fn artist(&self) -> Option<&str> {
+        self.tag(TARGET_TRACK, "ARTIST")
+            .or_else(|| self.tag(TARGET_ALBUM, "ARTIST"))
+    }
+    /// `TITLE` of the album level when the track level has its own title, otherwise `ALBUM`
+    pub fn album(&self) -> Option<&str> {
+        if self.tag(TARGET_TRACK, "TITLE").is_some() {
+            if let Some(album) = self.tag(TARGET_ALBUM, "TITLE") {
+                return Some(album);
+            }
+        }
+        self.tag(TARGET_ALBUM, "ALBUM")
+            .or_else(|| self.tag(TARGET_TRACK, "ALBUM"))
+    }
+    /// Pictures of the image attachments
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.attachments
+            .iter()
+            .filter_map(|it| it.to_picture())
+            .collect()
+    }
+    /// The best front cover among the pictures, the cover names rank first
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        front_cover(self.pictures())
+    }
+    /// The attached image named `cover.*`, or the first attached image
+    pub fn cover(&self) -> Option<&MatroskaAttachment<'a>> {
+        let mut images = self.attachments.iter().filter(|it| it.is_image());
+        images
+            .clone()
+            .find(|it| it.name.to_ascii_lowercase().starts_with("cover."))
+            .or_else(|| images.next())
+    }
+}
+
+/// EBML element, the id keeps its length marker bits
+pub(crate) struct Element<'a> {
+    id: u32,
+    /// Offset of the element header in the parent
+    offset: usize,
+    data: &'a [u8],
+}
+
+/// Iterates the elements laid out one after another, stops at the first invalid element,
+/// a truncated or unknown sized element extends to the end of the parent
+pub(crate) struct Elements<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Elements<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+    pub(crate) fn with_offset(bytes: &'a [u8], offset: usize) -> Self {
+        Self { bytes, offset }
+    }
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Element<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let (id, id_len) = read_vint(self.bytes.get(offset..)?, true)?;
+        if id_len > 4 {
+            return None;
+        }
+        let (size, size_len) = read_vint(self.bytes.get(offset + id_len..)?, false)?;
+        let start = offset + id_len + size_len;
+        let unknown = size == (1u64 << (7 * size_len)) - 1;
+        let end = if unknown {
+            self.bytes.len()
+        } else {
+            start.saturating_add(size as usize).min(self.bytes.len())
+        };
+        self.offset = end;
+        Some(Element {
+            id: id as u32,
+            offset,
+            data: &self.bytes[start..end],
+        })
+    }
+}
+
+/// Reads a variable length integer, the count of leading zero bits of the first byte is the
+/// count of the following bytes
+///
+/// ```text
+/// 1xxx xxxx
+/// 01xx xxxx xxxx xxxx
+/// 001x xxxx xxxx xxxx xxxx xxxx
+/// ...
+/// ```
+fn read_vint(bytes: &[u8], keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *bytes.first()?;
+    if first == 0 {
+        return None;
+    }
+    let len = first.leading_zeros() as usize + 1;
+    let mut value = if keep_marker {
+        first as u64
+    } else {
+        (first & 0xFFu8.checked_shr(len as u32).unwrap_or(0)) as u64
+    };
+    for byte in bytes.get(1..len)? {
+        value = value << 8 | *byte as u64;
+    }
+    Some((value, len))
+}
+
+fn read_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .take(8)
+        .fold(0, |value, byte| value << 8 | *byte as u64)
+}
+
+fn read_float(bytes: &[u8]) -> f64 {
+    match bytes.len() {
+        4 => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+        8 => f64::from_be_bytes(bytes.try_into().unwrap()),
+        _ => 0.0,
+    }
+}
+
+fn read_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// `Info` element
+#[derive(Debug)]
+pub struct MatroskaInfo {
+    /// Nanoseconds of a timestamp unit
+    timecode_scale: u64,
+    /// In timestamp units
+    duration: Option<f64>,
+    title: Option<String>,
+    muxing_app: Option<String>,
+    writing_app: Option<String>,
+}
+
+impl Default for MatroskaInfo {
+    fn default() -> Self {
+        Self {
+            timecode_scale: 1_000_000,
+            duration: None,
+            title: None,
+            muxing_app: None,
+            writing_app: None,
+        }
+    }
+}
+
+impl MatroskaInfo {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut info = Self::default();
+        for element in Elements::new(bytes) {
+            match element.id {
+                TIMECODE_SCALE => info.timecode_scale = read_uint(element.data),
+                DURATION => info.duration = Some(read_float(element.data)),
+                TITLE => info.title = Some(read_string(element.data)),
+                MUXING_APP => info.muxing_app = Some(read_string(element.data)),
+                WRITING_APP => info.writing_app = Some(read_string(element.data)),
+                _ => (),
+            }
+        }
+        info
+    }
+    /// Nanoseconds of a timestamp unit, 1 ms by default
+    pub fn timecode_scale(&self) -> u64 {
+        self.timecode_scale
+    }
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+    pub fn muxing_app(&self) -> Option<&str> {
+        self.muxing_app.as_deref()
+    }
+    pub fn writing_app(&self) -> Option<&str> {
+        self.writing_app.as_deref()
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        let duration = self.duration?;
+        if !duration.is_finite() || duration < 0.0 {
+            return None;
+        }
+        Some(Duration::from_nanos(
+            (duration * self.timecode_scale as f64) as u64,
+        ))
+    }
+}
+
+/// `TrackEntry` element
+#[derive(Debug)]
+pub struct MatroskaTrack {
+    number: u64,
+    uid: u64,
+    /// 1 video, 2 audio, 17 subtitle
+    track_type: u64,
+    codec_id: String,
+    codec_private: Vec<u8>,
+    name: Option<String>,
+    language: String,
+    sample_rate: f64,
+    output_sample_rate: Option<f64>,
+    channels: u64,
+    bit_depth: Option<u64>,
+}
+
+impl MatroskaTrack {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut track = Self {
+            number: 0,
+            uid: 0,
+            track_type: 0,
+            codec_id: String::new(),
+            codec_private: Vec::new(),
+            name: None,
+            language: String::from("eng"),
+            sample_rate: 8000.0,
+            output_sample_rate: None,
+            channels: 1,
+            bit_depth: None,
+        };
+        for element in Elements::new(bytes) {
+            match element.id {
+                TRACK_NUMBER => track.number = read_uint(element.data),
+                TRACK_UID => track.uid = read_uint(element.data),
+                TRACK_TYPE => track.track_type = read_uint(element.data),
+                CODEC_ID => track.codec_id = read_string(element.data),
+                CODEC_PRIVATE => track.codec_private = element.data.to_vec(),
+                NAME => track.name = Some(read_string(element.data)),
+                LANGUAGE => track.language = read_string(element.data),
+                AUDIO => {
+                    for element in Elements::new(element.data) {
+                        match element.id {
+                            SAMPLING_FREQUENCY => track.sample_rate = read_float(element.data),
+                            OUTPUT_SAMPLING_FREQUENCY => {
+                                track.output_sample_rate = Some(read_float(element.data))
+                            }
+                            CHANNELS => track.channels = read_uint(element.data),
+                            BIT_DEPTH => track.bit_depth = Some(read_uint(element.data)),
+                            _ => (),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        track
+    }
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+    /// 1 video, 2 audio, 17 subtitle
+    pub fn track_type(&self) -> u64 {
+        self.track_type
+    }
+    pub fn is_audio(&self) -> bool {
+        self.track_type == 2
+    }
+    /// e.g. `A_OPUS`, `A_VORBIS`, `A_FLAC`, `A_AAC`
+    pub fn codec_id(&self) -> &str {
+        &self.codec_id
+    }
+    /// Codec setup, e.g. the `OpusHead` of `A_OPUS` or the AudioSpecificConfig of `A_AAC`
+    pub fn codec_private(&self) -> &[u8] {
+        &self.codec_private
+    }
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+    /// The output sample rate if present (e.g. HE-AAC), otherwise the sampling frequency
+    pub fn sample_rate(&self) -> f64 {
+        self.output_sample_rate.unwrap_or(self.sample_rate)
+    }
+    pub fn channels(&self) -> u64 {
+        self.channels
+    }
+    pub fn bit_depth(&self) -> Option<u64> {
+        self.bit_depth
+    }
+}
+
+/// `Tag` element
+///
+/// Structure
+/// ```text
+/// Tag
+///     Targets
+///         TargetTypeValue: 70 collection, 50 album, 30 track, 10 subtrack
+///         TagTrackUID / TagChapterUID / TagAttachmentUID *
+///     SimpleTag +
+///         TagName, TagLanguage, TagDefault, TagString or TagBinary, SimpleTag *
+/// ```
+#[derive(Debug)]
+pub struct MatroskaTag {
+    targets: MatroskaTargets,
+    simple_tags: Vec<MatroskaSimpleTag>,
+}
+
+impl MatroskaTag {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut tag = Self {
+            targets: MatroskaTargets {
+                type_value: TARGET_ALBUM,
+                target_type: None,
+                track_uids: Vec::new(),
+                chapter_uids: Vec::new(),
+                attachment_uids: Vec::new(),
+            },
+            simple_tags: Vec::new(),
+        };
+        for element in Elements::new(bytes) {
+            match element.id {
+                TARGETS => tag.targets.parse(element.data),
+                SIMPLE_TAG => tag.simple_tags.push(MatroskaSimpleTag::new(element.data)),
+                _ => (),
+            }
+        }
+        tag
+    }
+    pub fn targets(&self) -> &MatroskaTargets {
+        &self.targets
+    }
+    pub fn simple_tags(&self) -> &[MatroskaSimpleTag] {
+        &self.simple_tags
+    }
+}
+
+/// `Targets` element, the tag applies to everything at the level when no uid is present
+#[derive(Debug)]
+pub struct MatroskaTargets {
+    type_value: u64,
+    target_type: Option<String>,
+    track_uids: Vec<u64>,
+    chapter_uids: Vec<u64>,
+    attachment_uids: Vec<u64>,
+}
+
+impl MatroskaTargets {
+    fn parse(&mut self, bytes: &[u8]) {
+        for element in Elements::new(bytes) {
+            match element.id {
+                TARGET_TYPE_VALUE => self.type_value = read_uint(element.data),
+                TARGET_TYPE => self.target_type = Some(read_string(element.data)),
+                TAG_TRACK_UID => self.track_uids.push(read_uint(element.data)),
+                TAG_CHAPTER_UID => self.chapter_uids.push(read_uint(element.data)),
+                TAG_ATTACHMENT_UID => self.attachment_uids.push(read_uint(element.data)),
+                _ => (),
+            }
+        }
+    }
+    /// 70 collection, 50 album, 30 track, 10 subtrack
+    pub fn type_value(&self) -> u64 {
+        self.type_value
+    }
+    /// e.g. `ALBUM`, `TRACK`
+    pub fn target_type(&self) -> Option<&str> {
+        self.target_type.as_deref()
+    }
+    pub fn track_uids(&self) -> &[u64] {
+        &self.track_uids
+    }
+    pub fn chapter_uids(&self) -> &[u64] {
+        &self.chapter_uids
+    }
+    pub fn attachment_uids(&self) -> &[u64] {
+        &self.attachment_uids
+    }
+}
+
+/// `SimpleTag` element
+#[derive(Debug)]
+pub struct MatroskaSimpleTag {
+    name: String,
+    language: String,
+    default: bool,
+    value: Option<String>,
+    binary: Option<Vec<u8>>,
+    children: Vec<MatroskaSimpleTag>,
+}
+
+impl MatroskaSimpleTag {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut tag = Self {
+            name: String::new(),
+            language: String::from("und"),
+            default: true,
+            value: None,
+            binary: None,
+            children: Vec::new(),
+        };
+        for element in Elements::new(bytes) {
+            match element.id {
+                TAG_NAME => tag.name = read_string(element.data),
+                TAG_LANGUAGE => tag.language = read_string(element.data),
+                TAG_DEFAULT => tag.default = read_uint(element.data) != 0,
+                TAG_STRING => tag.value = Some(read_string(element.data)),
+                TAG_BINARY => tag.binary = Some(element.data.to_vec()),
+                SIMPLE_TAG => tag.children.push(MatroskaSimpleTag::new(element.data)),
+                _ => (),
+            }
+        }
+        tag
+    }
+    /// Upper case name, e.g. `TITLE`, `ARTIST`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+    pub fn binary(&self) -> Option<&[u8]> {
+        self.binary.as_deref()
+    }
+    /// Nested tags describing this one, e.g. `SORT_WITH` under `TITLE`
+    pub fn children(&self) -> &[MatroskaSimpleTag] {
+        &self.children
+    }
+}
+
+/// `AttachedFile` element, the data borrows from the parsed file
+pub struct MatroskaAttachment<'a> {
+    uid: u64,
+    name: String,
+    mime: String,
+    description: Option<String>,
+    /// Sniffed before the data may be dropped
+    image: Option<ImageInfo>,
+    data: &'a [u8],
+}
+
+impl Debug for MatroskaAttachment<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MatroskaAttachment")
+            .field("uid", &self.uid)
+            .field("name", &self.name)
+            .field("mime", &self.mime)
+            .field("description", &self.description)
+            .field("image", &self.image)
+            .field("data", &format!("[..]({})", self.data.len()))
+            .finish()
+    }
+}
+
+impl<'a> MatroskaAttachment<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        let mut attachment = Self {
+            uid: 0,
+            name: String::new(),
+            mime: String::new(),
+            description: None,
+            image: None,
+            data: &[],
+        };
+        for element in Elements::new(bytes) {
+            match element.id {
+                FILE_UID => attachment.uid = read_uint(element.data),
+                FILE_NAME => attachment.name = read_string(element.data),
+                FILE_MIME_TYPE => attachment.mime = read_string(element.data),
+                FILE_DESCRIPTION => attachment.description = Some(read_string(element.data)),
+                FILE_DATA => attachment.data = element.data,
+                _ => (),
+            }
+        }
+        attachment.image = ImageInfo::sniff(attachment.data);
+        attachment
+    }
+    pub(crate) fn without_data(self) -> Self {
+        Self { data: &[], ..self }
+    }
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn mime(&self) -> &str {
+        &self.mime
+    }
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    /// Empty for images when parsed with [`crate::ParseOptions::skip_picture_data`]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+    pub fn is_image(&self) -> bool {
+        self.mime.starts_with("image/")
+    }
+    /// Declared as an image or with a recognized image header
+    fn is_picture(&self) -> bool {
+        self.is_image() || self.image.is_some()
+    }
+    /// Common picture with the dimensions and MIME read from the image header, None if the
+    /// attachment is not an image. The cover names of the Matroska spec, `cover.*`,
+    /// `small_cover.*`, `cover_land.*` and `small_cover_land.*`, are front covers.
+    pub fn to_picture(&self) -> Option<Picture<'a>> {
+        let name = self.name.to_ascii_lowercase();
+        let r#type = match name.split('.').next() {
+            Some("cover" | "small_cover" | "cover_land" | "small_cover_land") => {
+                PictureType::FrontCover
+            }
+            _ => PictureType::Other,
+        };
+        let description = self.description().unwrap_or("");
+        let image = self.image.clone();
+        self.is_picture()
+            .then(|| Picture::new(r#type, &self.mime, description, self.data, image))
+    }
+}
+
+/// `EditionEntry` element
+#[derive(Debug)]
+pub struct MatroskaEdition {
+    uid: u64,
+    chapters: Vec<MatroskaChapter>,
+}
+
+impl MatroskaEdition {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut edition = Self {
+            uid: 0,
+            chapters: Vec::new(),
+        };
+        for element in Elements::new(bytes) {
+            match element.id {
+                EDITION_UID => edition.uid = read_uint(element.data),
+                CHAPTER_ATOM => edition.chapters.push(MatroskaChapter::new(element.data)),
+                _ => (),
+            }
+        }
+        edition
+    }
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+    pub fn chapters(&self) -> &[MatroskaChapter] {
+        &self.chapters
+    }
+}
+
+/// `ChapterAtom` element, the times are in nanoseconds
+#[derive(Debug)]
+pub struct MatroskaChapter {
+    uid: u64,
+    start: u64,
+    end: Option<u64>,
+    /// `ChapterDisplay` strings with their language
+    titles: Vec<(String, String)>,
+    children: Vec<MatroskaChapter>,
+}
+
+impl MatroskaChapter {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut chapter = Self {
+            uid: 0,
+            start: 0,
+            end: None,
+            titles: Vec::new(),
+            children: Vec::new(),
+        };
+        for element in Elements::new(bytes) {
+            match element.id {
+                CHAPTER_UID => chapter.uid = read_uint(element.data),
+                CHAPTER_TIME_START => chapter.start = read_uint(element.data),
+                CHAPTER_TIME_END => chapter.end = Some(read_uint(element.data)),
+                CHAPTER_DISPLAY => {
+                    let mut title = String::new();
+                    let mut language = String::from("eng");
+                    for element in Elements::new(element.data) {
+                        match element.id {
+                            CHAP_STRING => title = read_string(element.data),
+                            CHAP_LANGUAGE => language = read_string(element.data),
+                            _ => (),
+                        }
+                    }
+                    chapter.titles.push((title, language));
+                }
+                CHAPTER_ATOM => chapter.children.push(MatroskaChapter::new(element.data)),
+                _ => (),
+            }
+        }
+        chapter
+    }
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+    pub fn start(&self) -> Duration {
+        Duration::from_nanos(self.start)
+    }
+    pub fn end(&self) -> Option<Duration> {
+        self.end.map(Duration::from_nanos)
+    }
+    /// Titles with their language
+    pub fn titles(&self) -> &[(String, String)] {
+        &self.titles
+    }
+    /// The first title
+    pub fn title(&self) -> Option<&str> {
+        self.titles.first().map(|it| it.0.as_str())
+    }
+    /// Nested chapters
+    pub fn children(&self) -> &[MatroskaChapter] {
+        &self.children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::picture::tests::png;
+
+    fn element(id: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|it| *it == 0)
+            .collect::<Vec<_>>();
+        // 8 bytes size
+        bytes.push(0x01);
+        bytes.extend(&(data.len() as u64).to_be_bytes()[1..]);
+        bytes.extend(data);
+        bytes
+    }
+
+    fn uint(id: u32, value: u64) -> Vec<u8> {
+        element(id, &value.to_be_bytes())
+    }
+
+    fn simple_tag(name: &str, value: &str) -> Vec<u8> {
+        element(
+            SIMPLE_TAG,
+            &[
+                element(TAG_NAME, name.as_bytes()),
+                element(TAG_STRING, value.as_bytes()),
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn test_vint() {
+        assert_eq!(read_vint(&[0x81], false), Some((1, 1)));
+        assert_eq!(read_vint(&[0x40, 0x02], false), Some((2, 2)));
+        assert_eq!(
+            read_vint(&[0x1A, 0x45, 0xDF, 0xA3], true),
+            Some((EBML as u64, 4))
+        );
+        assert_eq!(read_vint(&[0x40], false), None);
+        assert_eq!(read_vint(&[0x00], false), None);
+    }
+
+    #[test]
+    fn test_webm() {
+        let info = element(
+            INFO,
+            &[
+                uint(TIMECODE_SCALE, 1_000_000),
+                element(DURATION, &2500.0f64.to_be_bytes()),
+                element(MUXING_APP, b"Lavf"),
+            ]
+            .concat(),
+        );
+        let tracks = element(
+            TRACKS,
+            &element(
+                TRACK_ENTRY,
+                &[
+                    uint(TRACK_NUMBER, 1),
+                    uint(TRACK_UID, 7),
+                    uint(TRACK_TYPE, 2),
+                    element(CODEC_ID, b"A_OPUS"),
+                    element(
+                        AUDIO,
+                        &[
+                            element(SAMPLING_FREQUENCY, &48000.0f32.to_be_bytes()),
+                            uint(CHANNELS, 2),
+                        ]
+                        .concat(),
+                    ),
+                ]
+                .concat(),
+            ),
+        );
+        // unknown size cluster of garbage that must not be parsed, it extends to the end of the
+        // segment so the elements behind it can only be found through the SeekHead
+        let mut cluster = element(CLUSTER, &[0xFF; 32]);
+        cluster[4..12].copy_from_slice(&0x01FF_FFFF_FFFF_FFFFu64.to_be_bytes());
+        let tags = element(
+            TAGS,
+            &[
+                element(
+                    TAG,
+                    &[
+                        element(TARGETS, &uint(TARGET_TYPE_VALUE, 50)),
+                        simple_tag("TITLE", "Album"),
+                        simple_tag("ARTIST", "Artist"),
+                    ]
+                    .concat(),
+                ),
+                element(
+                    TAG,
+                    &[
+                        element(
+                            TARGETS,
+                            &[uint(TARGET_TYPE_VALUE, 30), uint(TAG_TRACK_UID, 7)].concat(),
+                        ),
+                        simple_tag("TITLE", "Track"),
+                    ]
+                    .concat(),
+                ),
+            ]
+            .concat(),
+        );
+        let attachments = element(
+            ATTACHMENTS,
+            &element(
+                ATTACHED_FILE,
+                &[
+                    element(FILE_NAME, b"cover.jpg"),
+                    element(FILE_MIME_TYPE, b"image/jpeg"),
+                    element(FILE_DATA, &[0xFF, 0xD8, 0xFF]),
+                    uint(FILE_UID, 1),
+                ]
+                .concat(),
+            ),
+        );
+        let chapters = element(
+            CHAPTERS,
+            &element(
+                EDITION_ENTRY,
+                &[
+                    element(
+                        CHAPTER_ATOM,
+                        &[
+                            uint(CHAPTER_TIME_START, 0),
+                            element(CHAPTER_DISPLAY, &element(CHAP_STRING, b"Intro")),
+                        ]
+                        .concat(),
+                    ),
+                    element(
+                        CHAPTER_ATOM,
+                        &[
+                            uint(CHAPTER_TIME_START, 1_000_000_000),
+                            element(CHAPTER_DISPLAY, &element(CHAP_STRING, b"Verse")),
+                        ]
+                        .concat(),
+                    ),
+                ]
+                .concat(),
+            ),
+        );
+        // every seek entry is 2 + 8 + (2 + 8 + 4) + (2 + 8 + 8) = 42 bytes
+        let seek_head_size = 4 + 8 + 42 * 3;
+        let tags_position = seek_head_size + info.len() + tracks.len() + cluster.len();
+        let attachments_position = tags_position + tags.len();
+        let chapters_position = attachments_position + attachments.len();
+        let seek = |id: u32, position: usize| {
+            element(
+                SEEK,
+                &[
+                    element(SEEK_ID, &id.to_be_bytes()),
+                    uint(SEEK_POSITION, position as u64),
+                ]
+                .concat(),
+            )
+        };
+        let seek_head = element(
+            SEEK_HEAD,
+            &[
+                seek(TAGS, tags_position),
+                seek(ATTACHMENTS, attachments_position),
+                seek(CHAPTERS, chapters_position),
+            ]
+            .concat(),
+        );
+        assert_eq!(seek_head.len(), seek_head_size);
+        let mut bytes = element(EBML, &element(DOC_TYPE, b"webm"));
+        // unknown size segment
+        bytes.extend(SEGMENT.to_be_bytes());
+        bytes.push(0xFF);
+        let segment = [
+            seek_head,
+            info,
+            tracks,
+            cluster,
+            tags,
+            attachments,
+            chapters,
+        ]
+        .concat();
+        assert_eq!(Elements::new(&segment).last().map(|it| it.id), Some(CLUSTER));
+        bytes.extend(segment);
+
+        let matroska = Matroska::from_bytes(&bytes);
+        assert_eq!(matroska.doc_type(), "webm");
+        assert_eq!(matroska.duration(), Some(Duration::from_millis(2500)));
+        assert_eq!(matroska.info().muxing_app(), Some("Lavf"));
+        let track = matroska.audio_track().unwrap();
+        assert_eq!(track.codec_id(), "A_OPUS");
+        assert_eq!(track.sample_rate(), 48000.0);
+        assert_eq!(track.channels(), 2);
+        assert_eq!(matroska.title(), Some("Track"));
+        assert_eq!(matroska.album(), Some("Album"));
+        assert_eq!(matroska.artist(), Some("Artist"));
+        assert_eq!(matroska.tags()[1].targets().track_uids(), [7]);
+        let cover = matroska.cover().unwrap();
+        assert_eq!(cover.mime(), "image/jpeg");
+        assert_eq!(cover.data(), [0xFF, 0xD8, 0xFF]);
+        let chapters = matroska.editions()[0].chapters();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1].title(), Some("Verse"));
+        assert_eq!(chapters[1].start(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_attachment_data() {
+        let png = png(600);
+        let attachments = element(
+            ATTACHMENTS,
+            &[
+                element(
+                    ATTACHED_FILE,
+                    &[
+                        element(FILE_NAME, b"cover.png"),
+                        element(FILE_MIME_TYPE, b"image/png"),
+                        element(FILE_DATA, &png),
+                    ]
+                    .concat(),
+                ),
+                element(
+                    ATTACHED_FILE,
+                    &[
+                        element(FILE_NAME, b"font.ttf"),
+                        element(FILE_MIME_TYPE, b"font/ttf"),
+                        element(FILE_DATA, &[0x00, 0x01, 0x00, 0x00]),
+                    ]
+                    .concat(),
+                ),
+            ]
+            .concat(),
+        );
+        let mut bytes = element(EBML, &element(DOC_TYPE, b"matroska"));
+        bytes.extend(element(SEGMENT, &attachments));
+
+        let matroska = Matroska::from_bytes(&bytes);
+        let cover = matroska.front_cover().unwrap();
+        assert_eq!(cover.data(), png);
+        let offset = bytes.windows(png.len()).position(|it| it == png).unwrap();
+        assert_eq!(cover.data().as_ptr(), bytes[offset..].as_ptr());
+
+        let options = ParseOptions::new().skip_picture_data(true);
+        let matroska = Matroska::from_bytes_with_options(&bytes, &options);
+        let cover = matroska.front_cover().unwrap();
+        assert!(cover.data().is_empty());
+        assert_eq!(cover.picture_type(), PictureType::FrontCover);
+        assert_eq!(cover.mime(), "image/png");
+        assert_eq!((cover.width(), cover.height()), (600, 600));
+        // not a picture
+        assert_eq!(matroska.attachments()[1].data(), [0x00, 0x01, 0x00, 0x00]);
+    }
+}
@@ -0,0 +1,115 @@
+const S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// Incremental MD5, used to verify the decoded audio of FLAC streams
+pub(crate) struct Md5 {
+    state: [u32; 4],
+    buffer: Vec<u8>,
+    len: u64,
+}
+
+impl Md5 {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            buffer: Vec::with_capacity(64),
+            len: 0,
+        }
+    }
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        let mut bytes = bytes;
+        if !self.buffer.is_empty() {
+            let take = (64 - self.buffer.len()).min(bytes.len());
+            self.buffer.extend_from_slice(&bytes[..take]);
+            bytes = &bytes[take..];
+            if self.buffer.len() < 64 {
+                return;
+            }
+            let block: [u8; 64] = self.buffer[..].try_into().unwrap();
+            self.process(&block);
+            self.buffer.clear();
+        }
+        let mut chunks = bytes.chunks_exact(64);
+        for chunk in &mut chunks {
+            self.process(chunk.try_into().unwrap());
+        }
+        self.buffer.extend_from_slice(chunks.remainder());
+    }
+    pub(crate) fn finalize(mut self) -> [u8; 16] {
+        let bits = self.len.wrapping_mul(8);
+        let mut padding = vec![0x80u8];
+        let pad_len = (55usize.wrapping_sub(self.len as usize)) % 64;
+        padding.extend(std::iter::repeat_n(0u8, pad_len));
+        padding.extend_from_slice(&bits.to_le_bytes());
+        self.update(&padding);
+        let mut digest = [0u8; 16];
+        for (chunk, value) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        digest
+    }
+    fn process(&mut self, block: &[u8; 64]) {
+        let m: Vec<u32> = block
+            .chunks_exact(4)
+            .map(|it| u32::from_le_bytes(it.try_into().unwrap()))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(K[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(S[i]));
+        }
+        self.state[0] = self.state[0].wrapping_add(a);
+        self.state[1] = self.state[1].wrapping_add(b);
+        self.state[2] = self.state[2].wrapping_add(c);
+        self.state[3] = self.state[3].wrapping_add(d);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(digest: [u8; 16]) -> String {
+        digest.iter().map(|it| format!("{:02x}", it)).collect()
+    }
+
+    #[test]
+    fn it_works() {
+        assert_eq!(
+            hex(Md5::new().finalize()),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        let mut md5 = Md5::new();
+        md5.update(b"The quick brown fox ");
+        md5.update(b"jumps over the lazy dog");
+        assert_eq!(hex(md5.finalize()), "9e107d9d372bb6826bd81d3542a419d6");
+        let mut md5 = Md5::new();
+        md5.update(&[0x61; 100]);
+        md5.update(&[0x61; 100]);
+        assert_eq!(hex(md5.finalize()), "887f30b43b2867f4a9accceee7d16e6c");
+    }
+}
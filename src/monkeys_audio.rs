@@ -0,0 +1,242 @@
+use crate::id3::id3v2_length;
+use crate::properties::{average_bitrate, samples_duration, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::ByteReader;
+use std::time::Duration;
+
+/// Monkey's Audio (APE) file, all fields are little-endian
+///
+/// Structure since version 3.98
+/// ```text
+/// Descriptor
+/// | "MAC " | version | padding | descriptor bytes | header bytes | seek table bytes |
+/// |  4 B   |   2 B   |   2 B   |       4 B        |     4 B      |       4 B        |
+/// | wav header bytes | frame data bytes | frame data bytes (high) | terminating bytes | md5  |
+/// |       4 B        |       4 B        |           4 B           |        4 B        | 16 B |
+/// Header, at the descriptor bytes
+/// | compression level | format flags | blocks per frame | final frame blocks | total frames |
+/// |        2 B        |     2 B      |       4 B        |        4 B         |     4 B      |
+/// | bits per sample | channels | sample rate |
+/// |       2 B       |   2 B    |     4 B     |
+/// ```
+///
+/// Structure before version 3.98
+/// ```text
+/// | "MAC " | version | compression level | format flags | channels | sample rate |
+/// |  4 B   |   2 B   |        2 B        |     2 B      |   2 B    |     4 B     |
+/// | wav header bytes | terminating bytes | total frames | final frame blocks |
+/// |       4 B        |        4 B        |     4 B      |        4 B         |
+/// ```
+#[derive(Debug)]
+pub struct MonkeysAudio {
+    /// e.g. 3990 for 3.99
+    version: u16,
+    compression_level: u16,
+    format_flags: u16,
+    blocks_per_frame: u32,
+    final_frame_blocks: u32,
+    total_frames: u32,
+    bits_per_sample: u16,
+    channels: u16,
+    sample_rate: u32,
+    md5: Option<[u8; 16]>,
+    /// Size of the file, excluding the leading tags
+    audio_size: usize,
+}
+
+impl Reader<'_> for MonkeysAudio {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        if !MonkeysAudio::is(bytes) {
+            panic!("Invalid monkey's audio format.");
+        }
+        let offset = id3v2_length(bytes);
+        let mut reader = ByteReader::with_offset(bytes, offset + 4);
+        let version = reader.read_next_u16(false);
+        if version >= 3980 {
+            reader.skip(2);
+            let descriptor_bytes = reader.read_next_u32(false) as usize;
+            reader.skip(24);
+            let md5 = reader.read(16).try_into().unwrap();
+            let mut reader = ByteReader::with_offset(bytes, offset + descriptor_bytes);
+            Self {
+                version,
+                compression_level: reader.read_next_u16(false),
+                format_flags: reader.read_next_u16(false),
+                blocks_per_frame: reader.read_next_u32(false),
+                final_frame_blocks: reader.read_next_u32(false),
+                total_frames: reader.read_next_u32(false),
+                bits_per_sample: reader.read_next_u16(false),
+                channels: reader.read_next_u16(false),
+                sample_rate: reader.read_next_u32(false),
+                md5: Some(md5),
+                audio_size: bytes.len() - offset,
+            }
+        } else {
+            let compression_level = reader.read_next_u16(false);
+            let format_flags = reader.read_next_u16(false);
+            let channels = reader.read_next_u16(false);
+            let sample_rate = reader.read_next_u32(false);
+            reader.skip(8);
+            let total_frames = reader.read_next_u32(false);
+            let final_frame_blocks = reader.read_next_u32(false);
+            let blocks_per_frame = if version >= 3950 {
+                73728 * 4
+            } else if version >= 3900 || (version >= 3800 && compression_level == 4000) {
+                73728
+            } else {
+                9216
+            };
+            let bits_per_sample = if format_flags & 0x01 != 0 {
+                8
+            } else if format_flags & 0x08 != 0 {
+                24
+            } else {
+                16
+            };
+            Self {
+                version,
+                compression_level,
+                format_flags,
+                blocks_per_frame,
+                final_frame_blocks,
+                total_frames,
+                bits_per_sample,
+                channels,
+                sample_rate,
+                md5: None,
+                audio_size: bytes.len() - offset,
+            }
+        }
+    }
+    fn is(bytes: &[u8]) -> bool {
+        let offset = id3v2_length(bytes);
+        if bytes.len() < offset + 76 || bytes[offset..offset + 4] != *b"MAC " {
+            return false;
+        }
+        // since 3.98 the header follows the descriptor, whose length is stored in the file
+        let version = u16::from_le_bytes([bytes[offset + 4], bytes[offset + 5]]);
+        let descriptor_bytes =
+            u32::from_le_bytes(bytes[offset + 8..offset + 12].try_into().unwrap()) as usize;
+        version < 3980 || bytes.len() >= offset + descriptor_bytes + 24
+    }
+}
+
+impl MonkeysAudio {
+    /// e.g. 3990 for 3.99
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+    /// 1000 fast, 2000 normal, 3000 high, 4000 extra high, 5000 insane
+    pub fn compression_level(&self) -> u16 {
+        self.compression_level
+    }
+    pub fn format_flags(&self) -> u16 {
+        self.format_flags
+    }
+    /// Samples per channel of a frame
+    pub fn blocks_per_frame(&self) -> u32 {
+        self.blocks_per_frame
+    }
+    pub fn final_frame_blocks(&self) -> u32 {
+        self.final_frame_blocks
+    }
+    pub fn total_frames(&self) -> u32 {
+        self.total_frames
+    }
+    pub fn bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// MD5 of the file, since version 3.98
+    pub fn md5(&self) -> Option<&[u8; 16]> {
+        self.md5.as_ref()
+    }
+    /// Samples per channel
+    pub fn total_samples(&self) -> u64 {
+        match self.total_frames {
+            0 => 0,
+            frames => {
+                (frames as u64 - 1) * self.blocks_per_frame as u64 + self.final_frame_blocks as u64
+            }
+        }
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        samples_duration(self.total_samples(), self.sample_rate)
+    }
+    pub fn properties(&self) -> AudioProperties {
+        AudioProperties::new(Container::MonkeysAudio, "Monkey's Audio")
+            .with_duration(self.duration())
+            .with_sample_rate(self.sample_rate)
+            .with_channels(self.channels)
+            .with_bits_per_sample(Some(self.bits_per_sample))
+            .with_bitrate(average_bitrate(self.audio_size as u64, self.duration()))
+            .with_lossless(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monkeys_audio() {
+        let mut bytes = b"MAC ".to_vec();
+        bytes.extend(3990u16.to_le_bytes());
+        bytes.extend([0x00, 0x00]);
+        bytes.extend(52u32.to_le_bytes());
+        bytes.extend(24u32.to_le_bytes());
+        bytes.extend([0x00; 20]);
+        bytes.extend([0xAB; 16]);
+        bytes.extend(2000u16.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        bytes.extend(294912u32.to_le_bytes());
+        bytes.extend(146088u32.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(44100u32.to_le_bytes());
+        let ape = MonkeysAudio::from_bytes(&bytes);
+        assert_eq!(ape.compression_level(), 2000);
+        assert_eq!(ape.total_samples(), 441000);
+        assert_eq!(ape.md5(), Some(&[0xAB; 16]));
+        assert_eq!(
+            ape.properties(),
+            AudioProperties::new(Container::MonkeysAudio, "Monkey's Audio")
+                .with_duration(Some(Duration::from_secs(10)))
+                .with_sample_rate(44100)
+                .with_channels(2)
+                .with_bits_per_sample(Some(16))
+                .with_bitrate(Some(0))
+                .with_lossless(true)
+        );
+
+        // descriptor longer than the file
+        let mut broken = bytes.clone();
+        broken[8..12].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(!MonkeysAudio::is(&broken));
+        assert_eq!(crate::audio_properties(&broken), None);
+
+        // 3.97, 24 bits
+        let mut bytes = b"MAC ".to_vec();
+        bytes.extend(3970u16.to_le_bytes());
+        bytes.extend(3000u16.to_le_bytes());
+        bytes.extend(0x08u16.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(48000u32.to_le_bytes());
+        bytes.extend([0x00; 8]);
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(96000u32.to_le_bytes());
+        bytes.extend([0x00; 48]);
+        let ape = MonkeysAudio::from_bytes(&bytes);
+        assert_eq!(ape.blocks_per_frame(), 294912);
+        assert_eq!(ape.bits_per_sample(), 24);
+        assert_eq!(ape.channels(), 1);
+        assert_eq!(ape.duration(), Some(Duration::from_secs(2)));
+        assert_eq!(ape.md5(), None);
+    }
+}
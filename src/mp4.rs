@@ -0,0 +1,1159 @@
+use crate::id3::genre_name;
+use crate::options::ParseOptions;
+use crate::picture::{front_cover, ImageInfo, Picture, PictureType};
+use crate::properties::{AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::{debug_vec, ByteReader};
+use std::borrow::Cow;
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+/// Sample rates of the MPEG-4 audio sampling frequency index
+pub(crate) const AAC_SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// MP4 / M4A file (ISO base media file format)
+///
+/// The file is a tree of atoms, the metadata lives in `moov`
+/// ```text
+/// ftyp
+/// moov
+/// ├── mvhd                  movie timescale and duration
+/// ├── trak
+/// │   ├── tkhd              track id
+/// │   └── mdia
+/// │       ├── mdhd          track timescale, duration and language
+/// │       ├── hdlr          handler type, `soun` for audio
+/// │       └── minf/stbl/stsd sample entries, e.g. mp4a (esds) or alac (alac)
+/// └── udta
+///     └── meta
+///         └── ilst          iTunes items, each holds one or more `data` atoms
+/// mdat
+/// ```
+#[derive(Debug)]
+pub struct Mp4<'a> {
+    major_brand: String,
+    minor_version: u32,
+    compatible_brands: Vec<String>,
+    /// Units per second of the movie duration
+    timescale: u32,
+    duration: u64,
+    tracks: Vec<Mp4Track>,
+    items: Vec<Mp4ParsedItem<'a>>,
+}
+
+impl<'a> Reader<'a> for Mp4<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
+        if !Mp4::is(bytes) {
+            panic!("Invalid mp4 audio format.");
+        }
+        let mut mp4 = Self {
+            major_brand: String::new(),
+            minor_version: 0,
+            compatible_brands: Vec::new(),
+            timescale: 0,
+            duration: 0,
+            tracks: Vec::new(),
+            items: Vec::new(),
+        };
+        for atom in Atoms::new(bytes) {
+            match &atom.name {
+                b"ftyp" if atom.data.len() >= 8 => {
+                    let mut reader = ByteReader::new(atom.data);
+                    mp4.major_brand = reader.read_uft8_string(4);
+                    mp4.minor_version = reader.read_next_u32(true);
+                    mp4.compatible_brands = atom.data[8..]
+                        .chunks_exact(4)
+                        .map(|it| String::from_utf8_lossy(it).into_owned())
+                        .collect();
+                }
+                b"moov" => mp4.parse_moov(atom.data, options),
+                _ => (),
+            }
+        }
+        mp4
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes.len() >= 12 && bytes[4..8] == *b"ftyp"
+    }
+}
+
+impl<'a> Mp4<'a> {
+    fn parse_moov(&mut self, bytes: &'a [u8], options: &ParseOptions) {
+        for atom in Atoms::new(bytes) {
+            match &atom.name {
+                b"mvhd" => {
+                    if let Some((timescale, duration)) = read_timescale_duration(atom.data) {
+                        self.timescale = timescale;
+                        self.duration = duration;
+                    }
+                }
+                b"trak" => self.tracks.push(Mp4Track::new(atom.data)),
+                b"udta" => {
+                    if let Some(meta) = Atoms::new(atom.data).find(|it| &it.name == b"meta") {
+                        self.parse_meta(meta.data, options);
+                    }
+                }
+                // some encoders put the meta atom directly in moov
+                b"meta" => self.parse_meta(atom.data, options),
+                _ => (),
+            }
+        }
+    }
+    fn parse_meta(&mut self, bytes: &'a [u8], options: &ParseOptions) {
+        // meta is a full atom in ISO files, but QuickTime files omit the version and flags
+        let bytes = if bytes.len() >= 8 && bytes[4..8] == *b"hdlr" {
+            bytes
+        } else {
+            &bytes[4.min(bytes.len())..]
+        };
+        if let Some(ilst) = Atoms::new(bytes).find(|it| &it.name == b"ilst") {
+            for item in Atoms::new(ilst.data) {
+                Mp4ParsedItem::parse(&item, &mut self.items, options);
+            }
+        }
+    }
+    pub fn major_brand(&self) -> &str {
+        &self.major_brand
+    }
+    pub fn minor_version(&self) -> u32 {
+        self.minor_version
+    }
+    pub fn compatible_brands(&self) -> &[String] {
+        &self.compatible_brands
+    }
+    pub fn tracks(&self) -> &[Mp4Track] {
+        &self.tracks
+    }
+    pub fn items(&self) -> &[Mp4ParsedItem<'a>] {
+        &self.items
+    }
+    /// First text item with the key, e.g. `©nam`
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.items.iter().find_map(|it| match it {
+            Mp4ParsedItem::Text((name, value)) if name == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+    /// First sound track with a parsed sample entry
+    pub fn audio_track(&self) -> Option<&Mp4Track> {
+        self.tracks
+            .iter()
+            .find(|it| it.handler == "soun" && it.sample_entry.is_some())
+    }
+    /// Duration of the movie header, falls back to the audio track
+    pub fn duration(&self) -> Option<Duration> {
+        if self.timescale != 0 && self.duration != 0 {
+            return Some(Duration::from_micros(
+                (self.duration as u128 * 1_000_000 / self.timescale as u128) as u64,
+            ));
+        }
+        self.audio_track()?.duration()
+    }
+    /// Pictures of the `covr` item
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.items
+            .iter()
+            .filter_map(|it| match it {
+                Mp4ParsedItem::Cover(cover) => Some(cover.to_picture()),
+                _ => None,
+            })
+            .collect()
+    }
+    /// The largest picture of the `covr` item
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        front_cover(self.pictures())
+    }
+    /// Properties of the audio track, None if the file has no sound track
+    pub fn properties(&self) -> Option<AudioProperties> {
+        let entry = self.audio_track()?.sample_entry()?;
+        let (codec, lossless) = match entry.format() {
+            "mp4a" => match entry.decoder_config().map(|it| it.object_type_indication()) {
+                Some(0x69 | 0x6B) => ("MP3", false),
+                _ => ("AAC", false),
+            },
+            "alac" => ("ALAC", true),
+            "fLaC" => ("FLAC", true),
+            "Opus" => ("Opus", false),
+            "ac-3" => ("AC-3", false),
+            "ec-3" => ("E-AC-3", false),
+            "lpcm" | "ipcm" | "fpcm" | "sowt" | "twos" | "raw " | "in24" | "in32" | "fl32"
+            | "fl64" => ("PCM", true),
+            format => (format, false),
+        };
+        Some(
+            AudioProperties::new(Container::Mp4, codec)
+                .with_duration(self.duration())
+                .with_sample_rate(entry.sample_rate())
+                .with_channels(entry.channels() as u16)
+                .with_bits_per_sample(lossless.then_some(entry.sample_size() as u16))
+                .with_bitrate(match entry.average_bitrate() {
+                    0 => None,
+                    bitrate => Some(bitrate / 1000),
+                })
+                .with_lossless(lossless),
+        )
+    }
+}
+
+/// Reads the timescale and duration of a `mvhd` or `mdhd` atom
+///
+/// Structure
+/// ```text
+/// | version | flags | creation | modification | timescale | duration |
+/// |   1 B   |  3 B  |  4/8 B   |    4/8 B     |    4 B    |  4/8 B   |
+/// ```
+/// None if the atom is too short for its version.
+fn read_timescale_duration(bytes: &[u8]) -> Option<(u32, u64)> {
+    let version = *bytes.first()?;
+    if bytes.len() < if version == 1 { 32 } else { 20 } {
+        return None;
+    }
+    let mut reader = ByteReader::with_offset(bytes, 4);
+    if version == 1 {
+        reader.skip(16);
+        Some((reader.read_next_u32(true), reader.read_next_u64(true)))
+    } else {
+        reader.skip(8);
+        Some((
+            reader.read_next_u32(true),
+            reader.read_next_u32(true) as u64,
+        ))
+    }
+}
+
+/// An atom, `data` excludes the size and name header
+pub(crate) struct Atom<'a> {
+    pub(crate) name: [u8; 4],
+    pub(crate) data: &'a [u8],
+}
+
+/// Iterates the atoms laid out one after another, stops at the first truncated atom
+pub(crate) struct Atoms<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Atoms<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for Atoms<'a> {
+    type Item = Atom<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        if offset + 8 > self.bytes.len() {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(self.bytes, offset);
+        let size = reader.read_next_u32(true) as usize;
+        let name: [u8; 4] = reader.read(4).try_into().unwrap();
+        let (header, size) = match size {
+            // 64-bit size follows the name
+            1 if offset + 16 <= self.bytes.len() => (16, reader.read_next_u64(true) as usize),
+            // extends to the end of the parent
+            0 => (8, self.bytes.len() - offset),
+            size => (8, size),
+        };
+        if size < header || self.bytes.len() - offset < size {
+            return None;
+        }
+        self.offset += size;
+        Some(Atom {
+            name,
+            data: &self.bytes[offset + header..offset + size],
+        })
+    }
+}
+
+/// Atom names are Mac OS Roman, only `©` (0xA9) is used in practice
+fn atom_name(name: &[u8; 4]) -> String {
+    name.iter()
+        .map(|it| if *it == 0xA9 { '©' } else { *it as char })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct Mp4Track {
+    id: u32,
+    /// Handler type, `soun` for audio, `vide` for video
+    handler: String,
+    timescale: u32,
+    duration: u64,
+    /// ISO 639-2/T language code
+    language: String,
+    sample_entry: Option<AudioSampleEntry>,
+}
+
+impl Mp4Track {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut track = Self {
+            id: 0,
+            handler: String::new(),
+            timescale: 0,
+            duration: 0,
+            language: String::new(),
+            sample_entry: None,
+        };
+        for atom in Atoms::new(bytes) {
+            match &atom.name {
+                b"tkhd" if atom.data.len() >= 16 => {
+                    let offset = if atom.data[0] == 1 { 20 } else { 12 };
+                    if atom.data.len() >= offset + 4 {
+                        track.id = ByteReader::with_offset(atom.data, offset).read_next_u32(true);
+                    }
+                }
+                b"mdia" => track.parse_mdia(atom.data),
+                _ => (),
+            }
+        }
+        track
+    }
+    fn parse_mdia(&mut self, bytes: &[u8]) {
+        for atom in Atoms::new(bytes) {
+            match &atom.name {
+                b"mdhd" => {
+                    let Some((timescale, duration)) = read_timescale_duration(atom.data) else {
+                        continue;
+                    };
+                    self.timescale = timescale;
+                    self.duration = duration;
+                    // packed as three 5 bit letters offset by 0x60
+                    let offset = if atom.data[0] == 1 { 32 } else { 20 };
+                    if atom.data.len() < offset + 2 {
+                        continue;
+                    }
+                    let language = ByteReader::with_offset(atom.data, offset).read_next_u16(true);
+                    self.language = (0..3)
+                        .rev()
+                        .map(|it| (((language >> (it * 5)) & 0x1f) as u8 + 0x60) as char)
+                        .collect();
+                }
+                b"hdlr" if atom.data.len() >= 12 => {
+                    self.handler = String::from_utf8_lossy(&atom.data[8..12]).into_owned();
+                }
+                b"minf" if self.handler == "soun" => {
+                    let stsd = Atoms::new(atom.data)
+                        .find(|it| &it.name == b"stbl")
+                        .and_then(|stbl| Atoms::new(stbl.data).find(|it| &it.name == b"stsd"));
+                    if let Some(stsd) = stsd.filter(|it| it.data.len() >= 8) {
+                        // version, flags and entry count
+                        self.sample_entry =
+                            Atoms::new(&stsd.data[8..]).find_map(|it| AudioSampleEntry::new(&it));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    pub fn handler(&self) -> &str {
+        &self.handler
+    }
+    pub fn timescale(&self) -> u32 {
+        self.timescale
+    }
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+    pub fn sample_entry(&self) -> Option<&AudioSampleEntry> {
+        self.sample_entry.as_ref()
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        if self.timescale == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(
+            (self.duration as u128 * 1_000_000 / self.timescale as u128) as u64,
+        ))
+    }
+}
+
+/// Audio sample entry of the `stsd` atom
+///
+/// Structure
+/// ```text
+/// | reserved | data reference index | version | revision | vendor |
+/// |   6 B    |         2 B          |   2 B   |   2 B    |  4 B   |
+/// | channels | sample size | compression id | packet size | sample rate (16.16) |
+/// |   2 B    |     2 B     |      2 B       |     2 B     |         4 B         |
+/// ```
+/// QuickTime version 1 appends 16 bytes, version 2 replaces the fields with a 64-bit float
+/// sample rate and 32-bit channels and sample size. Child atoms (esds, alac) follow.
+#[derive(Debug)]
+pub struct AudioSampleEntry {
+    /// Sample entry type, e.g. `mp4a` or `alac`
+    format: String,
+    channels: u32,
+    sample_size: u32,
+    sample_rate: u32,
+    decoder_config: Option<DecoderConfig>,
+    alac: Option<AlacConfig>,
+}
+
+impl AudioSampleEntry {
+    pub(crate) fn new(atom: &Atom) -> Option<Self> {
+        if atom.data.len() < 28 {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(atom.data, 8);
+        let version = reader.read_next_u16(true);
+        reader.skip(6);
+        let (channels, sample_size, sample_rate, children) = match version {
+            2 if atom.data.len() >= 64 => {
+                let mut reader = ByteReader::with_offset(atom.data, 32);
+                let sample_rate = f64::from_bits(reader.read_next_u64(true)) as u32;
+                let channels = reader.read_next_u32(true);
+                reader.skip(4);
+                let sample_size = reader.read_next_u32(true);
+                (channels, sample_size, sample_rate, 64)
+            }
+            _ => {
+                let channels = reader.read_next_u16(true) as u32;
+                let sample_size = reader.read_next_u16(true) as u32;
+                reader.skip(4);
+                let sample_rate = reader.read_next_u32(true) >> 16;
+                (
+                    channels,
+                    sample_size,
+                    sample_rate,
+                    if version == 1 { 44 } else { 28 },
+                )
+            }
+        };
+        let mut entry = Self {
+            format: atom_name(&atom.name),
+            channels,
+            sample_size,
+            sample_rate,
+            decoder_config: None,
+            alac: None,
+        };
+        for child in Atoms::new(atom.data.get(children..).unwrap_or_default()) {
+            match &child.name {
+                b"esds" if child.data.len() > 4 => {
+                    entry.decoder_config = DecoderConfig::new(&child.data[4..]);
+                }
+                b"alac" if child.data.len() >= 28 => {
+                    entry.alac = Some(AlacConfig::new(&child.data[4..]));
+                }
+                _ => (),
+            }
+        }
+        Some(entry)
+    }
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+    /// Channels of the sample entry, prefer the decoder configuration when present
+    pub fn channels(&self) -> u32 {
+        self.alac
+            .as_ref()
+            .map(|it| it.channels as u32)
+            .unwrap_or(self.channels)
+    }
+    pub fn sample_size(&self) -> u32 {
+        self.alac
+            .as_ref()
+            .map(|it| it.bit_depth as u32)
+            .unwrap_or(self.sample_size)
+    }
+    /// Sample rate in Hz, the 16.16 field of the sample entry can't hold rates above 65535
+    pub fn sample_rate(&self) -> u32 {
+        self.alac
+            .as_ref()
+            .map(|it| it.sample_rate)
+            .or(self.decoder_config.as_ref().and_then(|it| it.sample_rate))
+            .unwrap_or(self.sample_rate)
+    }
+    pub fn decoder_config(&self) -> Option<&DecoderConfig> {
+        self.decoder_config.as_ref()
+    }
+    pub fn alac(&self) -> Option<&AlacConfig> {
+        self.alac.as_ref()
+    }
+    /// Average bitrate in bits per second, 0 if unknown
+    pub fn average_bitrate(&self) -> u32 {
+        self.alac
+            .as_ref()
+            .map(|it| it.average_bitrate)
+            .or(self.decoder_config.as_ref().map(|it| it.average_bitrate))
+            .unwrap_or(0)
+    }
+}
+
+/// Decoder configuration of the `esds` elementary stream descriptor
+///
+/// Descriptors are `| tag 1 B | size 1-4 B, 7 bits each | payload |`, the decoder config
+/// descriptor (0x04) is nested in the ES descriptor (0x03) and holds the decoder specific
+/// info (0x05), which is the AudioSpecificConfig for AAC.
+#[derive(Debug)]
+pub struct DecoderConfig {
+    /// 0x40 MPEG-4 audio, 0x66-0x68 MPEG-2 AAC, 0x69 and 0x6B MPEG audio
+    object_type_indication: u8,
+    max_bitrate: u32,
+    average_bitrate: u32,
+    /// AAC audio object type, e.g. 2 AAC LC, 5 SBR, 29 PS
+    audio_object_type: Option<u8>,
+    sample_rate: Option<u32>,
+    channel_configuration: Option<u8>,
+}
+
+impl DecoderConfig {
+    pub(crate) fn new(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let (tag, _) = read_descriptor(&mut reader)?;
+        if tag != 0x03 {
+            return None;
+        }
+        // ES ID and flags
+        if reader.offset() + 3 > bytes.len() {
+            return None;
+        }
+        reader.skip(2);
+        let flags = reader.read_next_u8();
+        if flags & 0x80 != 0 {
+            // depends on ES ID
+            if reader.offset() + 2 > bytes.len() {
+                return None;
+            }
+            reader.skip(2);
+        }
+        if flags & 0x40 != 0 {
+            // URL
+            if reader.offset() + 1 > bytes.len() {
+                return None;
+            }
+            let len = reader.read_next_u8() as usize;
+            if reader.offset() + len > bytes.len() {
+                return None;
+            }
+            reader.skip(len);
+        }
+        if flags & 0x20 != 0 {
+            // OCR ES ID
+            if reader.offset() + 2 > bytes.len() {
+                return None;
+            }
+            reader.skip(2);
+        }
+        let (tag, _) = read_descriptor(&mut reader)?;
+        if tag != 0x04 || reader.offset() + 13 > bytes.len() {
+            return None;
+        }
+        let object_type_indication = reader.read_next_u8();
+        // stream type, buffer size
+        reader.skip(4);
+        let max_bitrate = reader.read_next_u32(true);
+        let average_bitrate = reader.read_next_u32(true);
+        let mut config = Self {
+            object_type_indication,
+            max_bitrate,
+            average_bitrate,
+            audio_object_type: None,
+            sample_rate: None,
+            channel_configuration: None,
+        };
+        if let Some((0x05, size)) = read_descriptor(&mut reader) {
+            let start = reader.offset();
+            if let Some(info) = bytes.get(start..start + size) {
+                config.parse_audio_specific_config(info);
+            }
+        }
+        Some(config)
+    }
+    /// ```text
+    /// | audio object type | sampling frequency index | (frequency) | channel configuration |
+    /// |  5 bits (+6 bits) |          4 bits          |  (24 bits)  |        4 bits         |
+    /// ```
+    fn parse_audio_specific_config(&mut self, bytes: &[u8]) {
+        let mut buf = [0u8; 8];
+        let len = bytes.len().min(8);
+        buf[..len].copy_from_slice(&bytes[..len]);
+        let bits = u64::from_be_bytes(buf);
+        let mut position = 0;
+        let mut read = |count: u32| {
+            let value = (bits << position) >> (64 - count);
+            position += count;
+            value as u32
+        };
+        let mut audio_object_type = read(5);
+        if audio_object_type == 31 {
+            audio_object_type = 32 + read(6);
+        }
+        let index = read(4);
+        let sample_rate = if index == 0x0f {
+            Some(read(24))
+        } else {
+            AAC_SAMPLE_RATES.get(index as usize).copied()
+        };
+        self.audio_object_type = Some(audio_object_type as u8);
+        self.sample_rate = sample_rate;
+        self.channel_configuration = Some(read(4) as u8);
+    }
+    pub fn object_type_indication(&self) -> u8 {
+        self.object_type_indication
+    }
+    pub fn max_bitrate(&self) -> u32 {
+        self.max_bitrate
+    }
+    pub fn average_bitrate(&self) -> u32 {
+        self.average_bitrate
+    }
+    pub fn audio_object_type(&self) -> Option<u8> {
+        self.audio_object_type
+    }
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+    }
+    /// 1-6 channels, 7 means 7.1 (8 channels), 0 means defined elsewhere
+    pub fn channel_configuration(&self) -> Option<u8> {
+        self.channel_configuration
+    }
+}
+
+/// Reads a descriptor tag and its size
+fn read_descriptor(reader: &mut ByteReader) -> Option<(u8, usize)> {
+    if reader.offset() + 2 > reader.len() {
+        return None;
+    }
+    let tag = reader.read_next_u8();
+    let mut size = 0usize;
+    for _ in 0..4 {
+        if reader.offset() >= reader.len() {
+            return None;
+        }
+        let byte = reader.read_next_u8();
+        size = (size << 7) | (byte & 0x7f) as usize;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    Some((tag, size))
+}
+
+/// ALACSpecificConfig, all fields are big-endian
+///
+/// Structure
+/// ```text
+/// | frame length | compatible version | bit depth | pb | mb | kb | channels |
+/// |     4 B      |        1 B         |    1 B    | 1 B| 1 B| 1 B|   1 B    |
+/// | max run | max frame bytes | average bitrate | sample rate |
+/// |   2 B   |       4 B       |       4 B       |     4 B     |
+/// ```
+#[derive(Debug)]
+pub struct AlacConfig {
+    frame_length: u32,
+    compatible_version: u8,
+    bit_depth: u8,
+    channels: u8,
+    max_frame_bytes: u32,
+    average_bitrate: u32,
+    sample_rate: u32,
+}
+
+impl AlacConfig {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        let frame_length = reader.read_next_u32(true);
+        let compatible_version = reader.read_next_u8();
+        let bit_depth = reader.read_next_u8();
+        // rice parameters
+        reader.skip(3);
+        let channels = reader.read_next_u8();
+        reader.skip(2);
+        Self {
+            frame_length,
+            compatible_version,
+            bit_depth,
+            channels,
+            max_frame_bytes: reader.read_next_u32(true),
+            average_bitrate: reader.read_next_u32(true),
+            sample_rate: reader.read_next_u32(true),
+        }
+    }
+    /// Samples per frame
+    pub fn frame_length(&self) -> u32 {
+        self.frame_length
+    }
+    pub fn compatible_version(&self) -> u8 {
+        self.compatible_version
+    }
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+    pub fn max_frame_bytes(&self) -> u32 {
+        self.max_frame_bytes
+    }
+    pub fn average_bitrate(&self) -> u32 {
+        self.average_bitrate
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+/// iTunes metadata item of the `ilst` atom
+///
+/// Each item atom holds `data` atoms
+/// ```text
+/// | size | "data" | version | type | locale | value |
+/// | 4 B  |  4 B   |   1 B   | 3 B  |  4 B   | nn B  |
+/// ```
+/// Type 1 is UTF-8, 2 UTF-16BE, 13 JPEG, 14 PNG, 27 BMP, 21 signed and 22 unsigned integer,
+/// 0 is implicit (e.g. `trkn`, `disk`, `gnre`).
+#[derive(Debug)]
+pub enum Mp4ParsedItem<'a> {
+    Text((String, String)),
+    Integer((String, i64)),
+    /// (number, total), total is 0 if unknown
+    TrackNumber((u16, u16)),
+    /// (number, total), total is 0 if unknown
+    DiscNumber((u16, u16)),
+    /// ID3v1 genre index and name, from `gnre`
+    Genre((u8, String)),
+    Cover(CoverArt<'a>),
+    /// `----` items, e.g. `com.apple.iTunes:iTunNORM`
+    Freeform(Freeform<'a>),
+    Raw((String, &'a [u8])),
+}
+
+impl<'a> Mp4ParsedItem<'a> {
+    pub(crate) fn parse(
+        item: &Atom<'a>,
+        items: &mut Vec<Mp4ParsedItem<'a>>,
+        options: &ParseOptions,
+    ) {
+        let key = atom_name(&item.name);
+        if &item.name == b"----" {
+            if let Some(freeform) = Freeform::new(item.data) {
+                items.push(Mp4ParsedItem::Freeform(freeform));
+            }
+            return;
+        }
+        for data in Atoms::new(item.data).filter(|it| &it.name == b"data" && it.data.len() >= 8) {
+            let data_type = u32::from_be_bytes(data.data[0..4].try_into().unwrap()) & 0x00ff_ffff;
+            let value = &data.data[8..];
+            let parsed = match (&item.name, data_type) {
+                (b"trkn", _) if value.len() >= 6 => Mp4ParsedItem::TrackNumber(read_pair(value)),
+                (b"disk", _) if value.len() >= 6 => Mp4ParsedItem::DiscNumber(read_pair(value)),
+                (b"gnre", _) if value.len() >= 2 => {
+                    // ID3v1 index plus one
+                    let index = u16::from_be_bytes([value[0], value[1]]).saturating_sub(1) as u8;
+                    match genre_name(index) {
+                        Some(name) => Mp4ParsedItem::Genre((index, name.to_string())),
+                        None => Mp4ParsedItem::Raw((key.clone(), value)),
+                    }
+                }
+                (b"covr", _) => {
+                    let cover = CoverArt::new(data_type, value);
+                    if options.skip_picture_data {
+                        Mp4ParsedItem::Cover(cover.without_data())
+                    } else {
+                        Mp4ParsedItem::Cover(cover)
+                    }
+                }
+                (_, 1) => {
+                    Mp4ParsedItem::Text((key.clone(), String::from_utf8_lossy(value).into_owned()))
+                }
+                (_, 2) => {
+                    let units = value
+                        .chunks_exact(2)
+                        .map(|it| u16::from_be_bytes([it[0], it[1]]))
+                        .collect::<Vec<_>>();
+                    Mp4ParsedItem::Text((key.clone(), String::from_utf16_lossy(&units)))
+                }
+                (_, 21 | 22) if matches!(value.len(), 1 | 2 | 4 | 8) => {
+                    let signed = data_type == 21;
+                    let value = match value.len() {
+                        1 if signed => value[0] as i8 as i64,
+                        1 => value[0] as i64,
+                        2 if signed => i16::from_be_bytes([value[0], value[1]]) as i64,
+                        2 => u16::from_be_bytes([value[0], value[1]]) as i64,
+                        4 if signed => i32::from_be_bytes(value.try_into().unwrap()) as i64,
+                        4 => u32::from_be_bytes(value.try_into().unwrap()) as i64,
+                        _ => i64::from_be_bytes(value.try_into().unwrap()),
+                    };
+                    Mp4ParsedItem::Integer((key.clone(), value))
+                }
+                _ => Mp4ParsedItem::Raw((key.clone(), value)),
+            };
+            items.push(parsed);
+        }
+    }
+}
+
+/// `| reserved 2 B | number 2 B | total 2 B |`
+fn read_pair(value: &[u8]) -> (u16, u16) {
+    (
+        u16::from_be_bytes([value[2], value[3]]),
+        u16::from_be_bytes([value[4], value[5]]),
+    )
+}
+
+/// Cover art of the `covr` item, the data borrows from the parsed file
+pub struct CoverArt<'a> {
+    mime: String,
+    /// Sniffed before the data may be dropped
+    image: Option<ImageInfo>,
+    data: &'a [u8],
+}
+
+impl Debug for CoverArt<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CoverArt")
+            .field("mime", &self.mime)
+            .field("image", &self.image)
+            .field("data", &format!("[..]({})", self.data.len()))
+            .finish()
+    }
+}
+
+impl<'a> CoverArt<'a> {
+    pub(crate) fn new(data_type: u32, data: &'a [u8]) -> Self {
+        let mime = match data_type {
+            13 => "image/jpeg",
+            14 => "image/png",
+            27 => "image/bmp",
+            _ => "application/octet-stream",
+        };
+        Self {
+            mime: mime.to_string(),
+            image: ImageInfo::sniff(data),
+            data,
+        }
+    }
+    pub(crate) fn without_data(self) -> Self {
+        Self { data: &[], ..self }
+    }
+    pub fn mime(&self) -> &str {
+        &self.mime
+    }
+    /// Empty when parsed with [`crate::ParseOptions::skip_picture_data`]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+    /// Common picture with the dimensions and MIME read from the image header, `covr` has no
+    /// picture type so it's taken as the front cover
+    pub fn to_picture(&self) -> Picture<'a> {
+        Picture::new(PictureType::FrontCover, &self.mime, "", self.data, self.image.clone())
+    }
+}
+
+/// Freeform `----` item, identified by a reverse DNS `mean` and a `name`
+///
+/// Structure
+/// ```text
+/// | mean: version/flags 4 B, string | name: version/flags 4 B, string | data |
+/// ```
+pub struct Freeform<'a> {
+    mean: String,
+    name: String,
+    data_type: u32,
+    data: &'a [u8],
+}
+
+impl Debug for Freeform<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Freeform")
+            .field("mean", &self.mean)
+            .field("name", &self.name)
+            .field("data_type", &self.data_type)
+            .field("data", &debug_vec(self.data))
+            .finish()
+    }
+}
+
+impl<'a> Freeform<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Option<Self> {
+        let mut mean = None;
+        let mut name = None;
+        let mut data = None;
+        for atom in Atoms::new(bytes) {
+            match &atom.name {
+                b"mean" if atom.data.len() >= 4 => {
+                    mean = Some(String::from_utf8_lossy(&atom.data[4..]).into_owned())
+                }
+                b"name" if atom.data.len() >= 4 => {
+                    name = Some(String::from_utf8_lossy(&atom.data[4..]).into_owned())
+                }
+                b"data" if atom.data.len() >= 8 && data.is_none() => {
+                    let data_type = u32::from_be_bytes(atom.data[0..4].try_into().unwrap());
+                    data = Some((data_type & 0x00ff_ffff, &atom.data[8..]))
+                }
+                _ => (),
+            }
+        }
+        let (data_type, data) = data?;
+        Some(Self {
+            mean: mean?,
+            name: name?,
+            data_type,
+            data,
+        })
+    }
+    /// Reverse DNS owner, usually `com.apple.iTunes`
+    pub fn mean(&self) -> &str {
+        &self.mean
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// `mean:name`, e.g. `com.apple.iTunes:iTunSMPB`
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.mean, self.name)
+    }
+    pub fn data_type(&self) -> u32 {
+        self.data_type
+    }
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+    /// Value decoded as UTF-8
+    pub fn value(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.data)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::picture::tests::png;
+
+    pub(crate) fn atom(name: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend(name);
+        bytes.extend(payload);
+        bytes
+    }
+
+    fn data(data_type: u32, value: &[u8]) -> Vec<u8> {
+        let mut payload = data_type.to_be_bytes().to_vec();
+        payload.extend([0x00; 4]);
+        payload.extend(value);
+        atom(b"data", &payload)
+    }
+
+    fn full(version: u8, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![version, 0x00, 0x00, 0x00];
+        bytes.extend(payload);
+        bytes
+    }
+
+    fn m4a(sample_entry: Vec<u8>) -> Vec<u8> {
+        let mut ftyp = b"M4A ".to_vec();
+        ftyp.extend(0u32.to_be_bytes());
+        ftyp.extend(b"M4A isommp42");
+        let mut mvhd = vec![0x00; 8];
+        mvhd.extend(1000u32.to_be_bytes());
+        mvhd.extend(180_500u32.to_be_bytes());
+        mvhd.extend([0x00; 80]);
+        let mut mdhd = vec![0x00; 8];
+        mdhd.extend(44100u32.to_be_bytes());
+        mdhd.extend(7_960_050u32.to_be_bytes());
+        // "eng"
+        mdhd.extend(0x15C7u16.to_be_bytes());
+        mdhd.extend([0x00; 2]);
+        let mut hdlr = vec![0x00; 4];
+        hdlr.extend(b"soun");
+        hdlr.extend([0x00; 13]);
+        let mut stsd = 1u32.to_be_bytes().to_vec();
+        stsd.extend(sample_entry);
+        let stbl = atom(b"stbl", &atom(b"stsd", &full(0, &stsd)));
+        let mut mdia = atom(b"mdhd", &full(0, &mdhd));
+        mdia.extend(atom(b"hdlr", &full(0, &hdlr)));
+        mdia.extend(atom(b"minf", &stbl));
+        let mut tkhd = vec![0x00; 8];
+        tkhd.extend(1u32.to_be_bytes());
+        tkhd.extend([0x00; 72]);
+        let mut trak = atom(b"tkhd", &full(0, &tkhd));
+        trak.extend(atom(b"mdia", &mdia));
+
+        let mut ilst = atom(b"\xa9nam", &data(1, b"Title"));
+        ilst.extend(atom(b"\xa9ART", &data(1, b"Artist")));
+        ilst.extend(atom(b"\xa9alb", &data(1, b"Album")));
+        ilst.extend(atom(b"trkn", &data(0, &[0, 0, 0, 3, 0, 12, 0, 0])));
+        ilst.extend(atom(b"disk", &data(0, &[0, 0, 0, 1, 0, 2])));
+        ilst.extend(atom(b"gnre", &data(0, &[0, 18])));
+        ilst.extend(atom(b"tmpo", &data(21, &[0, 120])));
+        let mut covers = data(13, &[0xFF, 0xD8, 0xFF]);
+        covers.extend(data(14, &[0x89, 0x50, 0x4E, 0x47]));
+        ilst.extend(atom(b"covr", &covers));
+        let mut freeform = atom(b"mean", &full(0, b"com.apple.iTunes"));
+        freeform.extend(atom(b"name", &full(0, b"iTunSMPB")));
+        freeform.extend(data(1, b" 00000000 00000840"));
+        ilst.extend(atom(b"----", &freeform));
+        let mut meta = atom(b"hdlr", &full(0, &[0x00; 21]));
+        meta.extend(atom(b"ilst", &ilst));
+        let udta = atom(b"meta", &full(0, &meta));
+
+        let mut moov = atom(b"mvhd", &full(0, &mvhd));
+        moov.extend(atom(b"trak", &trak));
+        moov.extend(atom(b"udta", &udta));
+        let mut bytes = atom(b"ftyp", &ftyp);
+        bytes.extend(atom(b"moov", &moov));
+        bytes.extend(atom(b"mdat", &[0x00; 16]));
+        bytes
+    }
+
+    fn sample_entry(format: &[u8; 4], sample_rate: u32, child: Vec<u8>) -> Vec<u8> {
+        let mut entry = vec![0x00; 6];
+        entry.extend(1u16.to_be_bytes());
+        entry.extend([0x00; 8]);
+        entry.extend(2u16.to_be_bytes());
+        entry.extend(16u16.to_be_bytes());
+        entry.extend([0x00; 4]);
+        entry.extend((sample_rate << 16).to_be_bytes());
+        entry.extend(child);
+        atom(format, &entry)
+    }
+
+    #[test]
+    fn test_aac() {
+        // ES descriptor > decoder config > AAC LC, 44100 Hz, stereo
+        let mut esds = vec![0x03, 0x19, 0x00, 0x01, 0x00, 0x04, 0x11, 0x40, 0x15];
+        esds.extend([0x00; 3]);
+        esds.extend(320_000u32.to_be_bytes());
+        esds.extend(256_000u32.to_be_bytes());
+        esds.extend([0x05, 0x02, 0x12, 0x10]);
+        let entry = sample_entry(b"mp4a", 44100, atom(b"esds", &full(0, &esds)));
+        let bytes = m4a(entry);
+        let mp4 = Mp4::from_bytes(&bytes);
+        assert_eq!(mp4.major_brand(), "M4A ");
+        assert_eq!(mp4.compatible_brands(), &["M4A ", "isom", "mp42"]);
+        assert_eq!(mp4.duration(), Some(Duration::from_millis(180_500)));
+        let track = mp4.audio_track().unwrap();
+        assert_eq!(track.id(), 1);
+        assert_eq!(track.language(), "eng");
+        assert_eq!(track.duration(), Some(Duration::from_micros(180_500_000)));
+        let entry = track.sample_entry().unwrap();
+        assert_eq!(entry.format(), "mp4a");
+        assert_eq!(entry.channels(), 2);
+        assert_eq!(entry.sample_rate(), 44100);
+        assert_eq!(entry.average_bitrate(), 256_000);
+        let config = entry.decoder_config().unwrap();
+        assert_eq!(config.object_type_indication(), 0x40);
+        assert_eq!(config.audio_object_type(), Some(2));
+        assert_eq!(config.channel_configuration(), Some(2));
+
+        assert_eq!(mp4.text("©nam"), Some("Title"));
+        assert_eq!(mp4.text("©ART"), Some("Artist"));
+        let items = mp4.items();
+        assert!(items
+            .iter()
+            .any(|it| matches!(it, Mp4ParsedItem::TrackNumber((3, 12)))));
+        assert!(items
+            .iter()
+            .any(|it| matches!(it, Mp4ParsedItem::DiscNumber((1, 2)))));
+        assert!(items
+            .iter()
+            .any(|it| matches!(it, Mp4ParsedItem::Genre((17, name)) if name == "Rock")));
+        assert!(items
+            .iter()
+            .any(|it| matches!(it, Mp4ParsedItem::Integer((key, 120)) if key == "tmpo")));
+        let covers = items
+            .iter()
+            .filter_map(|it| match it {
+                Mp4ParsedItem::Cover(cover) => Some(cover.mime()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(covers, ["image/jpeg", "image/png"]);
+        let freeform = items
+            .iter()
+            .find_map(|it| match it {
+                Mp4ParsedItem::Freeform(freeform) => Some(freeform),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(freeform.key(), "com.apple.iTunes:iTunSMPB");
+        assert_eq!(freeform.value(), " 00000000 00000840");
+    }
+
+    #[test]
+    fn test_alac() {
+        let mut alac = 4096u32.to_be_bytes().to_vec();
+        alac.extend([0x00, 24, 40, 10, 14, 2]);
+        alac.extend(255u16.to_be_bytes());
+        alac.extend(0u32.to_be_bytes());
+        alac.extend(2_304_000u32.to_be_bytes());
+        alac.extend(96000u32.to_be_bytes());
+        // the 16.16 sample rate field can't hold 96000
+        let entry = sample_entry(b"alac", 0, atom(b"alac", &full(0, &alac)));
+        let bytes = m4a(entry);
+        let mp4 = Mp4::from_bytes(&bytes);
+        let entry = mp4.audio_track().unwrap().sample_entry().unwrap();
+        assert_eq!(entry.format(), "alac");
+        assert_eq!(entry.sample_rate(), 96000);
+        assert_eq!(entry.sample_size(), 24);
+        assert_eq!(entry.alac().unwrap().frame_length(), 4096);
+        assert_eq!(entry.average_bitrate(), 2_304_000);
+    }
+
+    #[test]
+    fn test_truncated_atoms() {
+        let mut trak = atom(b"tkhd", &full(1, &[0x00; 16]));
+        trak.extend(atom(b"mdia", &atom(b"mdhd", &full(1, &[0x00; 24]))));
+        let mut moov = atom(b"mvhd", &full(0, &[0x00; 12]));
+        moov.extend(atom(b"trak", &trak));
+        let mut bytes = atom(b"ftyp", b"M4A ");
+        bytes.extend(atom(b"moov", &moov));
+        let mp4 = Mp4::from_bytes(&bytes);
+        assert_eq!(mp4.major_brand(), "");
+        assert_eq!(mp4.duration(), None);
+        assert_eq!(mp4.tracks()[0].id(), 0);
+        assert_eq!(mp4.tracks()[0].timescale(), 0);
+
+        // version 0 mdhd without the language
+        let mdhd = atom(b"mdhd", &full(0, &[0x00; 16]));
+        let mut bytes = atom(b"ftyp", b"M4A \x00\x00\x00\x00");
+        bytes.extend(atom(b"moov", &atom(b"trak", &atom(b"mdia", &mdhd))));
+        let mp4 = Mp4::from_bytes(&bytes);
+        assert_eq!(mp4.tracks()[0].language(), "");
+
+        // ES descriptor cut after its tag, and a URL longer than the descriptor
+        for esds in [&[0x03, 0x00][..], &[0x03, 0x05, 0x00, 0x01, 0x40, 0x10]] {
+            let entry = sample_entry(b"mp4a", 44100, atom(b"esds", &full(0, esds)));
+            let bytes = m4a(entry);
+            let mp4 = Mp4::from_bytes(&bytes);
+            let entry = mp4.audio_track().unwrap().sample_entry().unwrap();
+            assert!(entry.decoder_config().is_none());
+        }
+    }
+
+    #[test]
+    fn test_cover_data() {
+        let png = png(600);
+        // declared as JPEG
+        let ilst = atom(b"covr", &data(13, &png));
+        let mut meta = atom(b"hdlr", &full(0, &[0x00; 21]));
+        meta.extend(atom(b"ilst", &ilst));
+        let moov = atom(b"udta", &atom(b"meta", &full(0, &meta)));
+        let mut bytes = atom(b"ftyp", b"M4A \x00\x00\x00\x00");
+        bytes.extend(atom(b"moov", &moov));
+
+        let mp4 = Mp4::from_bytes(&bytes);
+        let cover = mp4.front_cover().unwrap();
+        assert_eq!(cover.data(), png);
+        assert_eq!(cover.data().as_ptr(), bytes[bytes.len() - png.len()..].as_ptr());
+
+        let options = ParseOptions::new().skip_picture_data(true);
+        let mp4 = Mp4::from_bytes_with_options(&bytes, &options);
+        let cover = mp4.front_cover().unwrap();
+        assert!(cover.data().is_empty());
+        assert_eq!(cover.mime(), "image/png");
+        assert_eq!((cover.width(), cover.height()), (600, 600));
+    }
+}
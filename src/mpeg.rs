@@ -0,0 +1,1181 @@
+use crate::properties::{AudioProperties, Container};
+use crate::utils::{crc16, crc16_arc, crc16_bits, read_bits, ByteReader};
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+/// Bitrate tables in kbps, indexed by `[version_row][layer_row][bitrate_index]`
+const BITRATES: [[[u32; 15]; 3]; 2] = [
+    // MPEG-1
+    [
+        [
+            0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+        ],
+        [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+        ],
+        [
+            0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+        ],
+    ],
+    // MPEG-2 and MPEG-2.5
+    [
+        [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+        ],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    ],
+];
+
+const SAMPLE_RATES: [[u32; 3]; 3] = [
+    [44100, 48000, 32000], // MPEG-1
+    [22050, 24000, 16000], // MPEG-2
+    [11025, 12000, 8000],  // MPEG-2.5
+];
+
+/// Bits of the Layer II bit allocation of each subband, as runs of `(subbands, bits)`, for the
+/// allocation tables B.2a to B.2d of ISO 11172-3 and the MPEG-2 table of ISO 13818-3
+const LAYER2_ALLOCATION_BITS: [&[(usize, u32)]; 5] = [
+    &[(11, 4), (12, 3), (4, 2)],
+    &[(11, 4), (12, 3), (7, 2)],
+    &[(2, 4), (6, 3)],
+    &[(2, 4), (10, 3)],
+    &[(4, 4), (7, 3), (19, 2)],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MpegVersion {
+    Mpeg1,
+    Mpeg2,
+    Mpeg25,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MpegLayer {
+    Layer1,
+    Layer2,
+    Layer3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+    Stereo,
+    JointStereo,
+    DualChannel,
+    Mono,
+}
+
+/// MPEG audio frame header
+///
+/// Structure(32 bits, big endian)
+/// ```text
+/// AAAAAAAA AAABBCCD EEEEFFGH IIJJKLMM
+/// ```
+/// - A: Frame sync, all bits set.
+/// - B: Version, `00` MPEG-2.5, `10` MPEG-2, `11` MPEG-1.
+/// - C: Layer, `01` Layer III, `10` Layer II, `11` Layer I.
+/// - D: Protection bit, `0` means a CRC-16 follows the header.
+/// - E: Bitrate index.
+/// - F: Sample rate index.
+/// - G: Padding bit.
+/// - H: Private bit.
+/// - I: Channel mode.
+/// - J: Mode extension.
+/// - K: Copyright.
+/// - L: Original.
+/// - M: Emphasis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    version: MpegVersion,
+    layer: MpegLayer,
+    protected: bool,
+    bitrate_index: u8,
+    sample_rate_index: u8,
+    padding: bool,
+    channel_mode: ChannelMode,
+    mode_extension: u8,
+    copyright: bool,
+    original: bool,
+    emphasis: u8,
+}
+
+impl FrameHeader {
+    /// Decode a frame header, returns `None` if the bytes are not a valid header.
+    pub(crate) fn new(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 4 || bytes[0] != 0xFF || bytes[1] & 0xE0 != 0xE0 {
+            return None;
+        }
+        let version = match (bytes[1] >> 3) & 0x03 {
+            0b00 => MpegVersion::Mpeg25,
+            0b10 => MpegVersion::Mpeg2,
+            0b11 => MpegVersion::Mpeg1,
+            _ => return None,
+        };
+        let layer = match (bytes[1] >> 1) & 0x03 {
+            0b01 => MpegLayer::Layer3,
+            0b10 => MpegLayer::Layer2,
+            0b11 => MpegLayer::Layer1,
+            _ => return None,
+        };
+        let bitrate_index = bytes[2] >> 4;
+        let sample_rate_index = (bytes[2] >> 2) & 0x03;
+        if bitrate_index == 0x0F || sample_rate_index == 0x03 {
+            return None;
+        }
+        let channel_mode = match bytes[3] >> 6 {
+            0b00 => ChannelMode::Stereo,
+            0b01 => ChannelMode::JointStereo,
+            0b10 => ChannelMode::DualChannel,
+            _ => ChannelMode::Mono,
+        };
+        Some(Self {
+            version,
+            layer,
+            protected: bytes[1] & 0x01 == 0,
+            bitrate_index,
+            sample_rate_index,
+            padding: (bytes[2] >> 1) & 0x01 == 1,
+            channel_mode,
+            mode_extension: (bytes[3] >> 4) & 0x03,
+            copyright: (bytes[3] >> 3) & 0x01 == 1,
+            original: (bytes[3] >> 2) & 0x01 == 1,
+            emphasis: bytes[3] & 0x03,
+        })
+    }
+    pub fn version(&self) -> MpegVersion {
+        self.version
+    }
+    pub fn layer(&self) -> MpegLayer {
+        self.layer
+    }
+    /// Whether a CRC-16 follows the header
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+    pub fn channel_mode(&self) -> ChannelMode {
+        self.channel_mode
+    }
+    pub fn mode_extension(&self) -> u8 {
+        self.mode_extension
+    }
+    pub fn is_copyright(&self) -> bool {
+        self.copyright
+    }
+    pub fn is_original(&self) -> bool {
+        self.original
+    }
+    pub fn emphasis(&self) -> u8 {
+        self.emphasis
+    }
+    pub fn has_padding(&self) -> bool {
+        self.padding
+    }
+    pub fn channels(&self) -> u8 {
+        if self.channel_mode == ChannelMode::Mono {
+            1
+        } else {
+            2
+        }
+    }
+    /// Bitrate in kbps, `0` means free format
+    pub fn bitrate(&self) -> u32 {
+        let version_row = if self.version == MpegVersion::Mpeg1 {
+            0
+        } else {
+            1
+        };
+        let layer_row = match self.layer {
+            MpegLayer::Layer1 => 0,
+            MpegLayer::Layer2 => 1,
+            MpegLayer::Layer3 => 2,
+        };
+        BITRATES[version_row][layer_row][self.bitrate_index as usize]
+    }
+    pub fn is_free_format(&self) -> bool {
+        self.bitrate_index == 0
+    }
+    pub fn sample_rate(&self) -> u32 {
+        let row = match self.version {
+            MpegVersion::Mpeg1 => 0,
+            MpegVersion::Mpeg2 => 1,
+            MpegVersion::Mpeg25 => 2,
+        };
+        SAMPLE_RATES[row][self.sample_rate_index as usize]
+    }
+    pub fn samples_per_frame(&self) -> u32 {
+        match (self.layer, self.version) {
+            (MpegLayer::Layer1, _) => 384,
+            (MpegLayer::Layer2, _) | (MpegLayer::Layer3, MpegVersion::Mpeg1) => 1152,
+            (MpegLayer::Layer3, _) => 576,
+        }
+    }
+    /// Frame length in bytes including the header, `None` for free format frames
+    pub fn frame_length(&self) -> Option<usize> {
+        if self.is_free_format() {
+            return None;
+        }
+        Some(self.frame_length_with_bitrate(self.bitrate()))
+    }
+    fn frame_length_with_bitrate(&self, bitrate: u32) -> usize {
+        let slot_size = if self.layer == MpegLayer::Layer1 {
+            4
+        } else {
+            1
+        };
+        let slots = self.samples_per_frame() / 8 / slot_size * bitrate * 1000 / self.sample_rate();
+        (slots * slot_size) as usize + self.padding_length()
+    }
+    /// Length of the padding slot, if any
+    pub(crate) fn padding_length(&self) -> usize {
+        match (self.padding, self.layer) {
+            (false, _) => 0,
+            (true, MpegLayer::Layer1) => 4,
+            (true, _) => 1,
+        }
+    }
+    /// Side information length of Layer III frames
+    pub(crate) fn side_info_length(&self) -> usize {
+        match (self.version, self.channel_mode) {
+            (MpegVersion::Mpeg1, ChannelMode::Mono) => 17,
+            (MpegVersion::Mpeg1, _) => 32,
+            (_, ChannelMode::Mono) => 9,
+            (_, _) => 17,
+        }
+    }
+    /// First joint stereo subband of Layer I and II frames, 32 when there is none
+    pub(crate) fn stereo_bound(&self) -> usize {
+        if self.channel_mode == ChannelMode::JointStereo {
+            (self.mode_extension as usize + 1) * 4
+        } else {
+            32
+        }
+    }
+    /// Whether the other header belongs to the same stream
+    pub(crate) fn is_compatible(&self, other: &FrameHeader) -> bool {
+        self.version == other.version
+            && self.layer == other.layer
+            && self.sample_rate_index == other.sample_rate_index
+    }
+}
+
+/// Find the first valid frame header starting at `offset`, the next frame must also be valid
+/// unless the first one is the last frame in the buffer.
+pub(crate) fn find_first_frame(bytes: &[u8], offset: usize) -> Option<(usize, FrameHeader)> {
+    let mut cur = offset;
+    while cur + 4 <= bytes.len() {
+        if bytes[cur] != 0xFF {
+            cur += 1;
+            continue;
+        }
+        if let Some(header) = FrameHeader::new(&bytes[cur..]) {
+            let next = header
+                .frame_length()
+                .map(|len| cur + len)
+                .filter(|next| *next + 4 <= bytes.len());
+            match next {
+                Some(next) => {
+                    if FrameHeader::new(&bytes[next..])
+                        .map(|it| it.is_compatible(&header))
+                        .unwrap_or(false)
+                    {
+                        return Some((cur, header));
+                    }
+                }
+                None => return Some((cur, header)),
+            }
+        }
+        cur += 1;
+    }
+    None
+}
+
+/// MPEG audio stream information, taken from the first frame and its VBR headers
+#[derive(Debug)]
+pub struct MpegAudio {
+    offset: usize,
+    audio_size: usize,
+    header: FrameHeader,
+    xing: Option<XingHeader>,
+    vbri: Option<VbriHeader>,
+    scan: Option<FrameScan>,
+}
+
+impl MpegAudio {
+    /// Parse the first MPEG frame found at or after `offset`. `end` excludes trailing tags.
+    pub(crate) fn new(bytes: &[u8], offset: usize, end: usize, scan_frames: bool) -> Option<Self> {
+        let bytes = &bytes[..end];
+        let (offset, header) = find_first_frame(bytes, offset)?;
+        let frame = &bytes[offset..];
+        let xing = XingHeader::new(frame, &header);
+        let vbri = if xing.is_none() {
+            VbriHeader::new(frame)
+        } else {
+            None
+        };
+        // the Xing/Info or VBRI frame carries no audio
+        let scan = scan_frames
+            .then(|| FrameScan::new(bytes, offset, header, xing.is_some() || vbri.is_some()));
+        Some(Self {
+            offset,
+            audio_size: end - offset,
+            header,
+            xing,
+            vbri,
+            scan,
+        })
+    }
+    /// Offset of the first frame
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+    /// Size of the audio data, starting at the first frame
+    pub fn audio_size(&self) -> usize {
+        self.audio_size
+    }
+    pub fn header(&self) -> &FrameHeader {
+        &self.header
+    }
+    pub fn xing(&self) -> Option<&XingHeader> {
+        self.xing.as_ref()
+    }
+    pub fn vbri(&self) -> Option<&VbriHeader> {
+        self.vbri.as_ref()
+    }
+    pub fn lame(&self) -> Option<&LameTag> {
+        self.xing.as_ref().and_then(|it| it.lame.as_ref())
+    }
+    /// Result of walking every frame, only present if `ParseOptions::scan_frames` is set
+    pub fn frame_scan(&self) -> Option<&FrameScan> {
+        self.scan.as_ref()
+    }
+    /// Number of audio frames declared by the Xing/Info or VBRI header
+    pub fn frame_count(&self) -> Option<u32> {
+        self.xing
+            .as_ref()
+            .and_then(|it| it.frames)
+            .or(self.vbri.as_ref().map(|it| it.frames))
+    }
+    /// Total number of samples per channel, including encoder delay and padding
+    pub fn total_samples(&self) -> Option<u64> {
+        self.frame_count()
+            .map(|frames| frames as u64 * self.header.samples_per_frame() as u64)
+    }
+    /// Number of samples per channel without encoder delay and padding
+    pub fn gapless_samples(&self) -> Option<u64> {
+        let total = self.total_samples()?;
+        match self.lame() {
+            Some(lame) => {
+                Some(total.saturating_sub(lame.encoder_delay as u64 + lame.padding as u64))
+            }
+            None => Some(total),
+        }
+    }
+    /// Duration from the frame scan or the VBR header, falls back to the bitrate of the first frame
+    pub fn duration(&self) -> Option<Duration> {
+        let sample_rate = self.header.sample_rate() as u64;
+        if let Some(scan) = &self.scan {
+            return Some(scan.duration());
+        }
+        if let Some(samples) = self.total_samples() {
+            return Some(Duration::from_micros(samples * 1_000_000 / sample_rate));
+        }
+        match self.header.bitrate() as u64 {
+            0 => None,
+            bitrate => Some(Duration::from_micros(
+                self.audio_size as u64 * 8 * 1000 / bitrate,
+            )),
+        }
+    }
+    /// Average bitrate in kbps
+    pub fn bitrate(&self) -> Option<u32> {
+        let bytes = self
+            .xing
+            .as_ref()
+            .and_then(|it| it.bytes)
+            .or(self.vbri.as_ref().map(|it| it.bytes))
+            .map(|it| it as u64);
+        match (bytes, self.total_samples()) {
+            (Some(bytes), Some(samples)) if samples > 0 => {
+                Some((bytes * 8 * self.header.sample_rate() as u64 / samples / 1000) as u32)
+            }
+            _ => match self.header.bitrate() {
+                0 => None,
+                bitrate => Some(bitrate),
+            },
+        }
+    }
+    pub fn properties(&self) -> AudioProperties {
+        let codec = match self.header.layer() {
+            MpegLayer::Layer1 => "MP1",
+            MpegLayer::Layer2 => "MP2",
+            MpegLayer::Layer3 => "MP3",
+        };
+        AudioProperties::new(Container::Mpeg, codec)
+            .with_duration(self.duration())
+            .with_sample_rate(self.header.sample_rate())
+            .with_channels(self.header.channels() as u16)
+            .with_bitrate(self.bitrate())
+    }
+}
+
+/// Result of walking every MPEG frame of the stream
+#[derive(Debug, Default)]
+pub struct FrameScan {
+    frames: u64,
+    samples: u64,
+    sample_rate: u32,
+    /// Frame length of a free format stream without padding
+    free_format_length: Option<usize>,
+    free_format_bitrate: Option<u32>,
+    sync_losses: Vec<usize>,
+    junk_bytes: usize,
+    truncated_frame: Option<usize>,
+    crc_checked: u64,
+    crc_failures: Vec<usize>,
+}
+
+impl FrameScan {
+    pub(crate) fn new(bytes: &[u8], offset: usize, first: FrameHeader, skip_first: bool) -> Self {
+        let mut scan = FrameScan {
+            sample_rate: first.sample_rate(),
+            ..Default::default()
+        };
+        if first.is_free_format() {
+            scan.free_format_length = FrameScan::detect_free_format_length(bytes, offset, &first);
+            scan.free_format_bitrate = scan.free_format_length.map(|length| {
+                (length as u64 * 8 * first.sample_rate() as u64
+                    / first.samples_per_frame() as u64
+                    / 1000) as u32
+            });
+        }
+        let mut cur = offset;
+        let mut is_first = true;
+        while cur < bytes.len() {
+            let header = match FrameHeader::new(&bytes[cur..]).filter(|it| it.is_compatible(&first))
+            {
+                Some(header) => header,
+                None => {
+                    scan.sync_losses.push(cur);
+                    match find_first_frame(bytes, cur + 1)
+                        .filter(|(_, it)| it.is_compatible(&first))
+                    {
+                        Some((next, _)) => {
+                            scan.junk_bytes += next - cur;
+                            cur = next;
+                            continue;
+                        }
+                        None => {
+                            scan.junk_bytes += bytes.len() - cur;
+                            break;
+                        }
+                    }
+                }
+            };
+            let length = match (header.frame_length(), scan.free_format_length) {
+                (Some(length), _) => length,
+                (None, Some(length)) => length + header.padding_length(),
+                (None, None) => {
+                    // free format without a detectable frame length
+                    scan.junk_bytes += bytes.len() - cur;
+                    break;
+                }
+            };
+            if cur + length > bytes.len() {
+                scan.truncated_frame = Some(cur);
+                break;
+            }
+            let frame = &bytes[cur..cur + length];
+            let bitrate = if header.is_free_format() {
+                scan.free_format_bitrate
+            } else {
+                Some(header.bitrate())
+            };
+            if let Some(valid) = FrameScan::check_crc(frame, &header, bitrate) {
+                scan.crc_checked += 1;
+                if !valid {
+                    scan.crc_failures.push(cur);
+                }
+            }
+            if !(is_first && skip_first) {
+                scan.frames += 1;
+                scan.samples += header.samples_per_frame() as u64;
+            }
+            is_first = false;
+            cur += length;
+        }
+        scan
+    }
+    /// Free format frames have no bitrate index, the frame length is the distance to the next
+    /// frame with the same header.
+    fn detect_free_format_length(
+        bytes: &[u8],
+        offset: usize,
+        header: &FrameHeader,
+    ) -> Option<usize> {
+        let mut cur = offset + 4;
+        while cur + 4 <= bytes.len() {
+            // same header except the padding bit
+            if bytes[cur] == 0xFF
+                && bytes[cur + 1] == bytes[offset + 1]
+                && bytes[cur + 2] & 0xFD == bytes[offset + 2] & 0xFD
+            {
+                return Some(cur - offset - header.padding_length());
+            }
+            cur += 1;
+        }
+        None
+    }
+    /// CRC-16 covers the last two bytes of the header and the bits following the CRC: the side
+    /// information of Layer III, the bit allocation of Layer I, the bit allocation and scale
+    /// factor selection of Layer II. Free format Layer II frames of an unknown bitrate are not
+    /// checked, their allocation table depends on it.
+    fn check_crc(frame: &[u8], header: &FrameHeader, bitrate: Option<u32>) -> Option<bool> {
+        if !header.protected {
+            return None;
+        }
+        let bits = match header.layer {
+            MpegLayer::Layer1 => {
+                let bound = header.stereo_bound();
+                4 * (header.channels() as usize * bound + 32 - bound)
+            }
+            MpegLayer::Layer2 => FrameScan::layer2_protected_bits(frame, header, bitrate?),
+            MpegLayer::Layer3 => header.side_info_length() * 8,
+        };
+        if frame.len() < 6 + bits.div_ceil(8) {
+            return Some(false);
+        }
+        let crc = crc16_bits(&frame[6..], bits, crc16(&frame[2..4], 0xFFFF));
+        Some(crc == u16::from_be_bytes([frame[4], frame[5]]))
+    }
+    /// Length of the Layer II bit allocation and scale factor selection, the latter only exists
+    /// for the subbands with allocated bits
+    fn layer2_protected_bits(frame: &[u8], header: &FrameHeader, bitrate: u32) -> usize {
+        let channels = header.channels() as usize;
+        let channel_bitrate = bitrate / channels as u32;
+        let sample_rate = header.sample_rate();
+        let table = if header.version != MpegVersion::Mpeg1 {
+            4
+        } else if (sample_rate == 48000 && channel_bitrate >= 56)
+            || (56..=80).contains(&channel_bitrate)
+        {
+            0
+        } else if sample_rate != 48000 && channel_bitrate >= 96 {
+            1
+        } else if sample_rate != 32000 && channel_bitrate <= 48 {
+            2
+        } else {
+            3
+        };
+        let bound = header.stereo_bound();
+        let mut position = 6 * 8;
+        let mut scfsi_bits = 0;
+        let subbands = LAYER2_ALLOCATION_BITS[table]
+            .iter()
+            .flat_map(|&(count, bits)| std::iter::repeat_n(bits, count));
+        for (subband, bits) in subbands.enumerate() {
+            // the joint stereo subbands share one allocation between the channels
+            let coded = if subband < bound { channels } else { 1 };
+            for _ in 0..coded {
+                if read_bits(frame, &mut position, bits) != 0 {
+                    scfsi_bits += 2 * channels / coded;
+                }
+            }
+        }
+        position - 6 * 8 + scfsi_bits
+    }
+    /// Number of audio frames, excluding the Xing/Info or VBRI frame
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+    /// Number of samples per channel
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+    pub fn duration(&self) -> Duration {
+        Duration::from_micros(self.samples * 1_000_000 / self.sample_rate as u64)
+    }
+    /// Detected bitrate in kbps of a free format stream
+    pub fn free_format_bitrate(&self) -> Option<u32> {
+        self.free_format_bitrate
+    }
+    /// Offsets where a frame header was expected but not found
+    pub fn sync_losses(&self) -> &[usize] {
+        &self.sync_losses
+    }
+    /// Number of bytes skipped to regain sync
+    pub fn junk_bytes(&self) -> usize {
+        self.junk_bytes
+    }
+    /// Offset of the final frame if it is cut short
+    pub fn truncated_frame(&self) -> Option<usize> {
+        self.truncated_frame
+    }
+    /// Number of frames whose CRC-16 was checked
+    pub fn crc_checked(&self) -> u64 {
+        self.crc_checked
+    }
+    /// Offsets of frames whose CRC-16 does not match
+    pub fn crc_failures(&self) -> &[usize] {
+        &self.crc_failures
+    }
+    /// Whether the stream has no sync loss, junk, truncation or CRC failure
+    pub fn is_intact(&self) -> bool {
+        self.sync_losses.is_empty()
+            && self.junk_bytes == 0
+            && self.truncated_frame.is_none()
+            && self.crc_failures.is_empty()
+    }
+}
+
+/// Xing/Info VBR header, written into the first frame by LAME and others
+///
+/// Structure
+/// ```text
+/// | Xing/Info | F | [N] | [B] | [T] | [Q] | [LAME]
+/// ```
+/// - F: Flags, 4 Bytes, bit 0 frames, bit 1 bytes, bit 2 TOC, bit 3 quality.
+/// - N: Number of frames, 4 Bytes.
+/// - B: Number of bytes, 4 Bytes.
+/// - T: Table of contents, 100 Bytes.
+/// - Q: Quality indicator, 4 Bytes.
+pub struct XingHeader {
+    /// `Info` is written for CBR files, `Xing` for VBR files
+    is_info: bool,
+    frames: Option<u32>,
+    bytes: Option<u32>,
+    toc: Option<[u8; 100]>,
+    quality: Option<u32>,
+    lame: Option<LameTag>,
+}
+
+impl Debug for XingHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("XingHeader")
+            .field("is_info", &self.is_info)
+            .field("frames", &self.frames)
+            .field("bytes", &self.bytes)
+            .field("toc", &self.toc.map(|_| "[..](100)"))
+            .field("quality", &self.quality)
+            .field("lame", &self.lame)
+            .finish()
+    }
+}
+
+impl XingHeader {
+    pub(crate) fn new(frame: &[u8], header: &FrameHeader) -> Option<Self> {
+        let start = 4 + if header.protected { 2 } else { 0 } + header.side_info_length();
+        let is_info = match frame.get(start..start + 4)? {
+            b"Xing" => false,
+            b"Info" => true,
+            _ => return None,
+        };
+        let mut reader = ByteReader::with_offset(frame, start + 4);
+        let has = |needed: usize, reader: &ByteReader| reader.offset() + needed <= frame.len();
+        if !has(4, &reader) {
+            return None;
+        }
+        let flags = reader.read_next_u32(true);
+        let frames = (flags & 0x01 != 0 && has(4, &reader)).then(|| reader.read_next_u32(true));
+        let bytes = (flags & 0x02 != 0 && has(4, &reader)).then(|| reader.read_next_u32(true));
+        let toc =
+            (flags & 0x04 != 0 && has(100, &reader)).then(|| reader.read(100).try_into().unwrap());
+        let quality = (flags & 0x08 != 0 && has(4, &reader)).then(|| reader.read_next_u32(true));
+        let lame = LameTag::new(frame, reader.offset());
+        Some(Self {
+            is_info,
+            frames,
+            bytes,
+            toc,
+            quality,
+            lame,
+        })
+    }
+    pub fn is_info(&self) -> bool {
+        self.is_info
+    }
+    pub fn frames(&self) -> Option<u32> {
+        self.frames
+    }
+    pub fn bytes(&self) -> Option<u32> {
+        self.bytes
+    }
+    /// Seek table, entry `i` is the position of `i%` of the duration in 1/256 of the file size
+    pub fn toc(&self) -> Option<&[u8; 100]> {
+        self.toc.as_ref()
+    }
+    /// Quality indicator, from 0 (best) to 100 (worst)
+    pub fn quality(&self) -> Option<u32> {
+        self.quality
+    }
+    pub fn lame(&self) -> Option<&LameTag> {
+        self.lame.as_ref()
+    }
+}
+
+/// Fraunhofer VBRI header, always 32 bytes after the frame header
+///
+/// Structure
+/// ```text
+/// | VBRI | V | D | Q | B | F | E | S | Z | P | ...T
+/// ```
+/// - V: Version, 2 Bytes.
+/// - D: Delay, 2 Bytes.
+/// - Q: Quality, 2 Bytes.
+/// - B: Number of bytes, 4 Bytes.
+/// - F: Number of frames, 4 Bytes.
+/// - E: Number of TOC entries, 2 Bytes.
+/// - S: Scale factor of TOC entries, 2 Bytes.
+/// - Z: Size of a TOC entry in bytes, 2 Bytes.
+/// - P: Frames per TOC entry, 2 Bytes.
+/// - T: TOC entries.
+#[derive(Debug)]
+pub struct VbriHeader {
+    version: u16,
+    delay: u16,
+    quality: u16,
+    bytes: u32,
+    frames: u32,
+    toc_scale: u16,
+    frames_per_entry: u16,
+    toc: Vec<u32>,
+}
+
+impl VbriHeader {
+    pub(crate) fn new(frame: &[u8]) -> Option<Self> {
+        const START: usize = 4 + 32;
+        if frame.get(START..START + 4)? != b"VBRI" || frame.len() < START + 26 {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(frame, START + 4);
+        let version = reader.read_next_u16(true);
+        let delay = reader.read_next_u16(true);
+        let quality = reader.read_next_u16(true);
+        let bytes = reader.read_next_u32(true);
+        let frames = reader.read_next_u32(true);
+        let entries = reader.read_next_u16(true) as usize;
+        let toc_scale = reader.read_next_u16(true);
+        let entry_size = reader.read_next_u16(true) as usize;
+        let frames_per_entry = reader.read_next_u16(true);
+        let toc = if (1..=4).contains(&entry_size)
+            && reader.offset() + entries * entry_size <= frame.len()
+        {
+            reader
+                .read(entries * entry_size)
+                .chunks(entry_size)
+                .map(|it| it.iter().fold(0u32, |a, b| (a << 8) | *b as u32))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Some(Self {
+            version,
+            delay,
+            quality,
+            bytes,
+            frames,
+            toc_scale,
+            frames_per_entry,
+            toc,
+        })
+    }
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+    pub fn delay(&self) -> u16 {
+        self.delay
+    }
+    pub fn quality(&self) -> u16 {
+        self.quality
+    }
+    pub fn bytes(&self) -> u32 {
+        self.bytes
+    }
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+    pub fn toc_scale(&self) -> u16 {
+        self.toc_scale
+    }
+    pub fn frames_per_entry(&self) -> u16 {
+        self.frames_per_entry
+    }
+    /// TOC entries, each is the byte size of `frames_per_entry` frames divided by `toc_scale`
+    pub fn toc(&self) -> &[u32] {
+        &self.toc
+    }
+}
+
+/// ReplayGain value stored in the LAME tag
+///
+/// Structure(16 bits)
+/// ```text
+/// NNNOOOSG GGGGGGGG
+/// ```
+/// - N: Name code, `1` radio(track), `2` audiophile(album).
+/// - O: Originator code.
+/// - S: Sign bit.
+/// - G: Absolute gain in 1/10 dB.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LameReplayGain {
+    name: u8,
+    originator: u8,
+    gain: f32,
+}
+
+impl LameReplayGain {
+    fn new(value: u16) -> Option<Self> {
+        let name = (value >> 13) as u8;
+        if name == 0 {
+            return None;
+        }
+        let gain = (value & 0x01FF) as f32 / 10.0;
+        Some(Self {
+            name,
+            originator: ((value >> 10) & 0x07) as u8,
+            gain: if value & 0x0200 != 0 { -gain } else { gain },
+        })
+    }
+    /// `1` radio(track) gain, `2` audiophile(album) gain
+    pub fn name(&self) -> u8 {
+        self.name
+    }
+    /// `1` set by artist, `2` set by user, `3` set automatically
+    pub fn originator(&self) -> u8 {
+        self.originator
+    }
+    /// Gain adjustment in dB
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+}
+
+/// LAME extension of the Xing/Info header
+///
+/// Structure(36 Bytes)
+/// ```text
+/// | E | R | L | P | TG | AG | F | B | DP | M | G | S | ML | MC | C
+/// ```
+/// - E: Encoder version string, 9 Bytes.
+/// - R: Tag revision(4 bits) and VBR method(4 bits).
+/// - L: Lowpass frequency in 100 Hz, 1 Byte.
+/// - P: Peak signal amplitude, 4 Bytes, fixed point with 23 fractional bits.
+/// - TG: Radio ReplayGain, 2 Bytes.
+/// - AG: Audiophile ReplayGain, 2 Bytes.
+/// - F: Encoding flags(4 bits) and ATH type(4 bits).
+/// - B: ABR bitrate or minimal VBR bitrate, 1 Byte.
+/// - DP: Encoder delay(12 bits) and padding(12 bits).
+/// - M: Misc: noise shaping, stereo mode, unwise settings, source sample rate.
+/// - G: MP3 gain, signed, 1.5 dB steps.
+/// - S: Surround info(3 bits) and preset(11 bits), 2 Bytes.
+/// - ML: Music length in bytes, 4 Bytes.
+/// - MC: CRC-16 of the music data, 2 Bytes.
+/// - C: CRC-16 of the first 190 bytes of the frame, 2 Bytes.
+#[derive(Debug)]
+pub struct LameTag {
+    encoder: String,
+    revision: u8,
+    vbr_method: u8,
+    lowpass: u32,
+    peak: f32,
+    track_gain: Option<LameReplayGain>,
+    album_gain: Option<LameReplayGain>,
+    encoding_flags: u8,
+    ath_type: u8,
+    bitrate: u8,
+    encoder_delay: u16,
+    padding: u16,
+    misc: u8,
+    mp3_gain: i8,
+    surround: u8,
+    preset: u16,
+    music_length: u32,
+    music_crc: u16,
+    tag_crc: u16,
+    crc_valid: bool,
+}
+
+impl LameTag {
+    const SIZE: usize = 36;
+
+    pub(crate) fn new(frame: &[u8], start: usize) -> Option<Self> {
+        if start + Self::SIZE > frame.len() {
+            return None;
+        }
+        let encoder = &frame[start..start + 9];
+        if !(encoder.starts_with(b"LAME")
+            || encoder.starts_with(b"L3.99")
+            || encoder.starts_with(b"Lavc"))
+        {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(frame, start);
+        let encoder = reader
+            .read_uft8_string(9)
+            .trim_end_matches(['\u{0000}', ' '])
+            .to_string();
+        let revision_method = reader.read_next_u8();
+        let lowpass = reader.read_next_u8() as u32 * 100;
+        let peak = reader.read_next_u32(true) as f32 / (1 << 23) as f32;
+        let track_gain = LameReplayGain::new(reader.read_next_u16(true));
+        let album_gain = LameReplayGain::new(reader.read_next_u16(true));
+        let flags_ath = reader.read_next_u8();
+        let bitrate = reader.read_next_u8();
+        let (encoder_delay, padding) = {
+            let bytes = reader.read(3);
+            (
+                ((bytes[0] as u16) << 4) | (bytes[1] >> 4) as u16,
+                (((bytes[1] & 0x0F) as u16) << 8) | bytes[2] as u16,
+            )
+        };
+        let misc = reader.read_next_u8();
+        let mp3_gain = reader.read_next_u8() as i8;
+        let surround_preset = reader.read_next_u16(true);
+        let music_length = reader.read_next_u32(true);
+        let music_crc = reader.read_next_u16(true);
+        let crc_pos = reader.offset();
+        let tag_crc = reader.read_next_u16(true);
+        Some(Self {
+            encoder,
+            revision: revision_method >> 4,
+            vbr_method: revision_method & 0x0F,
+            lowpass,
+            peak,
+            track_gain,
+            album_gain,
+            encoding_flags: flags_ath >> 4,
+            ath_type: flags_ath & 0x0F,
+            bitrate,
+            encoder_delay,
+            padding,
+            misc,
+            mp3_gain,
+            surround: ((surround_preset >> 11) & 0x07) as u8,
+            preset: surround_preset & 0x07FF,
+            music_length,
+            music_crc,
+            tag_crc,
+            crc_valid: crc16_arc(&frame[..crc_pos]) == tag_crc,
+        })
+    }
+    /// Encoder version string, e.g. `LAME3.100`
+    pub fn encoder(&self) -> &str {
+        &self.encoder
+    }
+    pub fn revision(&self) -> u8 {
+        self.revision
+    }
+    /// VBR method
+    ///
+    /// - 1: CBR
+    /// - 2: ABR
+    /// - 3-6: VBR
+    /// - 8: CBR 2 pass
+    /// - 9: ABR 2 pass
+    pub fn vbr_method(&self) -> u8 {
+        self.vbr_method
+    }
+    /// Lowpass filter frequency in Hz, `0` if unknown
+    pub fn lowpass(&self) -> u32 {
+        self.lowpass
+    }
+    /// Peak signal amplitude, `1.0` is full scale, `0.0` if unknown
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+    pub fn track_gain(&self) -> Option<&LameReplayGain> {
+        self.track_gain.as_ref()
+    }
+    pub fn album_gain(&self) -> Option<&LameReplayGain> {
+        self.album_gain.as_ref()
+    }
+    /// - bit 0: nspsytune
+    /// - bit 1: nssafejoint
+    /// - bit 2: nogap continued
+    /// - bit 3: nogap continuation
+    pub fn encoding_flags(&self) -> u8 {
+        self.encoding_flags
+    }
+    pub fn ath_type(&self) -> u8 {
+        self.ath_type
+    }
+    /// ABR target bitrate or minimal VBR bitrate in kbps, `255` means 255 or higher
+    pub fn bitrate(&self) -> u8 {
+        self.bitrate
+    }
+    /// Samples added by the encoder at the start
+    pub fn encoder_delay(&self) -> u16 {
+        self.encoder_delay
+    }
+    /// Samples added by the encoder at the end
+    pub fn padding(&self) -> u16 {
+        self.padding
+    }
+    /// Noise shaping(2 bits), stereo mode(3 bits), unwise settings(1 bit), source sample rate(2 bits)
+    pub fn misc(&self) -> u8 {
+        self.misc
+    }
+    /// MP3 gain in 1.5 dB steps
+    pub fn mp3_gain(&self) -> i8 {
+        self.mp3_gain
+    }
+    pub fn surround(&self) -> u8 {
+        self.surround
+    }
+    /// LAME preset, e.g. `1000`-`1007` for `--preset` V0-V7 and `320` for insane
+    pub fn preset(&self) -> u16 {
+        self.preset
+    }
+    /// Size of the audio data including the Info frame
+    pub fn music_length(&self) -> u32 {
+        self.music_length
+    }
+    pub fn music_crc(&self) -> u16 {
+        self.music_crc
+    }
+    pub fn tag_crc(&self) -> u16 {
+        self.tag_crc
+    }
+    /// Whether the tag CRC matches the first 190 bytes of the frame
+    pub fn is_crc_valid(&self) -> bool {
+        self.crc_valid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MPEG-1 Layer III, 128 kbps, 44.1 kHz, joint stereo, 417 bytes
+    const HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x44];
+
+    fn info_frame() -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&HEADER);
+        let mut cur = 4 + 32;
+        frame[cur..cur + 4].copy_from_slice(b"Xing");
+        frame[cur + 4..cur + 8].copy_from_slice(&0x0Fu32.to_be_bytes());
+        frame[cur + 8..cur + 12].copy_from_slice(&1000u32.to_be_bytes());
+        frame[cur + 12..cur + 16].copy_from_slice(&300_000u32.to_be_bytes());
+        frame[cur + 116..cur + 120].copy_from_slice(&78u32.to_be_bytes());
+        cur += 120;
+        frame[cur..cur + 9].copy_from_slice(b"LAME3.100");
+        frame[cur + 9] = 0x03;
+        frame[cur + 10] = 195;
+        // radio gain, set automatically, -2.3 dB
+        frame[cur + 15..cur + 17].copy_from_slice(&0x2E17u16.to_be_bytes());
+        // delay 576, padding 1234
+        frame[cur + 21..cur + 24].copy_from_slice(&[0x24, 0x04, 0xD2]);
+        frame[cur + 26..cur + 28].copy_from_slice(&1004u16.to_be_bytes());
+        let crc = crc16_arc(&frame[..cur + 34]);
+        frame[cur + 34..cur + 36].copy_from_slice(&crc.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_frame_header() {
+        let header = FrameHeader::new(&HEADER).unwrap();
+        assert_eq!(header.version(), MpegVersion::Mpeg1);
+        assert_eq!(header.layer(), MpegLayer::Layer3);
+        assert_eq!(header.bitrate(), 128);
+        assert_eq!(header.sample_rate(), 44100);
+        assert_eq!(header.channel_mode(), ChannelMode::JointStereo);
+        assert_eq!(header.frame_length(), Some(417));
+        assert!(FrameHeader::new(&[0xFF, 0xFB, 0xF0, 0x44]).is_none());
+    }
+
+    #[test]
+    fn test_xing_lame() {
+        let mut bytes = info_frame();
+        bytes.extend_from_slice(&HEADER);
+        let mpeg = MpegAudio::new(&bytes, 0, bytes.len(), false).unwrap();
+        let xing = mpeg.xing().unwrap();
+        assert!(!xing.is_info());
+        assert_eq!(xing.frames(), Some(1000));
+        assert_eq!(xing.bytes(), Some(300_000));
+        assert_eq!(xing.quality(), Some(78));
+        let lame = mpeg.lame().unwrap();
+        assert_eq!(lame.encoder(), "LAME3.100");
+        assert_eq!(lame.vbr_method(), 3);
+        assert_eq!(lame.lowpass(), 19500);
+        assert_eq!(lame.track_gain().unwrap().gain(), -2.3);
+        assert_eq!(lame.encoder_delay(), 576);
+        assert_eq!(lame.padding(), 1234);
+        assert_eq!(lame.preset(), 1004);
+        assert!(lame.is_crc_valid());
+        assert_eq!(mpeg.total_samples(), Some(1_152_000));
+        assert_eq!(mpeg.gapless_samples(), Some(1_152_000 - 576 - 1234));
+        assert_eq!(mpeg.duration(), Some(Duration::from_micros(26_122_448)));
+        assert_eq!(mpeg.bitrate(), Some(91));
+    }
+
+    /// Protected frame with a valid CRC-16 over the zeroed side information
+    fn protected_frame() -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFA, 0x90, 0x44]);
+        let crc = crc16(&frame[6..38], crc16(&frame[2..4], 0xFFFF));
+        frame[4..6].copy_from_slice(&crc.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_frame_scan() {
+        let mut bytes = info_frame();
+        bytes.extend(protected_frame());
+        let mut corrupted = protected_frame();
+        corrupted[20] = 0x55;
+        bytes.extend(corrupted);
+        bytes.extend([0x00; 5]);
+        bytes.extend(protected_frame());
+        bytes.extend(&protected_frame()[..100]);
+        let mpeg = MpegAudio::new(&bytes, 0, bytes.len(), true).unwrap();
+        let scan = mpeg.frame_scan().unwrap();
+        assert_eq!(scan.frames(), 3);
+        assert_eq!(scan.samples(), 3 * 1152);
+        assert_eq!(scan.sync_losses(), &[417 * 3]);
+        assert_eq!(scan.junk_bytes(), 5);
+        assert_eq!(scan.truncated_frame(), Some(417 * 4 + 5));
+        assert_eq!(scan.crc_checked(), 3);
+        assert_eq!(scan.crc_failures(), &[417 * 2]);
+        assert!(!scan.is_intact());
+        assert_eq!(mpeg.duration(), Some(scan.duration()));
+    }
+
+    #[test]
+    fn test_layer1_layer2_crc() {
+        // MPEG-1 Layer II, 192 kbps, 44.1 kHz, stereo, allocation table B.2b of 30 subbands,
+        // the first subband of both channels is allocated: 2 * 94 allocation bits followed by
+        // 2 * 2 scale factor selection bits, 24 bytes in total
+        let mut frame = vec![0u8; 626];
+        frame[..4].copy_from_slice(&[0xFF, 0xFC, 0xA0, 0x00]);
+        frame[6] = 0x11;
+        frame[29] = 0x05;
+        frame[40] = 0x55;
+        let crc = crc16(&frame[6..30], crc16(&frame[2..4], 0xFFFF));
+        frame[4..6].copy_from_slice(&crc.to_be_bytes());
+        let mut corrupted = frame.clone();
+        corrupted[29] = 0x06;
+        // MPEG-1 Layer I, 32 kbps, 44.1 kHz, mono, 32 allocations of 4 bits
+        let mut layer1 = vec![0u8; 32];
+        layer1[..4].copy_from_slice(&[0xFF, 0xFE, 0x10, 0xC0]);
+        layer1[6] = 0x12;
+        let crc = crc16(&layer1[6..22], crc16(&layer1[2..4], 0xFFFF));
+        layer1[4..6].copy_from_slice(&crc.to_be_bytes());
+
+        let mut bytes = frame.clone();
+        bytes.extend(&corrupted);
+        bytes.extend(&frame);
+        let header = FrameHeader::new(&bytes).unwrap();
+        assert_eq!(header.layer(), MpegLayer::Layer2);
+        assert_eq!(header.frame_length(), Some(626));
+        let scan = FrameScan::new(&bytes, 0, header, false);
+        assert_eq!(scan.frames(), 3);
+        assert_eq!(scan.crc_checked(), 3);
+        assert_eq!(scan.crc_failures(), &[626]);
+
+        let header = FrameHeader::new(&layer1).unwrap();
+        assert_eq!(header.frame_length(), Some(32));
+        let scan = FrameScan::new(&layer1, 0, header, false);
+        assert_eq!(scan.crc_checked(), 1);
+        assert!(scan.is_intact());
+    }
+}
@@ -1,90 +1,519 @@
+use crate::error::Error;
+use crate::options::ParseOptions;
+use crate::properties::{AudioProperties, Container};
 use crate::reader::Reader;
-use crate::utils::{crc32, debug_vec, ByteReader};
+use crate::utils::{crc32_ogg, debug_vec, ByteReader};
 use crate::vorbis_comment::VorbisComment;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Formatter};
-use crate::base64::decode_base64;
-use crate::flac::Picture;
+use std::time::Duration;
+use crate::base64::{decode_base64, decode_base64_prefix};
+use crate::flac::{Block, FlacParsedBlock, StreamInfo};
+use crate::picture::{front_cover, Picture};
 
 const OGG_SIGNATURE: [u8; 4] = [0x4f, 0x67, 0x67, 0x53];
+const PICTURE_COMMENT: &str = "METADATA_BLOCK_PICTURE";
+/// Bytes of the image decoded to sniff its header when the picture data is skipped, large enough
+/// for the APP segments of a JPEG before its frame header
+const IMAGE_HEADER_LENGTH: usize = 64 * 1024;
 
+/// Ogg file, made of one or more chained links
+///
+/// Each link holds one or more multiplexed logical streams, distinguished by their serial number.
+/// A chained file (e.g. concatenated files or internet radio dumps) starts a new link every time
+/// a group of BOS pages follows the end of the previous link.
 #[derive(Debug)]
 pub struct Ogg {
+    links: Vec<ChainLink>,
+    /// CRC check result of every page, in file order
+    integrity: Vec<PageIntegrity>,
+    file_size: usize,
+}
+
+/// CRC check result of an Ogg page
+#[derive(Debug, Clone)]
+pub struct PageIntegrity {
+    offset: usize,
+    serial_number: u32,
+    sequence_number: u32,
+    checksum: u32,
+    computed_checksum: u32,
+}
+
+impl PageIntegrity {
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+    pub fn serial_number(&self) -> u32 {
+        self.serial_number
+    }
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+    pub fn computed_checksum(&self) -> u32 {
+        self.computed_checksum
+    }
+    pub fn is_valid(&self) -> bool {
+        self.checksum == self.computed_checksum
+    }
+}
+
+/// A group of logical streams which are multiplexed together
+#[derive(Debug)]
+pub struct ChainLink {
+    streams: Vec<LogicalStream>,
+}
+
+/// Logical bitstream, identified by its serial number
+#[derive(Debug)]
+pub struct LogicalStream {
+    serial_number: u32,
+    /// Parsed header packets
     pages: Vec<OggParsedPage>,
+    /// Granule position of the last page which finishes a packet
+    last_granule_position: Option<u64>,
+    /// Total size of the pages of the stream
+    size: usize,
+    /// Error of an invalid header packet, recorded by the lenient mode
+    error: Option<Error>,
+    /// Whether only the header of the `METADATA_BLOCK_PICTURE` comments is decoded, see
+    /// [`ParseOptions::skip_picture_data`]
+    skip_picture_data: bool,
 }
 
 impl Ogg {
-    fn load_fulldata(segments: &[Segment], start: usize) -> Vec<u8> {
-        let mut buf: Vec<u8> = Vec::new();
-        let mut cur = start;
-        loop {
-            let segment = &segments[cur];
-            // not continued from previous segment
-            if cur > start && segment.flags != 0x01 {
-                break;
+    /// Whether the next packet of a stream is still a header packet
+    fn is_header_pending(packets: &[Vec<u8>]) -> bool {
+        match packets.first() {
+            None => true,
+            Some(first) if FlacIdentification::is_flac_format(first) => {
+                match FlacIdentification::read_header_count(first) {
+                    // 0 means the number of header packets is unknown, read until the last block
+                    0 => {
+                        let last = packets.last().unwrap();
+                        let offset = if packets.len() == 1 { 13 } else { 0 };
+                        let block_header = last.get(offset).copied().unwrap_or(0xFF);
+                        block_header & 0x80 == 0 && block_header != 0xFF
+                    }
+                    count => packets.len() < 1 + count as usize,
+                }
             }
-            buf.extend_from_slice(&segment.data);
-            cur += 1;
-            // last segment
-            if segment.flags == 0x04 || cur >= segments.len() {
-                break;
+            // the skeleton stream only carries header packets
+            Some(first) if SkeletonHead::is_skeleton_format(first) => true,
+            // identification and comment headers
+            _ => packets.len() < 2,
+        }
+    }
+    /// Parse the header packets of a logical stream, an invalid header is returned with the
+    /// pages that could still be decoded
+    fn parse_headers(
+        packets: &[Vec<u8>],
+        options: &ParseOptions,
+    ) -> (Vec<OggParsedPage>, Option<Error>) {
+        let mut pages = Vec::new();
+        if let Some(first) = packets.first().filter(|it| FlacIdentification::is_flac_format(it)) {
+            pages.push(OggParsedPage::FlacIdentification(FlacIdentification::new(first)));
+            // each following header packet is a single metadata block, reassembled from pages
+            let mut error = None;
+            for packet in &packets[1..] {
+                // block type and 24 bits length
+                let expected = match packet.get(1..4) {
+                    Some(len) => 4 + u32::from_be_bytes([0, len[0], len[1], len[2]]) as usize,
+                    None => 4,
+                };
+                if packet.len() < expected {
+                    error.get_or_insert(Error::InvalidHeader {
+                        header: "flac metadata block",
+                        reason: format!("expected {} bytes, got {}", expected, packet.len()),
+                    });
+                    continue;
+                }
+                let block = Block::new(&mut ByteReader::new(packet));
+                let block = FlacParsedBlock::with_options(block, options).into_owned();
+                pages.push(OggParsedPage::FlacBlock(block));
             }
+            return (pages, error);
         }
-        buf
+        if let Some(first) = packets.first().filter(|it| SkeletonHead::is_skeleton_format(it)) {
+            pages.push(OggParsedPage::SkeletonHead(SkeletonHead::new(first)));
+            for packet in packets[1..]
+                .iter()
+                .filter(|it| SkeletonBone::is_skeleton_bone(it))
+            {
+                pages.push(OggParsedPage::SkeletonBone(SkeletonBone::new(packet)));
+            }
+            return (pages, None);
+        }
+        if let Some(first) = packets.first().filter(|it| SpeexIdentification::is_speex_format(it)) {
+            pages.push(OggParsedPage::SpeexIdentification(SpeexIdentification::new(first)));
+            // the speex comment header has no magic
+            if let Some(comments) = packets.get(1) {
+                pages.push(OggParsedPage::Comments(VorbisComment::new(comments)));
+            }
+            return (pages, None);
+        }
+        let mut error = None;
+        match packets.first() {
+            Some(bytes) if OpusIdentification::is_opus_format(bytes) => {
+                match OpusIdentification::new(bytes) {
+                    Ok(identification) => {
+                        pages.push(OggParsedPage::OpusIdentification(identification))
+                    }
+                    Err(err) => error = Some(err),
+                }
+            }
+            Some(bytes) if VorbisIdentification::is_vorbis_format(bytes) => {
+                match VorbisIdentification::new(bytes) {
+                    Ok(identification) => {
+                        pages.push(OggParsedPage::VorbisIdentification(identification))
+                    }
+                    // keep the fields of an invalid header if it is long enough to be decoded
+                    Err(err) => {
+                        if let Some(identification) = VorbisIdentification::decode(bytes) {
+                            pages.push(OggParsedPage::VorbisIdentification(identification));
+                        }
+                        error = Some(err);
+                    }
+                }
+            }
+            Some(bytes) if TheoraIdentification::is_theora_format(bytes) => pages.push(
+                OggParsedPage::TheoraIdentification(TheoraIdentification::new(bytes)),
+            ),
+            _ => return (pages, None),
+        };
+        if let Some(comments) = packets.get(1).and_then(|it| Comments::new(it)) {
+            pages.push(OggParsedPage::Comments(comments.inner));
+        }
+        (pages, error)
+    }
+    /// Iterate over the packets of all logical streams, in the order they are completed
+    pub fn packets(bytes: &[u8]) -> Packets<'_> {
+        Packets {
+            bytes,
+            reader: ByteReader::new(bytes),
+            assembler: PacketAssembler::default(),
+            queue: VecDeque::new(),
+        }
+    }
+    pub fn parse_picture(picture: &str) -> Picture<'static> {
+        Picture::from_owned_bytes(decode_base64(picture))
     }
-    pub fn parse_picture(picture: &str) -> Picture{
-        let bytes = decode_base64(picture);
-        Picture::from_bytes(&bytes)
+    /// Decodes the picture header and the beginning of the image, the data is dropped
+    fn parse_picture_header(picture: &str) -> Picture<'static> {
+        // length field at `offset`, 0 if the picture is truncated
+        let read_length = |offset: usize| {
+            let prefix = decode_base64_prefix(picture, offset.saturating_add(4));
+            prefix
+                .get(offset..offset.saturating_add(4))
+                .map_or(0, |it| u32::from_be_bytes(it.try_into().unwrap()) as usize)
+        };
+        let mime_length = read_length(4);
+        let desc_length = read_length(mime_length.saturating_add(8));
+        let length = mime_length
+            .saturating_add(desc_length)
+            .saturating_add(32 + IMAGE_HEADER_LENGTH);
+        Picture::from_owned_bytes(decode_base64_prefix(picture, length)).without_data()
     }
+    /// The first logical stream with a recognized codec
+    fn first_stream(&self) -> Option<&LogicalStream> {
+        self.streams()
+            .find(|it| !it.pages.is_empty() && !it.is_skeleton())
+    }
+    /// Header packets of the first logical stream with a recognized codec
     pub fn pages(&self) -> &[OggParsedPage]{
+        self.first_stream()
+            .map(|it| it.pages.as_slice())
+            .unwrap_or(&[])
+    }
+    /// CRC check results of all pages, see [`ParseOptions::strict`] to fail on the first mismatch
+    pub fn integrity(&self) -> &[PageIntegrity] {
+        &self.integrity
+    }
+    /// Whether the CRC of every page matches
+    pub fn is_intact(&self) -> bool {
+        self.integrity.iter().all(|it| it.is_valid())
+    }
+    /// R128 gains of the first Opus stream, `None` for other codecs
+    pub fn r128_gain(&self) -> Option<R128Gain> {
+        let pages = self.pages();
+        if !matches!(pages.first(), Some(OggParsedPage::OpusIdentification(_))) {
+            return None;
+        }
+        pages.iter().find_map(|it| match it {
+            OggParsedPage::Comments(comments) => Some(R128Gain::new(comments)),
+            _ => None,
+        })
+    }
+    pub fn links(&self) -> &[ChainLink] {
+        &self.links
+    }
+    /// All logical streams of all links
+    pub fn streams(&self) -> impl Iterator<Item = &LogicalStream> {
+        self.links.iter().flat_map(|it| it.streams.iter())
+    }
+    /// Total duration of all links
+    pub fn duration(&self) -> Option<Duration> {
+        self.links
+            .iter()
+            .map(|it| it.duration())
+            .sum::<Option<Duration>>()
+    }
+    /// Average bitrate in kbps over the whole file
+    pub fn bitrate(&self) -> Option<u32> {
+        let duration = self.duration()?.as_micros();
+        if duration == 0 {
+            return None;
+        }
+        Some((self.file_size as u128 * 8 * 1000 / duration) as u32)
+    }
+    /// Pictures of the `METADATA_BLOCK_PICTURE` comments and the Ogg FLAC PICTURE blocks
+    pub fn pictures(&self) -> Vec<Picture<'static>> {
+        let Some(stream) = self.first_stream() else {
+            return Vec::new();
+        };
+        let pictures = stream.pages.iter().flat_map(|it| match it {
+            OggParsedPage::Comments(comments)
+            | OggParsedPage::FlacBlock(FlacParsedBlock::Comment(comments)) => comments
+                .comments()
+                .iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case(PICTURE_COMMENT))
+                .map(|(_, value)| match stream.skip_picture_data {
+                    true => Ogg::parse_picture_header(value),
+                    false => Ogg::parse_picture(value),
+                })
+                .collect(),
+            OggParsedPage::FlacBlock(FlacParsedBlock::Picture(picture)) => {
+                vec![picture.clone()]
+            }
+            _ => Vec::new(),
+        });
+        pictures.collect()
+    }
+    /// The best front cover among the pictures, see [`crate::PictureType`]
+    pub fn front_cover(&self) -> Option<Picture<'static>> {
+        front_cover(self.pictures())
+    }
+    /// Properties of the first audio stream, None if no stream has a recognized audio codec.
+    /// The duration and bitrate cover the whole file, including every link of a chained file.
+    pub fn properties(&self) -> Option<AudioProperties> {
+        self.streams().find_map(|stream| {
+            let properties = match stream.pages.first()? {
+                OggParsedPage::VorbisIdentification(identification) => {
+                    AudioProperties::new(Container::Ogg, "Vorbis")
+                        .with_sample_rate(identification.audio_sample_rate)
+                        .with_channels(identification.audio_channels as u16)
+                }
+                // Opus always decodes at 48 kHz, the input sample rate is informational
+                OggParsedPage::OpusIdentification(identification) => {
+                    AudioProperties::new(Container::Ogg, "Opus")
+                        .with_sample_rate(48000)
+                        .with_channels(identification.channel_output_count as u16)
+                }
+                OggParsedPage::FlacIdentification(identification) => {
+                    identification.stream_info.properties(Container::Ogg)
+                }
+                OggParsedPage::SpeexIdentification(identification) => {
+                    AudioProperties::new(Container::Ogg, "Speex")
+                        .with_sample_rate(identification.sample_rate)
+                        .with_channels(identification.channels as u16)
+                }
+                _ => return None,
+            };
+            Some(
+                properties
+                    .with_duration(self.duration())
+                    .with_bitrate(self.bitrate()),
+            )
+        })
+    }
+}
+
+impl ChainLink {
+    pub fn streams(&self) -> &[LogicalStream] {
+        &self.streams
+    }
+    /// Duration of the longest stream with a known duration
+    pub fn duration(&self) -> Option<Duration> {
+        self.streams.iter().filter_map(|it| it.duration()).max()
+    }
+}
+
+impl LogicalStream {
+    pub fn serial_number(&self) -> u32 {
+        self.serial_number
+    }
+    /// Parsed header packets, empty if the codec is not recognized
+    pub fn pages(&self) -> &[OggParsedPage] {
         &self.pages
     }
+    pub fn last_granule_position(&self) -> Option<u64> {
+        self.last_granule_position
+    }
+    /// Error of an invalid header packet, the pages keep the headers which could be decoded
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+    /// Whether the stream is a Skeleton stream, which describes the other streams
+    pub fn is_skeleton(&self) -> bool {
+        matches!(self.pages.first(), Some(OggParsedPage::SkeletonHead(_)))
+    }
+    /// Duration from the granule position of the last page, for Opus the pre-skip is subtracted.
+    /// `None` if a corrupted granule position overflows it.
+    pub fn duration(&self) -> Option<Duration> {
+        let granule_position = self.last_granule_position? as u128;
+        let (samples, sample_rate) = match self.pages.first()? {
+            OggParsedPage::VorbisIdentification(identification) => {
+                (granule_position, identification.audio_sample_rate as u64)
+            }
+            // Opus granule positions always count at 48 kHz
+            OggParsedPage::OpusIdentification(identification) => (
+                granule_position.saturating_sub(identification.pre_skip as u128),
+                48000,
+            ),
+            OggParsedPage::FlacIdentification(identification) => (
+                granule_position,
+                identification.stream_info.sample_rate() as u64,
+            ),
+            OggParsedPage::SpeexIdentification(identification) => {
+                (granule_position, identification.sample_rate as u64)
+            }
+            // frames counted in units of the frame duration
+            OggParsedPage::TheoraIdentification(identification) => (
+                identification.frame_count(granule_position as u64) as u128
+                    * identification.frame_rate_denominator as u128,
+                identification.frame_rate_numerator as u64,
+            ),
+            _ => return None,
+        };
+        if sample_rate == 0 {
+            return None;
+        }
+        let micros = samples * 1_000_000 / sample_rate as u128;
+        u64::try_from(micros).ok().map(Duration::from_micros)
+    }
+    /// Average bitrate in kbps of the stream
+    pub fn bitrate(&self) -> Option<u32> {
+        let duration = self.duration()?.as_micros();
+        if duration == 0 {
+            return None;
+        }
+        Some((self.size as u128 * 8 * 1000 / duration) as u32)
+    }
 }
 
-impl Reader for Ogg {
-    #[allow(unused)]
+impl Reader<'_> for Ogg {
     fn from_bytes(bytes: &[u8]) -> Self {
+        Ogg::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes[0..4] == OGG_SIGNATURE
+    }
+    fn from_bytes_with_options(bytes: &[u8], options: &ParseOptions) -> Self {
+        Ogg::try_from_bytes_with_options(bytes, options).unwrap_or_else(|err| panic!("{}", err))
+    }
+    fn try_from_bytes_with_options(bytes: &[u8], options: &ParseOptions) -> Result<Self, Error> {
         if !Ogg::is(bytes) {
             panic!("Invalid ogg audio format.");
         }
-        let mut segments: Vec<Segment> = Vec::new();
+        // (link, header packets, stream)
+        let mut streams: Vec<(usize, Vec<Vec<u8>>, LogicalStream)> = Vec::new();
         let mut reader = ByteReader::new(bytes);
-        let mut full_page = 0;
-        loop {
+        let mut assembler = PacketAssembler::default();
+        let mut packets = VecDeque::new();
+        let mut integrity = Vec::new();
+        let mut link = 0;
+        let mut previous_is_bos = false;
+        while Segment::is_complete(bytes, reader.offset()) {
             let segment = Segment::new(&mut reader);
-            let flags = segment.flags;
-            let size = segment.size;
-            if segment.flags == 0x00 {
-                full_page += 1;
+            let page = PageIntegrity {
+                offset: segment.offset,
+                serial_number: segment.serial_number,
+                sequence_number: segment.sequence_number,
+                checksum: segment.checksum,
+                computed_checksum: segment.computed_checksum,
+            };
+            if options.strict && !page.is_valid() {
+                return Err(Error::CorruptedPage {
+                    offset: page.offset,
+                    serial_number: page.serial_number,
+                    sequence_number: page.sequence_number,
+                    checksum: page.checksum,
+                    computed_checksum: page.computed_checksum,
+                });
             }
-            segments.push(segment);
-            if full_page >= 2 || reader.is_end() || flags == 0x4 {
-                break;
+            integrity.push(page);
+            let is_bos = segment.flags & 0x02 != 0;
+            // BOS pages after other pages start a new chain link
+            if is_bos && !previous_is_bos && !streams.is_empty() {
+                link += 1;
+            }
+            previous_is_bos = is_bos;
+            let index = match streams
+                .iter()
+                .rposition(|(_, _, it)| it.serial_number == segment.serial_number)
+            {
+                Some(index) if !is_bos => index,
+                _ => {
+                    streams.push((
+                        link,
+                        Vec::new(),
+                        LogicalStream {
+                            serial_number: segment.serial_number,
+                            pages: Vec::new(),
+                            last_granule_position: None,
+                            size: 0,
+                            error: None,
+                            skip_picture_data: options.skip_picture_data,
+                        },
+                    ));
+                    streams.len() - 1
+                }
+            };
+            let (_, headers, stream) = &mut streams[index];
+            stream.size += segment.size;
+            if segment.granule_position != u64::MAX {
+                stream.last_granule_position = Some(segment.granule_position);
+            }
+            // only the header packets are kept, the audio packets are not assembled
+            if !Ogg::is_header_pending(headers) {
+                assembler.drop_pending(segment.serial_number);
+                continue;
+            }
+            assembler.push(&segment, &mut packets);
+            for packet in packets.drain(..) {
+                if Ogg::is_header_pending(headers) {
+                    headers.push(packet.data);
+                }
             }
         }
-        let mut pages = Vec::new();
-        match Ogg::load_fulldata(&segments, 0) {
-            bytes if OpusIdentification::is_opus_format(&bytes) => pages.push(
-                OggParsedPage::OpusIdentification(OpusIdentification::new(&bytes)),
-            ),
-            bytes if VorbisIdentification::is_vorbis_format(&bytes) => pages.push(
-                OggParsedPage::VorbisIdentification(VorbisIdentification::new(&bytes)),
-            ),
-            _ => (),
-        };
-        if pages.is_empty() {
-            return Self { pages };
-        }
-        if let Some(comments) = Comments::new(&Ogg::load_fulldata(&segments, 1)) {
-            pages.push(OggParsedPage::Comments(comments.inner));
+        let mut links: Vec<ChainLink> = Vec::new();
+        for (link, headers, mut stream) in streams {
+            let (pages, error) = Ogg::parse_headers(&headers, options);
+            match error {
+                Some(err) if options.strict => return Err(err),
+                error => stream.error = error,
+            }
+            stream.pages = pages;
+            match links.get_mut(link) {
+                Some(chain_link) => chain_link.streams.push(stream),
+                None => links.push(ChainLink {
+                    streams: vec![stream],
+                }),
+            }
         }
-        Self { pages }
-    }
-    fn is(bytes: &[u8]) -> bool {
-        bytes[0..4] == OGG_SIGNATURE
+        Ok(Self {
+            links,
+            integrity,
+            file_size: bytes.len(),
+        })
     }
 }
 
-pub(crate) struct Segment {
+pub(crate) struct Segment<'a> {
+    offset: usize,
     signature: String,
     version: u8,
     /// This is an 8 bit field of flags, which indicates the type of page that follows.
@@ -92,16 +521,20 @@ pub(crate) struct Segment {
     /// - 0x02 BOS(Begin of Stream)
     /// - 0x04 EOS(End of Stream)
     flags: u8,
-    granule_position: usize,
+    granule_position: u64,
     serial_number: u32,
     sequence_number: u32,
     checksum: u32,
+    /// CRC of the page with the checksum field zeroed
+    computed_checksum: u32,
     total_segments: u8,
+    /// Lacing values, a value less than 255 ends a packet
+    lacing: &'a [u8],
     size: usize,
-    data: Vec<u8>,
+    data: &'a [u8],
 }
 
-impl Debug for Segment {
+impl Debug for Segment<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Segment")
             .field("signature", &self.signature)
@@ -116,13 +549,25 @@ impl Debug for Segment {
             .field("sequence_number", &self.sequence_number)
             .field("checksum", &self.checksum)
             .field("total_segments", &self.total_segments)
-            .field("data", &debug_vec(&self.data))
+            .field("data", &debug_vec(self.data))
             .finish()
     }
 }
 
-impl Segment {
-    pub(crate) fn new(reader: &mut ByteReader) -> Self {
+impl<'a> Segment<'a> {
+    /// Whether a whole page starts at `offset`
+    pub(crate) fn is_complete(bytes: &[u8], offset: usize) -> bool {
+        if offset + 27 > bytes.len() || bytes[offset..offset + 4] != OGG_SIGNATURE {
+            return false;
+        }
+        let total_segments = bytes[offset + 26] as usize;
+        let Some(lacing) = bytes.get(offset + 27..offset + 27 + total_segments) else {
+            return false;
+        };
+        let size = lacing.iter().map(|it| *it as usize).sum::<usize>();
+        offset + 27 + total_segments + size <= bytes.len()
+    }
+    pub(crate) fn new(reader: &mut ByteReader<'a>) -> Self {
         if reader.peek(4) != OGG_SIGNATURE {
             panic!("Invalid ogg segment format")
         }
@@ -130,28 +575,23 @@ impl Segment {
         let signature = reader.read_uft8_string(4);
         let version = reader.read_next_u8();
         let flags = reader.read_next_u8();
-        let granule_position = reader.read_next_u64(true) as usize;
-        let serial_number = reader.read_next_u32(true);
-        let sequence_number = reader.read_next_u32(true);
+        // little-endian
+        let granule_position = reader.read_next_u64(false);
+        let serial_number = reader.read_next_u32(false);
+        let sequence_number = reader.read_next_u32(false);
         let checksum_pos = reader.offset();
-        let checksum = reader.read_next_u32(true);
+        let checksum = reader.read_next_u32(false);
         let total_segments = reader.read_next_u8();
-        let segment_size = reader
-            .read(total_segments as usize)
-            .iter()
-            .fold(0, |a, b| a + (*b as usize));
-        let data = reader.read(segment_size).to_vec();
-        // validate crc32
-        {
-            let mut view: Vec<u8> = reader.peek_range(page_start, checksum_pos).to_vec();
-            view.push(0);
-            view.push(total_segments);
-            view.extend_from_slice(data.as_slice());
-            if crc32(&view) == checksum {
-                eprintln!("The packet is corrupted");
-            }
-        }
+        let lacing = reader.read(total_segments as usize);
+        let segment_size = lacing.iter().fold(0, |a, b| a + (*b as usize));
+        let data = reader.read(segment_size);
+        let computed_checksum = {
+            let mut view: Vec<u8> = reader.peek_range(page_start, reader.offset()).to_vec();
+            view[checksum_pos - page_start..checksum_pos - page_start + 4].fill(0);
+            crc32_ogg(&view)
+        };
         Self {
+            offset: page_start,
             signature,
             version,
             flags,
@@ -159,20 +599,192 @@ impl Segment {
             serial_number,
             sequence_number,
             checksum,
+            computed_checksum,
             total_segments,
+            lacing,
             size: segment_size,
             data,
         }
     }
 }
 
+/// Ogg packet, reassembled from the lacing values of one or more pages
+pub struct Packet {
+    serial_number: u32,
+    granule_position: u64,
+    data: Vec<u8>,
+}
+
+impl Debug for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Packet")
+            .field("serial_number", &self.serial_number)
+            .field("granule_position", &self.granule_position)
+            .field("data", &debug_vec(&self.data))
+            .finish()
+    }
+}
+
+impl Packet {
+    pub fn serial_number(&self) -> u32 {
+        self.serial_number
+    }
+    /// Granule position of the page on which the packet ends, `u64::MAX` if the page finishes
+    /// no packet
+    pub fn granule_position(&self) -> u64 {
+        self.granule_position
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Collects segments of unfinished packets per logical stream
+#[derive(Default)]
+struct PacketAssembler {
+    pending: HashMap<u32, Vec<u8>>,
+}
+
+impl PacketAssembler {
+    fn push(&mut self, segment: &Segment, packets: &mut VecDeque<Packet>) {
+        let continued = segment.flags & 0x01 != 0;
+        let mut buf = match self.pending.remove(&segment.serial_number) {
+            Some(buf) if continued => Some(buf),
+            // a continued page without the start of the packet, the partial packet is dropped
+            _ if continued => None,
+            // the previous packet was never finished
+            _ => Some(Vec::new()),
+        };
+        let mut offset = 0;
+        for lacing in segment.lacing {
+            let len = *lacing as usize;
+            if let Some(buf) = buf.as_mut() {
+                buf.extend_from_slice(&segment.data[offset..offset + len]);
+            }
+            offset += len;
+            if len < 255 {
+                if let Some(data) = buf.take() {
+                    packets.push_back(Packet {
+                        serial_number: segment.serial_number,
+                        granule_position: segment.granule_position,
+                        data,
+                    });
+                }
+                buf = Some(Vec::new());
+            }
+        }
+        if let Some(buf) = buf.filter(|it| !it.is_empty()) {
+            self.pending.insert(segment.serial_number, buf);
+        }
+    }
+    /// Drops the unfinished packet of a stream
+    fn drop_pending(&mut self, serial_number: u32) {
+        self.pending.remove(&serial_number);
+    }
+}
+
+/// Iterator over the packets of an Ogg file, see [`Ogg::packets`]
+pub struct Packets<'a> {
+    bytes: &'a [u8],
+    reader: ByteReader<'a>,
+    assembler: PacketAssembler,
+    queue: VecDeque<Packet>,
+}
+
+impl Iterator for Packets<'_> {
+    type Item = Packet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(packet) = self.queue.pop_front() {
+                return Some(packet);
+            }
+            if !Segment::is_complete(self.bytes, self.reader.offset()) {
+                return None;
+            }
+            let segment = Segment::new(&mut self.reader);
+            self.assembler.push(&segment, &mut self.queue);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum OggParsedPage {
     VorbisIdentification(VorbisIdentification),
     OpusIdentification(OpusIdentification),
     Comments(VorbisComment),
+    FlacIdentification(FlacIdentification),
+    /// Metadata block of an Ogg FLAC stream, following the identification header
+    FlacBlock(FlacParsedBlock<'static>),
+    SpeexIdentification(SpeexIdentification),
+    TheoraIdentification(TheoraIdentification),
+    SkeletonHead(SkeletonHead),
+    SkeletonBone(SkeletonBone),
+}
+
+/// First header packet of the Ogg FLAC mapping
+///
+/// Structure
+/// ```text
+/// | 0x7F | "FLAC" | major | minor | header count | "fLaC" | STREAMINFO block |
+/// | 1 B  |  4 B   |  1 B  |  1 B  | 2 B, BE      |  4 B   |  4 + 34 B        |
+/// ```
+#[derive(Debug)]
+pub struct FlacIdentification {
+    major_version: u8,
+    minor_version: u8,
+    header_count: u16,
+    stream_info: StreamInfo,
+}
+
+impl FlacIdentification {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        reader.skip(5);
+        let major_version = reader.read_next_u8();
+        let minor_version = reader.read_next_u8();
+        let header_count = reader.read_next_u16(true);
+        reader.skip(4);
+        let stream_info = StreamInfo::new(Block::new(&mut reader));
+        Self {
+            major_version,
+            minor_version,
+            header_count,
+            stream_info,
+        }
+    }
+    pub(crate) fn is_flac_format(bytes: &[u8]) -> bool {
+        bytes.len() >= 13 && bytes[0..5] == *b"\x7fFLAC" && bytes[9..13] == *b"fLaC"
+    }
+    fn read_header_count(bytes: &[u8]) -> u16 {
+        u16::from_be_bytes([bytes[7], bytes[8]])
+    }
+    pub fn major_version(&self) -> u8 {
+        self.major_version
+    }
+    pub fn minor_version(&self) -> u8 {
+        self.minor_version
+    }
+    /// Number of header packets following this one, 0 if unknown
+    pub fn header_count(&self) -> u16 {
+        self.header_count
+    }
+    pub fn stream_info(&self) -> &StreamInfo {
+        &self.stream_info
+    }
 }
 
+/// Vorbis identification header, all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | 0x01 | "vorbis" | version | channels | rate | bitrate max | nominal | min |
+/// | 1 B  |   6 B    |   4 B   |   1 B    | 4 B  |     4 B     |   4 B   | 4 B |
+/// | blocksize 0 | blocksize 1 | framing |
+/// |   4 bits    |   4 bits    |   1 B   |
+/// ```
+///
+/// The block sizes are stored as exponents, blocksize 0 in the low nibble.
 #[derive(Debug)]
 pub struct VorbisIdentification {
     vorbis_version: u32,
@@ -183,11 +795,61 @@ pub struct VorbisIdentification {
     bitrate_minimum: i32,
     blocksize_0: u8, // 2 exponent, should less or equal blocksize_1
     blocksize_1: u8, // 2 exponent
-    framing_flag: u8,
 }
 
 impl VorbisIdentification {
-    pub(crate) fn new(bytes: &[u8]) -> Self {
+    pub(crate) fn new(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |reason: String| Error::InvalidHeader {
+            header: "vorbis identification",
+            reason,
+        };
+        let Some(identification) = VorbisIdentification::decode(bytes) else {
+            return Err(invalid(format!("expected 30 bytes, got {}", bytes.len())));
+        };
+        let VorbisIdentification {
+            vorbis_version,
+            audio_channels,
+            audio_sample_rate,
+            blocksize_0,
+            blocksize_1,
+            ..
+        } = identification;
+        let framing_flag = bytes[29] & 0x1;
+        if vorbis_version != 0 {
+            return Err(invalid(format!("unsupported version {}", vorbis_version)));
+        }
+        if audio_channels == 0 {
+            return Err(invalid("no audio channels".to_string()));
+        }
+        if audio_sample_rate == 0 {
+            return Err(invalid("sample rate is 0".to_string()));
+        }
+        // allowed block sizes are 64 to 8192
+        for exponent in [blocksize_0, blocksize_1] {
+            if !(6..=13).contains(&exponent) {
+                return Err(invalid(format!(
+                    "block size {} is not in 64..=8192",
+                    1u32 << exponent
+                )));
+            }
+        }
+        if blocksize_0 > blocksize_1 {
+            return Err(invalid(format!(
+                "blocksize 0 ({}) is greater than blocksize 1 ({})",
+                1u32 << blocksize_0,
+                1u32 << blocksize_1
+            )));
+        }
+        if framing_flag == 0 {
+            return Err(invalid("framing bit is not set".to_string()));
+        }
+        Ok(identification)
+    }
+    /// Decode the fields without validating them, `None` if the header is too short
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 30 {
+            return None;
+        }
         let mut reader = ByteReader::new(bytes);
         reader.skip(7);
         let vorbis_version = reader.read_next_u32(false);
@@ -197,21 +859,48 @@ impl VorbisIdentification {
         let bitrate_nominal = reader.read_next_i32(false);
         let bitrate_minimum = reader.read_next_i32(false);
         let blocksize = reader.read_next_u8();
-        let framing_flag = reader.read_next_u8() & 0x1;
-        Self {
+        Some(Self {
             vorbis_version,
             audio_channels,
             audio_sample_rate,
             bitrate_maximum,
             bitrate_nominal,
             bitrate_minimum,
-            blocksize_0: blocksize & 0xf0 >> 4, // front 4 bits
-            blocksize_1: blocksize & 0x0f,      // back 4 bits
-            framing_flag,
-        }
+            blocksize_0: blocksize & 0x0f, // low 4 bits
+            blocksize_1: blocksize >> 4,   // high 4 bits
+        })
     }
     pub(crate) fn is_vorbis_format(bytes: &[u8]) -> bool {
-        bytes[0] == 0x1 && bytes[1..7] == [0x76, 0x6F, 0x72, 0x62, 0x69, 0x73]
+        bytes.len() >= 7 && bytes[0] == 0x1 && bytes[1..7] == [0x76, 0x6F, 0x72, 0x62, 0x69, 0x73]
+    }
+    pub fn version(&self) -> u32 {
+        self.vorbis_version
+    }
+    pub fn channels(&self) -> u8 {
+        self.audio_channels
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.audio_sample_rate
+    }
+    /// Maximum bitrate in bits per second, 0 or negative if unset
+    pub fn bitrate_maximum(&self) -> i32 {
+        self.bitrate_maximum
+    }
+    /// Nominal bitrate in bits per second, 0 or negative if unset
+    pub fn bitrate_nominal(&self) -> i32 {
+        self.bitrate_nominal
+    }
+    /// Minimum bitrate in bits per second, 0 or negative if unset
+    pub fn bitrate_minimum(&self) -> i32 {
+        self.bitrate_minimum
+    }
+    /// Short block size in samples
+    pub fn blocksize_0(&self) -> u32 {
+        1 << self.blocksize_0
+    }
+    /// Long block size in samples
+    pub fn blocksize_1(&self) -> u32 {
+        1 << self.blocksize_1
     }
 }
 
@@ -221,27 +910,72 @@ pub struct OpusIdentification {
     channel_output_count: u8,
     pre_skip: u16,
     input_sample_rate: u32,
-    output_gain: u16,
+    /// Q7.8 fixed point dB
+    output_gain: i16,
     channel_mapping_family: u8,
-    channel_mapping_table: Option<Vec<u8>>,
+    channel_mapping_table: Option<ChannelMappingTable>,
+}
+
+/// Channel mapping table of an Opus stream, present when the mapping family is not 0
+///
+/// Structure
+/// ```text
+/// | stream count | coupled count | channel mapping          |
+/// |     1 B      |      1 B      | 1 B per output channel   |
+/// ```
+#[derive(Debug)]
+pub struct ChannelMappingTable {
+    stream_count: u8,
+    coupled_count: u8,
+    mapping: Vec<u8>,
+}
+
+impl ChannelMappingTable {
+    /// Number of Opus streams in each packet
+    pub fn stream_count(&self) -> u8 {
+        self.stream_count
+    }
+    /// Number of streams which decode to two channels
+    pub fn coupled_count(&self) -> u8 {
+        self.coupled_count
+    }
+    /// Decoded channel index for each output channel, 255 means a silent channel
+    pub fn mapping(&self) -> &[u8] {
+        &self.mapping
+    }
 }
 
 impl OpusIdentification {
-    pub(crate) fn new(bytes: &[u8]) -> Self {
+    pub(crate) fn new(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |expected: usize| Error::InvalidHeader {
+            header: "opus identification",
+            reason: format!("expected {} bytes, got {}", expected, bytes.len()),
+        };
+        if bytes.len() < 19 {
+            return Err(invalid(19));
+        }
         let mut reader = ByteReader::new(bytes);
         reader.skip(8);
         let version = reader.read_next_u8();
         let channel_output_count = reader.read_next_u8();
         let pre_skip = reader.read_next_u16(false);
         let input_sample_rate = reader.read_next_u32(false);
-        let output_gain = reader.read_next_u16(false);
+        let output_gain = reader.read_next_u16(false) as i16;
         let channel_mapping_family = reader.read_next_u8();
         let channel_mapping_table = if channel_mapping_family == 0x00 {
             None
         } else {
-            Some(reader.read(channel_mapping_family as usize).to_vec())
+            // stream count, coupled count and one mapping per output channel
+            if bytes.len() < 21 + channel_output_count as usize {
+                return Err(invalid(21 + channel_output_count as usize));
+            }
+            Some(ChannelMappingTable {
+                stream_count: reader.read_next_u8(),
+                coupled_count: reader.read_next_u8(),
+                mapping: reader.read(channel_output_count as usize).to_vec(),
+            })
         };
-        Self {
+        Ok(Self {
             version,
             channel_output_count,
             pre_skip,
@@ -249,10 +983,483 @@ impl OpusIdentification {
             output_gain,
             channel_mapping_family,
             channel_mapping_table,
-        }
+        })
     }
     pub(crate) fn is_opus_format(bytes: &[u8]) -> bool {
-        bytes[0..8] == [0x4F, 0x70, 0x75, 0x73, 0x54, 0x61, 0x67, 0x73]
+        // OpusHead
+        bytes.len() >= 8 && bytes[0..8] == [0x4F, 0x70, 0x75, 0x73, 0x48, 0x65, 0x61, 0x64]
+    }
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+    pub fn channel_output_count(&self) -> u8 {
+        self.channel_output_count
+    }
+    /// Samples at 48 kHz to discard from the decoder output at the start
+    pub fn pre_skip(&self) -> u16 {
+        self.pre_skip
+    }
+    /// Sample rate of the original input, informational only
+    pub fn input_sample_rate(&self) -> u32 {
+        self.input_sample_rate
+    }
+    /// Gain in dB to apply when decoding
+    pub fn output_gain(&self) -> f64 {
+        self.output_gain as f64 / 256.0
+    }
+    pub fn channel_mapping_family(&self) -> u8 {
+        self.channel_mapping_family
+    }
+    pub fn channel_mapping_table(&self) -> Option<&ChannelMappingTable> {
+        self.channel_mapping_table.as_ref()
+    }
+}
+
+/// `R128_TRACK_GAIN` and `R128_ALBUM_GAIN` comments of an Opus stream
+///
+/// The values are Q7.8 fixed point dB relative to -23 LUFS (EBU R128), applied in addition to
+/// the output gain of the identification header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct R128Gain {
+    track_gain: Option<i16>,
+    album_gain: Option<i16>,
+}
+
+impl R128Gain {
+    pub(crate) fn new(comments: &VorbisComment) -> Self {
+        let parse = |key: &str| {
+            comments
+                .comments()
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .and_then(|(_, value)| value.trim().parse::<i16>().ok())
+        };
+        Self {
+            track_gain: parse("R128_TRACK_GAIN"),
+            album_gain: parse("R128_ALBUM_GAIN"),
+        }
+    }
+    /// Track gain in dB
+    pub fn track_gain(&self) -> Option<f64> {
+        self.track_gain.map(|it| it as f64 / 256.0)
+    }
+    /// Album gain in dB
+    pub fn album_gain(&self) -> Option<f64> {
+        self.album_gain.map(|it| it as f64 / 256.0)
+    }
+    /// Track gain in dB relative to the ReplayGain reference (-18 LUFS)
+    pub fn track_replay_gain(&self) -> Option<f64> {
+        self.track_gain().map(R128Gain::to_replay_gain)
+    }
+    /// Album gain in dB relative to the ReplayGain reference (-18 LUFS)
+    pub fn album_replay_gain(&self) -> Option<f64> {
+        self.album_gain().map(R128Gain::to_replay_gain)
+    }
+    /// ReplayGain targets 5 dB louder than EBU R128
+    fn to_replay_gain(gain: f64) -> f64 {
+        gain + 5.0
+    }
+}
+
+/// Speex header, all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | "Speex   " | version | version id | header size | rate | mode | mode bitstream version |
+/// |    8 B     |  20 B   |    4 B     |     4 B     | 4 B  | 4 B  |          4 B           |
+/// | channels | bitrate | frame size | vbr | frames per packet | extra headers | reserved |
+/// |   4 B    |   4 B   |    4 B     | 4 B |        4 B        |      4 B      |   8 B    |
+/// ```
+#[derive(Debug)]
+pub struct SpeexIdentification {
+    version: String,
+    version_id: u32,
+    header_size: u32,
+    sample_rate: u32,
+    /// 0 narrowband, 1 wideband, 2 ultra-wideband
+    mode: u32,
+    mode_bitstream_version: u32,
+    channels: u32,
+    /// -1 if unknown
+    bitrate: i32,
+    frame_size: u32,
+    vbr: bool,
+    frames_per_packet: u32,
+    extra_headers: u32,
+}
+
+impl SpeexIdentification {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        reader.skip(8);
+        let version = reader
+            .read_uft8_string(20)
+            .trim_end_matches('\0')
+            .to_string();
+        Self {
+            version,
+            version_id: reader.read_next_u32(false),
+            header_size: reader.read_next_u32(false),
+            sample_rate: reader.read_next_u32(false),
+            mode: reader.read_next_u32(false),
+            mode_bitstream_version: reader.read_next_u32(false),
+            channels: reader.read_next_u32(false),
+            bitrate: reader.read_next_i32(false),
+            frame_size: reader.read_next_u32(false),
+            vbr: reader.read_next_u32(false) != 0,
+            frames_per_packet: reader.read_next_u32(false),
+            extra_headers: reader.read_next_u32(false),
+        }
+    }
+    pub(crate) fn is_speex_format(bytes: &[u8]) -> bool {
+        bytes.len() >= 80 && bytes[0..8] == *b"Speex   "
+    }
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+    pub fn version_id(&self) -> u32 {
+        self.version_id
+    }
+    pub fn header_size(&self) -> u32 {
+        self.header_size
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// 0 narrowband, 1 wideband, 2 ultra-wideband
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+    pub fn mode_bitstream_version(&self) -> u32 {
+        self.mode_bitstream_version
+    }
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+    /// Bitrate in bits per second, `None` if unknown
+    pub fn bitrate(&self) -> Option<u32> {
+        u32::try_from(self.bitrate).ok()
+    }
+    /// Samples per frame
+    pub fn frame_size(&self) -> u32 {
+        self.frame_size
+    }
+    pub fn is_vbr(&self) -> bool {
+        self.vbr
+    }
+    pub fn frames_per_packet(&self) -> u32 {
+        self.frames_per_packet
+    }
+    pub fn extra_headers(&self) -> u32 {
+        self.extra_headers
+    }
+}
+
+/// Theora identification header, all fields are big-endian
+///
+/// Structure
+/// ```text
+/// | 0x80 | "theora" | major | minor | revision | frame width / 16 | frame height / 16 |
+/// | 1 B  |   6 B    |  1 B  |  1 B  |   1 B    |       2 B        |        2 B        |
+/// | picture width | picture height | picture x | picture y | frame rate n/d | aspect n/d |
+/// |      3 B      |      3 B       |    1 B    |    1 B    |    4 + 4 B     |  3 + 3 B   |
+/// | color space | nominal bitrate | quality, keyframe granule shift, pixel format |
+/// |     1 B     |       3 B       |              6 + 5 + 2 + 3 bits               |
+/// ```
+#[derive(Debug)]
+pub struct TheoraIdentification {
+    version: (u8, u8, u8),
+    frame_width: u32,
+    frame_height: u32,
+    picture_width: u32,
+    picture_height: u32,
+    picture_x: u8,
+    picture_y: u8,
+    frame_rate_numerator: u32,
+    frame_rate_denominator: u32,
+    aspect_numerator: u32,
+    aspect_denominator: u32,
+    color_space: u8,
+    nominal_bitrate: u32,
+    quality: u8,
+    keyframe_granule_shift: u8,
+    pixel_format: u8,
+}
+
+impl TheoraIdentification {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        reader.skip(7);
+        let version = (
+            reader.read_next_u8(),
+            reader.read_next_u8(),
+            reader.read_next_u8(),
+        );
+        let frame_width = reader.read_next_u16(true) as u32 * 16;
+        let frame_height = reader.read_next_u16(true) as u32 * 16;
+        let picture_width = reader.read_next_u24(true);
+        let picture_height = reader.read_next_u24(true);
+        let picture_x = reader.read_next_u8();
+        let picture_y = reader.read_next_u8();
+        let frame_rate_numerator = reader.read_next_u32(true);
+        let frame_rate_denominator = reader.read_next_u32(true);
+        let aspect_numerator = reader.read_next_u24(true);
+        let aspect_denominator = reader.read_next_u24(true);
+        let color_space = reader.read_next_u8();
+        let nominal_bitrate = reader.read_next_u24(true);
+        let flags = reader.read_next_u16(true);
+        Self {
+            version,
+            frame_width,
+            frame_height,
+            picture_width,
+            picture_height,
+            picture_x,
+            picture_y,
+            frame_rate_numerator,
+            frame_rate_denominator,
+            aspect_numerator,
+            aspect_denominator,
+            color_space,
+            nominal_bitrate,
+            quality: (flags >> 10) as u8,
+            keyframe_granule_shift: ((flags >> 5) & 0x1f) as u8,
+            pixel_format: ((flags >> 3) & 0x03) as u8,
+        }
+    }
+    pub(crate) fn is_theora_format(bytes: &[u8]) -> bool {
+        bytes.len() >= 42 && bytes[0] == 0x80 && bytes[1..7] == *b"theora"
+    }
+    /// Number of frames up to a granule position, the granule position holds the last keyframe
+    /// shifted left by the keyframe granule shift plus the frames since that keyframe
+    pub fn frame_count(&self, granule_position: u64) -> u64 {
+        let shift = self.keyframe_granule_shift as u32;
+        let keyframe = granule_position.checked_shr(shift).unwrap_or(0);
+        let delta = granule_position & (1u64.checked_shl(shift).unwrap_or(0).wrapping_sub(1));
+        keyframe + delta
+    }
+    /// (major, minor, revision)
+    pub fn version(&self) -> (u8, u8, u8) {
+        self.version
+    }
+    /// Width of the encoded frame, a multiple of 16
+    pub fn frame_width(&self) -> u32 {
+        self.frame_width
+    }
+    /// Height of the encoded frame, a multiple of 16
+    pub fn frame_height(&self) -> u32 {
+        self.frame_height
+    }
+    /// Width of the visible picture
+    pub fn picture_width(&self) -> u32 {
+        self.picture_width
+    }
+    /// Height of the visible picture
+    pub fn picture_height(&self) -> u32 {
+        self.picture_height
+    }
+    pub fn picture_x(&self) -> u8 {
+        self.picture_x
+    }
+    pub fn picture_y(&self) -> u8 {
+        self.picture_y
+    }
+    pub fn frame_rate_numerator(&self) -> u32 {
+        self.frame_rate_numerator
+    }
+    pub fn frame_rate_denominator(&self) -> u32 {
+        self.frame_rate_denominator
+    }
+    /// Frames per second, `None` if the frame rate is invalid
+    pub fn fps(&self) -> Option<f64> {
+        if self.frame_rate_denominator == 0 {
+            return None;
+        }
+        Some(self.frame_rate_numerator as f64 / self.frame_rate_denominator as f64)
+    }
+    pub fn aspect_numerator(&self) -> u32 {
+        self.aspect_numerator
+    }
+    pub fn aspect_denominator(&self) -> u32 {
+        self.aspect_denominator
+    }
+    /// Pixel aspect ratio, `None` if unspecified
+    pub fn pixel_aspect_ratio(&self) -> Option<f64> {
+        if self.aspect_numerator == 0 || self.aspect_denominator == 0 {
+            return None;
+        }
+        Some(self.aspect_numerator as f64 / self.aspect_denominator as f64)
+    }
+    /// 0 unspecified, 1 Rec. 470M, 2 Rec. 470BG
+    pub fn color_space(&self) -> u8 {
+        self.color_space
+    }
+    /// Nominal bitrate in bits per second, 0 if unspecified
+    pub fn nominal_bitrate(&self) -> u32 {
+        self.nominal_bitrate
+    }
+    pub fn quality(&self) -> u8 {
+        self.quality
+    }
+    pub fn keyframe_granule_shift(&self) -> u8 {
+        self.keyframe_granule_shift
+    }
+    /// 0 4:2:0, 2 4:2:2, 3 4:4:4
+    pub fn pixel_format(&self) -> u8 {
+        self.pixel_format
+    }
+}
+
+/// Skeleton fishead packet, first packet of a Skeleton stream, all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | "fishead\0" | major | minor | presentation time n/d | base time n/d | UTC  |
+/// |     8 B     |  2 B  |  2 B  |        8 + 8 B        |    8 + 8 B    | 20 B |
+/// ```
+#[derive(Debug)]
+pub struct SkeletonHead {
+    major_version: u16,
+    minor_version: u16,
+    presentation_time_numerator: i64,
+    presentation_time_denominator: i64,
+    base_time_numerator: i64,
+    base_time_denominator: i64,
+    utc: String,
+}
+
+impl SkeletonHead {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        reader.skip(8);
+        Self {
+            major_version: reader.read_next_u16(false),
+            minor_version: reader.read_next_u16(false),
+            presentation_time_numerator: reader.read_next_i64(false),
+            presentation_time_denominator: reader.read_next_i64(false),
+            base_time_numerator: reader.read_next_i64(false),
+            base_time_denominator: reader.read_next_i64(false),
+            utc: reader
+                .read_uft8_string(20)
+                .trim_end_matches('\0')
+                .to_string(),
+        }
+    }
+    pub(crate) fn is_skeleton_format(bytes: &[u8]) -> bool {
+        bytes.len() >= 64 && bytes[0..8] == *b"fishead\0"
+    }
+    pub fn major_version(&self) -> u16 {
+        self.major_version
+    }
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version
+    }
+    pub fn presentation_time_numerator(&self) -> i64 {
+        self.presentation_time_numerator
+    }
+    pub fn presentation_time_denominator(&self) -> i64 {
+        self.presentation_time_denominator
+    }
+    pub fn base_time_numerator(&self) -> i64 {
+        self.base_time_numerator
+    }
+    pub fn base_time_denominator(&self) -> i64 {
+        self.base_time_denominator
+    }
+    /// UTC time of the base time, empty if unset
+    pub fn utc(&self) -> &str {
+        &self.utc
+    }
+}
+
+/// Skeleton fisbone packet, describes one logical stream, all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | "fisbone\0" | message header offset | serial number | header packets |
+/// |     8 B     |          4 B          |      4 B      |      4 B       |
+/// | granule rate n/d | base granule | preroll | granule shift | padding | message headers |
+/// |     8 + 8 B      |     8 B      |   4 B   |      1 B      |   3 B   |       nn B      |
+/// ```
+#[derive(Debug)]
+pub struct SkeletonBone {
+    serial_number: u32,
+    header_packets: u32,
+    granule_rate_numerator: i64,
+    granule_rate_denominator: i64,
+    base_granule: i64,
+    preroll: u32,
+    granule_shift: u8,
+    /// Message header fields, e.g. `Content-Type: audio/vorbis`
+    headers: Vec<(String, String)>,
+}
+
+impl SkeletonBone {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        reader.skip(8);
+        // relative to the offset field
+        let headers_offset = reader.read_next_u32(false) as usize + 8;
+        let serial_number = reader.read_next_u32(false);
+        let header_packets = reader.read_next_u32(false);
+        let granule_rate_numerator = reader.read_next_i64(false);
+        let granule_rate_denominator = reader.read_next_i64(false);
+        let base_granule = reader.read_next_i64(false);
+        let preroll = reader.read_next_u32(false);
+        let granule_shift = reader.read_next_u8();
+        let headers = bytes
+            .get(headers_offset..)
+            .map(|it| String::from_utf8_lossy(it).into_owned())
+            .unwrap_or_default()
+            .split("\r\n")
+            .filter_map(|it| it.split_once(':'))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Self {
+            serial_number,
+            header_packets,
+            granule_rate_numerator,
+            granule_rate_denominator,
+            base_granule,
+            preroll,
+            granule_shift,
+            headers,
+        }
+    }
+    pub(crate) fn is_skeleton_bone(bytes: &[u8]) -> bool {
+        bytes.len() >= 52 && bytes[0..8] == *b"fisbone\0"
+    }
+    /// Serial number of the described stream
+    pub fn serial_number(&self) -> u32 {
+        self.serial_number
+    }
+    pub fn header_packets(&self) -> u32 {
+        self.header_packets
+    }
+    pub fn granule_rate_numerator(&self) -> i64 {
+        self.granule_rate_numerator
+    }
+    pub fn granule_rate_denominator(&self) -> i64 {
+        self.granule_rate_denominator
+    }
+    pub fn base_granule(&self) -> i64 {
+        self.base_granule
+    }
+    pub fn preroll(&self) -> u32 {
+        self.preroll
+    }
+    pub fn granule_shift(&self) -> u8 {
+        self.granule_shift
+    }
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+    /// Value of the `Content-Type` message header field
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("Content-Type"))
+            .map(|(_, value)| value.as_str())
     }
 }
 
@@ -263,6 +1470,9 @@ pub struct Comments {
 
 impl Comments {
     pub(crate) fn new(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 8 {
+            return None;
+        }
         let mut reader = ByteReader::new(bytes);
         match reader.peek(8) {
             head if Comments::is_opus_format(head) => {
@@ -271,7 +1481,7 @@ impl Comments {
                     inner: VorbisComment::with_byte_reader(&mut reader),
                 })
             }
-            head if Comments::is_vorbis_format(head) => {
+            head if Comments::is_vorbis_format(head) || Comments::is_theora_format(head) => {
                 reader.skip(7);
                 Some(Self {
                     inner: VorbisComment::with_byte_reader(&mut reader),
@@ -288,4 +1498,550 @@ impl Comments {
         // Vorbis
         bytes[0] == 0x03 && bytes[1..7] == [0x76, 0x6F, 0x72, 0x62, 0x69, 0x73]
     }
+    pub(crate) fn is_theora_format(bytes: &[u8]) -> bool {
+        // theora
+        bytes[0] == 0x81 && bytes[1..7] == *b"theora"
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::base64::encode_base64;
+    use crate::picture::tests::png;
+
+    pub(crate) fn page(
+        flags: u8,
+        granule_position: u64,
+        serial_number: u32,
+        sequence_number: u32,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut page = OGG_SIGNATURE.to_vec();
+        page.push(0x00);
+        page.push(flags);
+        page.extend(granule_position.to_le_bytes());
+        page.extend(serial_number.to_le_bytes());
+        page.extend(sequence_number.to_le_bytes());
+        page.extend([0x00; 4]);
+        let mut lacing = vec![0xFF; data.len() / 255];
+        lacing.push((data.len() % 255) as u8);
+        page.push(lacing.len() as u8);
+        page.extend(lacing);
+        page.extend(data);
+        let checksum = crc32_ogg(&page);
+        page[22..26].copy_from_slice(&checksum.to_le_bytes());
+        page
+    }
+
+    /// Page with raw lacing values, used to lay out packets across pages
+    pub(crate) fn laced_page(
+        flags: u8,
+        granule_position: u64,
+        serial_number: u32,
+        sequence_number: u32,
+        lacing: &[u8],
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut page = OGG_SIGNATURE.to_vec();
+        page.push(0x00);
+        page.push(flags);
+        page.extend(granule_position.to_le_bytes());
+        page.extend(serial_number.to_le_bytes());
+        page.extend(sequence_number.to_le_bytes());
+        page.extend([0x00; 4]);
+        page.push(lacing.len() as u8);
+        page.extend(lacing);
+        page.extend(data);
+        let checksum = crc32_ogg(&page);
+        page[22..26].copy_from_slice(&checksum.to_le_bytes());
+        page
+    }
+
+    pub(crate) fn opus_head(pre_skip: u16) -> Vec<u8> {
+        let mut bytes = b"OpusHead".to_vec();
+        bytes.extend([0x01, 0x02]);
+        bytes.extend(pre_skip.to_le_bytes());
+        bytes.extend(44100u32.to_le_bytes());
+        bytes.extend([0x00, 0x00, 0x00]);
+        bytes
+    }
+
+    pub(crate) fn comments(magic: &[u8], comments: &[&str]) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend(4u32.to_le_bytes());
+        bytes.extend(b"test");
+        bytes.extend((comments.len() as u32).to_le_bytes());
+        for comment in comments {
+            bytes.extend((comment.len() as u32).to_le_bytes());
+            bytes.extend(comment.as_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_duration() {
+        let mut bytes = page(0x02, 0, 1, 0, &opus_head(312));
+        bytes.extend(page(0x00, 0, 1, 1, &comments(b"OpusTags", &["TITLE=test"])));
+        bytes.extend(page(0x00, 480_312, 1, 2, &[0x00; 4000]));
+        bytes.extend(page(0x04, 960_312, 1, 3, &[0x00; 4000]));
+        let ogg = Ogg::from_bytes(&bytes);
+        assert_eq!(ogg.duration(), Some(Duration::from_secs(20)));
+        assert_eq!(ogg.bitrate(), Some(3));
+
+        // corrupted granule position of the last page
+        let mut bytes = page(0x02, 0, 1, 0, &vorbis_head(0xB8, 0x01));
+        bytes.extend(page(0x00, 0, 1, 1, &comments(b"\x03vorbis", &["TITLE=test"])));
+        bytes.extend(page(0x04, 1 << 50, 1, 2, &[0x00; 100]));
+        let ogg = Ogg::from_bytes(&bytes);
+        let micros = (1u128 << 50) * 1_000_000 / 44100;
+        assert_eq!(ogg.duration(), Some(Duration::from_micros(micros as u64)));
+    }
+
+    #[test]
+    fn test_picture_comment() {
+        let png = png(600);
+        let mut picture = 3u32.to_be_bytes().to_vec();
+        picture.extend(9u32.to_be_bytes());
+        picture.extend(b"image/png");
+        picture.extend(0u32.to_be_bytes());
+        picture.extend([0x00; 16]);
+        picture.extend((png.len() as u32).to_be_bytes());
+        picture.extend(&png);
+        let comment = format!("METADATA_BLOCK_PICTURE={}", encode_base64(&picture));
+        let mut bytes = page(0x02, 0, 1, 0, &opus_head(312));
+        let comments = comments(b"OpusTags", &["TITLE=test", &comment]);
+        bytes.extend(page(0x00, 0, 1, 1, &comments));
+        bytes.extend(page(0x04, 480_312, 1, 2, &[0x00; 100]));
+
+        let ogg = Ogg::from_bytes(&bytes);
+        assert_eq!(ogg.front_cover().unwrap().data(), png);
+
+        let options = ParseOptions::new().skip_picture_data(true);
+        let ogg = Ogg::from_bytes_with_options(&bytes, &options);
+        match &ogg.pages()[1] {
+            OggParsedPage::Comments(comments) => {
+                assert_eq!(comments.comments().len(), 2);
+                assert_eq!(comments.comments()[1].0, "METADATA_BLOCK_PICTURE");
+            }
+            _ => panic!("expected comments"),
+        }
+        let cover = ogg.front_cover().unwrap();
+        assert!(cover.data().is_empty());
+        assert_eq!(cover.mime(), "image/png");
+        assert_eq!((cover.width(), cover.height()), (600, 600));
+    }
+
+    fn opus_stream(serial_number: u32, title: &str, granule_position: u64) -> Vec<u8> {
+        let title = format!("TITLE={}", title);
+        let mut bytes = page(0x02, 0, serial_number, 0, &opus_head(312));
+        bytes.extend(page(0x00, 0, serial_number, 1, &comments(b"OpusTags", &[&title])));
+        bytes.extend(page(0x04, granule_position, serial_number, 2, &[0x00; 100]));
+        bytes
+    }
+
+    #[test]
+    fn test_chained_streams() {
+        let mut bytes = opus_stream(1, "first", 480_312);
+        bytes.extend(opus_stream(2, "second", 240_312));
+        let ogg = Ogg::from_bytes(&bytes);
+        assert_eq!(ogg.links().len(), 2);
+        let second = &ogg.links()[1].streams()[0];
+        assert_eq!(second.serial_number(), 2);
+        assert_eq!(second.duration(), Some(Duration::from_secs(5)));
+        match &second.pages()[1] {
+            OggParsedPage::Comments(comments) => {
+                assert_eq!(comments.comments()[0], ("TITLE".to_string(), "second".to_string()))
+            }
+            _ => panic!("expected comments"),
+        }
+        assert_eq!(ogg.duration(), Some(Duration::from_secs(15)));
+        let properties = ogg.properties().unwrap();
+        assert_eq!(properties.duration(), Some(Duration::from_secs(15)));
+        assert_eq!(properties.bitrate(), ogg.bitrate());
+    }
+
+    #[test]
+    fn test_multiplexed_streams() {
+        let mut bytes = page(0x02, 0, 7, 0, b"\x80theora");
+        bytes.extend(page(0x02, 0, 1, 0, &opus_head(312)));
+        bytes.extend(page(0x00, 0, 7, 1, b"\x81theora"));
+        bytes.extend(page(0x00, 0, 1, 1, &comments(b"OpusTags", &["TITLE=test"])));
+        bytes.extend(page(0x00, 480_312, 1, 2, &[0x00; 100]));
+        bytes.extend(page(0x04, 100, 7, 2, &[0x00; 100]));
+        let ogg = Ogg::from_bytes(&bytes);
+        assert_eq!(ogg.links().len(), 1);
+        let streams = ogg.links()[0].streams();
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0].serial_number(), 7);
+        assert_eq!(streams[1].serial_number(), 1);
+        assert_eq!(streams[1].pages().len(), 2);
+        assert_eq!(ogg.pages().len(), 2);
+        assert_eq!(ogg.duration(), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_packets() {
+        let head = opus_head(312);
+        let description = format!("DESCRIPTION={}", "a".repeat(300));
+        let tags = comments(b"OpusTags", &[&description, "ARTIST=someone"]);
+        let audio = (0..600).map(|it| it as u8).collect::<Vec<_>>();
+        let mut bytes = page(0x02, 0, 1, 0, &head);
+        // the comment header spans two pages
+        bytes.extend(laced_page(0x00, u64::MAX, 1, 1, &[255], &tags[..255]));
+        let mut lacing = vec![(tags.len() - 255) as u8];
+        // the first audio packet is finished, the second one continues on the next page
+        lacing.extend([255, 255, 90, 255]);
+        let mut data = tags[255..].to_vec();
+        data.extend(&audio);
+        data.extend(&audio[..255]);
+        bytes.extend(laced_page(0x01, 960, 1, 2, &lacing, &data));
+        bytes.extend(laced_page(0x05, 1920, 1, 3, &[45], &audio[255..300]));
+        let packets = Ogg::packets(&bytes).collect::<Vec<_>>();
+        assert_eq!(packets.len(), 4);
+        assert_eq!(packets[0].data(), &head[..]);
+        assert_eq!(packets[1].data(), &tags[..]);
+        assert_eq!(packets[1].granule_position(), 960);
+        assert_eq!(packets[2].data(), &audio[..]);
+        assert_eq!(packets[3].data(), &audio[..300]);
+        assert_eq!(packets[3].granule_position(), 1920);
+        let ogg = Ogg::from_bytes(&bytes);
+        let comments = ogg
+            .pages()
+            .iter()
+            .find_map(|it| match it {
+                OggParsedPage::Comments(comments) => Some(comments),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(comments.comments().len(), 2);
+        assert_eq!(comments.comments()[1].1, "someone");
+        // the audio pages are not assembled but still counted
+        let stream = &ogg.links()[0].streams()[0];
+        assert_eq!(stream.last_granule_position(), Some(1920));
+    }
+
+    #[test]
+    fn test_orphan_continuation() {
+        let mut bytes = laced_page(0x01, 0, 1, 5, &[10, 4], &[0x00; 14]);
+        bytes.extend(laced_page(0x00, 0, 1, 6, &[3], &[0x01; 3]));
+        let packets = Ogg::packets(&bytes).collect::<Vec<_>>();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].data(), &[0x00; 4]);
+        assert_eq!(packets[1].data(), &[0x01; 3]);
+    }
+
+    #[test]
+    fn test_page_crc() {
+        let mut bytes = opus_stream(1, "test", 960_312);
+        let ogg = Ogg::from_bytes(&bytes);
+        assert!(ogg.is_intact());
+        assert_eq!(ogg.integrity().len(), 3);
+        // flip a bit in the comment header page
+        let offset = ogg.integrity()[1].offset();
+        bytes[offset + 40] ^= 0x01;
+        let ogg = Ogg::from_bytes(&bytes);
+        assert!(!ogg.is_intact());
+        assert!(ogg.integrity()[0].is_valid());
+        assert!(!ogg.integrity()[1].is_valid());
+        let options = ParseOptions::new().strict(true);
+        match Ogg::try_from_bytes_with_options(&bytes, &options) {
+            Err(Error::CorruptedPage {
+                offset: page_offset,
+                sequence_number,
+                ..
+            }) => {
+                assert_eq!(page_offset, offset);
+                assert_eq!(sequence_number, 1);
+            }
+            _ => panic!("expected a corrupted page"),
+        }
+    }
+
+    fn ogg_flac(header_count: u16) -> Vec<u8> {
+        let mut head = b"\x7fFLAC\x01\x00".to_vec();
+        head.extend(header_count.to_be_bytes());
+        let mut stream_info = crate::flac_frame::tests::flac_bytes(&[], 441_000, [0x00; 16]);
+        // more blocks follow
+        stream_info[4] = 0x00;
+        head.extend(stream_info);
+        let comment = comments(b"", &["TITLE=test"]);
+        let mut block = vec![0x84];
+        block.extend(&(comment.len() as u32).to_be_bytes()[1..]);
+        block.extend(comment);
+        let mut bytes = page(0x02, 0, 1, 0, &head);
+        bytes.extend(page(0x00, 0, 1, 1, &block));
+        bytes.extend(page(0x04, 441_000, 1, 2, &[0xFF, 0xF8, 0x69, 0x18]));
+        bytes
+    }
+
+    #[test]
+    fn test_ogg_flac() {
+        for header_count in [1, 0] {
+            let ogg = Ogg::from_bytes(&ogg_flac(header_count));
+            let pages = ogg.pages();
+            assert_eq!(pages.len(), 2);
+            match &pages[0] {
+                OggParsedPage::FlacIdentification(identification) => {
+                    assert_eq!(identification.major_version(), 1);
+                    assert_eq!(identification.header_count(), header_count);
+                    assert_eq!(identification.stream_info().sample_rate(), 44100);
+                }
+                _ => panic!("expected the flac identification header"),
+            }
+            match &pages[1] {
+                OggParsedPage::FlacBlock(FlacParsedBlock::Comment(comment)) => {
+                    assert_eq!(comment.comments()[0].1, "test");
+                }
+                _ => panic!("expected a vorbis comment block"),
+            }
+            assert_eq!(ogg.duration(), Some(Duration::from_secs(10)));
+        }
+    }
+
+    #[test]
+    fn test_ogg_flac_short_block() {
+        for (block, reason) in [
+            (&[0x84, 0x00][..], "expected 4 bytes, got 2"),
+            (&[0x84, 0x00, 0x00, 0x10, 0x00][..], "expected 20 bytes, got 5"),
+        ] {
+            let mut head = b"\x7fFLAC\x01\x00\x00\x01".to_vec();
+            head.extend(crate::flac_frame::tests::flac_bytes(&[], 441_000, [0x00; 16]));
+            let mut bytes = page(0x02, 0, 1, 0, &head);
+            bytes.extend(page(0x00, 0, 1, 1, block));
+            bytes.extend(page(0x04, 441_000, 1, 2, &[0xFF, 0xF8, 0x69, 0x18]));
+            let ogg = Ogg::from_bytes(&bytes);
+            let stream = &ogg.links()[0].streams()[0];
+            assert_eq!(stream.pages().len(), 1);
+            assert!(matches!(stream.pages()[0], OggParsedPage::FlacIdentification(_)));
+            assert_eq!(
+                stream.error().map(|it| it.to_string()),
+                Some(format!("invalid flac metadata block header: {}", reason))
+            );
+            let options = ParseOptions::new().strict(true);
+            assert!(Ogg::try_from_bytes_with_options(&bytes, &options).is_err());
+        }
+    }
+
+    fn speex_head() -> Vec<u8> {
+        let mut bytes = b"Speex   ".to_vec();
+        let mut version = b"1.2.0".to_vec();
+        version.resize(20, 0);
+        bytes.extend(version);
+        // version id, header size, rate, mode, mode bitstream version, channels, bitrate,
+        // frame size, vbr, frames per packet, extra headers, reserved
+        for value in [1i32, 80, 16000, 1, 4, 1, -1, 320, 0, 1, 0, 0, 0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_speex() {
+        let mut bytes = page(0x02, 0, 1, 0, &speex_head());
+        bytes.extend(page(0x00, 0, 1, 1, &comments(b"", &["TITLE=memo"])));
+        bytes.extend(page(0x04, 160_000, 1, 2, &[0x00; 100]));
+        let ogg = Ogg::from_bytes(&bytes);
+        match &ogg.pages()[0] {
+            OggParsedPage::SpeexIdentification(speex) => {
+                assert_eq!(speex.version(), "1.2.0");
+                assert_eq!(speex.sample_rate(), 16000);
+                assert_eq!(speex.mode(), 1);
+                assert_eq!(speex.channels(), 1);
+                assert_eq!(speex.bitrate(), None);
+                assert_eq!(speex.frames_per_packet(), 1);
+            }
+            _ => panic!("expected the speex header"),
+        }
+        match &ogg.pages()[1] {
+            OggParsedPage::Comments(comments) => assert_eq!(comments.comments()[0].1, "memo"),
+            _ => panic!("expected the comment header"),
+        }
+        assert_eq!(ogg.duration(), Some(Duration::from_secs(10)));
+    }
+
+    fn theora_head() -> Vec<u8> {
+        let mut bytes = b"\x80theora\x03\x02\x01".to_vec();
+        bytes.extend(80u16.to_be_bytes());
+        bytes.extend(45u16.to_be_bytes());
+        bytes.extend(&1280u32.to_be_bytes()[1..]);
+        bytes.extend(&720u32.to_be_bytes()[1..]);
+        bytes.extend([0x00, 0x00]);
+        bytes.extend(30000u32.to_be_bytes());
+        bytes.extend(1001u32.to_be_bytes());
+        bytes.extend(&1u32.to_be_bytes()[1..]);
+        bytes.extend(&1u32.to_be_bytes()[1..]);
+        bytes.push(0x00);
+        bytes.extend(&0u32.to_be_bytes()[1..]);
+        // quality 48, keyframe granule shift 6, pixel format 4:2:0
+        bytes.extend(((48u16 << 10) | (6 << 5)).to_be_bytes());
+        bytes
+    }
+
+    /// BOS page and the following pages of a Skeleton stream
+    fn skeleton_stream(serial_number: u32, described: u32) -> (Vec<u8>, Vec<u8>) {
+        let mut head = b"fishead\0".to_vec();
+        head.extend(3u16.to_le_bytes());
+        head.extend(0u16.to_le_bytes());
+        head.extend([0x00; 32]);
+        head.extend([0x00; 20]);
+        let mut bone = b"fisbone\0".to_vec();
+        bone.extend(44u32.to_le_bytes());
+        bone.extend(described.to_le_bytes());
+        bone.extend(3u32.to_le_bytes());
+        bone.extend(30000i64.to_le_bytes());
+        bone.extend(1001i64.to_le_bytes());
+        bone.extend(0i64.to_le_bytes());
+        bone.extend(0u32.to_le_bytes());
+        bone.extend([0x06, 0x00, 0x00, 0x00]);
+        bone.extend(b"Content-Type: video/theora\r\nRole: video/main\r\n");
+        let mut bytes = page(0x00, 0, serial_number, 1, &bone);
+        bytes.extend(page(0x04, 0, serial_number, 2, &[]));
+        (page(0x02, 0, serial_number, 0, &head), bytes)
+    }
+
+    #[test]
+    fn test_theora_skeleton() {
+        // all BOS pages come first
+        let (mut bytes, skeleton) = skeleton_stream(1, 2);
+        bytes.extend(page(0x02, 0, 2, 0, &theora_head()));
+        bytes.extend(skeleton);
+        let mut comment = b"\x81theora".to_vec();
+        comment.extend(&comments(b"", &["TITLE=clip"])[..]);
+        bytes.extend(page(0x00, 0, 2, 1, &comment));
+        // keyframe 250, 49 frames later
+        bytes.extend(page(0x04, (250 << 6) | 49, 2, 2, &[0x00; 100]));
+        let ogg = Ogg::from_bytes(&bytes);
+        let skeleton = &ogg.links()[0].streams()[0];
+        assert!(skeleton.is_skeleton());
+        match &skeleton.pages()[1] {
+            OggParsedPage::SkeletonBone(bone) => {
+                assert_eq!(bone.serial_number(), 2);
+                assert_eq!(bone.content_type(), Some("video/theora"));
+                assert_eq!(bone.headers().len(), 2);
+            }
+            _ => panic!("expected a fisbone"),
+        }
+        match &ogg.pages()[0] {
+            OggParsedPage::TheoraIdentification(theora) => {
+                assert_eq!(theora.version(), (3, 2, 1));
+                assert_eq!(theora.frame_width(), 1280);
+                assert_eq!(theora.picture_height(), 720);
+                assert!((theora.fps().unwrap() - 29.97).abs() < 0.01);
+                assert_eq!(theora.pixel_aspect_ratio(), Some(1.0));
+                assert_eq!(theora.quality(), 48);
+                assert_eq!(theora.keyframe_granule_shift(), 6);
+            }
+            _ => panic!("expected the theora header"),
+        }
+        match &ogg.pages()[1] {
+            OggParsedPage::Comments(comments) => assert_eq!(comments.comments()[0].1, "clip"),
+            _ => panic!("expected the comment header"),
+        }
+        // 299 frames at 30000/1001 fps
+        assert_eq!(ogg.duration(), Some(Duration::from_micros(9_976_633)));
+    }
+
+    #[test]
+    fn test_opus_header() {
+        let mut head = b"OpusHead".to_vec();
+        head.extend([0x01, 0x06]);
+        head.extend(312u16.to_le_bytes());
+        head.extend(48000u32.to_le_bytes());
+        // -3.5 dB
+        head.extend((-896i16).to_le_bytes());
+        // family 1, 4 streams, 2 coupled, 5.1 mapping
+        head.extend([0x01, 0x04, 0x02, 0x00, 0x04, 0x01, 0x02, 0x03, 0x05]);
+        let mut bytes = page(0x02, 0, 1, 0, &head);
+        let tags = comments(b"OpusTags", &["R128_TRACK_GAIN=-512", "R128_ALBUM_GAIN=256"]);
+        bytes.extend(page(0x04, 0, 1, 1, &tags));
+        let ogg = Ogg::from_bytes(&bytes);
+        match &ogg.pages()[0] {
+            OggParsedPage::OpusIdentification(opus) => {
+                assert_eq!(opus.channel_output_count(), 6);
+                assert_eq!(opus.output_gain(), -3.5);
+                assert_eq!(opus.channel_mapping_family(), 1);
+                let table = opus.channel_mapping_table().unwrap();
+                assert_eq!(table.stream_count(), 4);
+                assert_eq!(table.coupled_count(), 2);
+                assert_eq!(table.mapping(), &[0, 4, 1, 2, 3, 5]);
+            }
+            _ => panic!("expected the opus header"),
+        }
+        let gain = ogg.r128_gain().unwrap();
+        assert_eq!(gain.track_gain(), Some(-2.0));
+        assert_eq!(gain.album_gain(), Some(1.0));
+        assert_eq!(gain.track_replay_gain(), Some(3.0));
+        assert_eq!(gain.album_replay_gain(), Some(6.0));
+
+        // mapping table cut short, and a header cut before the mapping family
+        for (len, reason) in [
+            (26, "expected 27 bytes, got 26"),
+            (12, "expected 19 bytes, got 12"),
+        ] {
+            let mut bytes = page(0x02, 0, 1, 0, &head[..len]);
+            bytes.extend(page(0x04, 0, 1, 1, &tags));
+            let ogg = Ogg::from_bytes(&bytes);
+            let stream = &ogg.links()[0].streams()[0];
+            assert!(matches!(stream.pages(), [OggParsedPage::Comments(_)]));
+            assert_eq!(
+                stream.error().map(|it| it.to_string()),
+                Some(format!("invalid opus identification header: {}", reason))
+            );
+            let options = ParseOptions::new().strict(true);
+            assert!(Ogg::try_from_bytes_with_options(&bytes, &options).is_err());
+        }
+    }
+
+    fn vorbis_head(blocksize: u8, framing: u8) -> Vec<u8> {
+        let mut bytes = b"\x01vorbis".to_vec();
+        bytes.extend(0u32.to_le_bytes());
+        bytes.push(0x02);
+        bytes.extend(44100u32.to_le_bytes());
+        bytes.extend(0i32.to_le_bytes());
+        bytes.extend(128_000i32.to_le_bytes());
+        bytes.extend(0i32.to_le_bytes());
+        bytes.extend([blocksize, framing]);
+        bytes
+    }
+
+    #[test]
+    fn test_vorbis_identification() {
+        let identification = VorbisIdentification::new(&vorbis_head(0xB8, 0x01)).unwrap();
+        assert_eq!(identification.channels(), 2);
+        assert_eq!(identification.sample_rate(), 44100);
+        assert_eq!(identification.bitrate_nominal(), 128_000);
+        assert_eq!(identification.blocksize_0(), 256);
+        assert_eq!(identification.blocksize_1(), 2048);
+        // blocksize 0 greater than blocksize 1
+        assert!(VorbisIdentification::new(&vorbis_head(0x8B, 0x01)).is_err());
+        assert!(VorbisIdentification::new(&vorbis_head(0xE8, 0x01)).is_err());
+
+        let mut bytes = page(0x02, 0, 1, 0, &vorbis_head(0xB8, 0x00));
+        bytes.extend(page(0x04, 0, 1, 1, &comments(b"\x03vorbis", &["TITLE=test"])));
+        let ogg = Ogg::from_bytes(&bytes);
+        let stream = &ogg.links()[0].streams()[0];
+        assert_eq!(stream.pages().len(), 2);
+        match &stream.pages()[0] {
+            OggParsedPage::VorbisIdentification(identification) => {
+                assert_eq!(identification.sample_rate(), 44100)
+            }
+            _ => panic!("expected the vorbis identification header"),
+        }
+        match &stream.pages()[1] {
+            OggParsedPage::Comments(comments) => {
+                assert_eq!(comments.comments(), &[("TITLE".to_string(), "test".to_string())])
+            }
+            _ => panic!("expected the comment header"),
+        }
+        assert_eq!(
+            stream.error().map(|it| it.to_string()),
+            Some("invalid vorbis identification header: framing bit is not set".to_string())
+        );
+        let options = ParseOptions::new().strict(true);
+        assert!(matches!(
+            Ogg::try_from_bytes_with_options(&bytes, &options),
+            Err(Error::InvalidHeader { .. })
+        ));
+    }
 }
@@ -0,0 +1,40 @@
+/// Options controlling how much of a file is parsed
+///
+/// The default only reads the metadata and the headers needed to describe the audio stream.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub(crate) scan_frames: bool,
+    pub(crate) strict: bool,
+    pub(crate) skip_picture_data: bool,
+    #[cfg(feature = "flac-verify")]
+    pub(crate) verify_md5: bool,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Walk every audio frame to count samples exactly and check the stream integrity.
+    pub fn scan_frames(mut self, scan_frames: bool) -> Self {
+        self.scan_frames = scan_frames;
+        self
+    }
+    /// Fail on integrity errors (e.g. an Ogg page with a wrong CRC) instead of recording them.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+    /// Keep the type, MIME and description of the embedded pictures but not their image data,
+    /// for reading the tags only.
+    pub fn skip_picture_data(mut self, skip_picture_data: bool) -> Self {
+        self.skip_picture_data = skip_picture_data;
+        self
+    }
+    /// Decode every FLAC frame and compare the MD5 of the audio against STREAMINFO, implies
+    /// `scan_frames`.
+    #[cfg(feature = "flac-verify")]
+    pub fn verify_md5(mut self, verify_md5: bool) -> Self {
+        self.verify_md5 = verify_md5;
+        self
+    }
+}
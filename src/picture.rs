@@ -0,0 +1,744 @@
+use crate::mp4::Atoms;
+use crate::utils::ByteReader;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::fmt::{Debug, Formatter};
+
+/// Picture type of the ID3v2 `APIC` frame and the FLAC `PICTURE` block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PictureType {
+    Other,
+    /// 32x32 pixels PNG
+    FileIcon,
+    OtherFileIcon,
+    FrontCover,
+    BackCover,
+    Leaflet,
+    /// e.g. label side of the CD
+    Media,
+    LeadArtist,
+    Artist,
+    Conductor,
+    Band,
+    Composer,
+    Lyricist,
+    RecordingLocation,
+    DuringRecording,
+    DuringPerformance,
+    ScreenCapture,
+    BrightColoredFish,
+    Illustration,
+    BandLogo,
+    PublisherLogo,
+}
+
+impl PictureType {
+    /// Values above 20 are reserved and read as `Other`
+    pub(crate) fn new(value: u32) -> Self {
+        match value {
+            1 => PictureType::FileIcon,
+            2 => PictureType::OtherFileIcon,
+            3 => PictureType::FrontCover,
+            4 => PictureType::BackCover,
+            5 => PictureType::Leaflet,
+            6 => PictureType::Media,
+            7 => PictureType::LeadArtist,
+            8 => PictureType::Artist,
+            9 => PictureType::Conductor,
+            10 => PictureType::Band,
+            11 => PictureType::Composer,
+            12 => PictureType::Lyricist,
+            13 => PictureType::RecordingLocation,
+            14 => PictureType::DuringRecording,
+            15 => PictureType::DuringPerformance,
+            16 => PictureType::ScreenCapture,
+            17 => PictureType::BrightColoredFish,
+            18 => PictureType::Illustration,
+            19 => PictureType::BandLogo,
+            20 => PictureType::PublisherLogo,
+            _ => PictureType::Other,
+        }
+    }
+    /// How likely the picture is the front cover, lower is better. Untyped pictures are usually
+    /// the cover, icons are thumbnails.
+    fn cover_rank(&self) -> u8 {
+        match self {
+            PictureType::FrontCover => 0,
+            PictureType::Other => 1,
+            PictureType::FileIcon | PictureType::OtherFileIcon => 3,
+            _ => 2,
+        }
+    }
+}
+
+/// Embedded picture, the common type every container converts its cover art to
+///
+/// FLAC `PICTURE` block and Ogg `METADATA_BLOCK_PICTURE` structure, all fields are big-endian
+/// ```text
+/// | type | mime length | mime | description length | description |
+/// | 4 B  |     4 B     | nn B |        4 B         |    nn B     |
+/// | width | height | color depth | indexed colors | data length | data |
+/// |  4 B  |  4 B   |     4 B     |      4 B       |     4 B     | nn B |
+/// ```
+/// The declared MIME, dimensions and color depth are often missing or wrong, they are replaced
+/// by the ones read from the image header when the image format is recognized.
+///
+/// The data borrows from the parsed file, except for pictures decoded from text (e.g. base64
+/// comments) or reassembled from Ogg packets.
+#[derive(Clone)]
+pub struct Picture<'a> {
+    r#type: PictureType,
+    /// MIME of the image format, or the normalized declared MIME
+    mime: String,
+    declared_mime: String,
+    desc: String,
+    len: u32,
+    width: u32,
+    height: u32,
+    color_depth: u32,
+    indexed_color: u32,
+    image: Option<ImageInfo>,
+    data: Cow<'a, [u8]>,
+}
+
+impl Debug for Picture<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Picture")
+            .field("type", &self.r#type)
+            .field("mime", &self.mime)
+            .field("declared_mime", &self.declared_mime)
+            .field("desc", &self.desc)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("color_depth", &self.color_depth)
+            .field("indexed_color", &self.indexed_color)
+            .field("image", &self.image)
+            .field("len", &self.len)
+            .field("data", &format!("[..]({})", self.data.len()))
+            .finish()
+    }
+}
+
+impl<'a> Picture<'a> {
+    /// Picture of the other containers, which only declare the MIME. The image header is sniffed
+    /// beforehand by the caller, the data may have been dropped since.
+    pub(crate) fn new(
+        r#type: PictureType,
+        mime: &str,
+        description: &str,
+        data: &'a [u8],
+        image: Option<ImageInfo>,
+    ) -> Self {
+        Self {
+            r#type,
+            mime: effective_mime(image.as_ref(), mime),
+            declared_mime: mime.to_string(),
+            desc: description.to_string(),
+            len: data.len() as u32,
+            width: 0,
+            height: 0,
+            color_depth: 0,
+            indexed_color: 0,
+            image,
+            data: Cow::Borrowed(data),
+        }
+    }
+    pub(crate) fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::parse(Cow::Borrowed(bytes))
+    }
+    /// Picture of a decoded or reassembled block, the data is moved instead of copied
+    pub(crate) fn from_owned_bytes(bytes: Vec<u8>) -> Picture<'static> {
+        Picture::parse(Cow::Owned(bytes))
+    }
+    fn parse(bytes: Cow<'a, [u8]>) -> Self {
+        let mut reader = ByteReader::new(&bytes);
+        // type
+        let r#type = PictureType::new(reader.read_next_u32(true));
+        // mime
+        let mime_length = reader.read_next_u32(true) as usize;
+        let mime = reader.read_uft8_string(mime_length);
+        // desc
+        let desc_length = reader.read_next_u32(true) as usize;
+        let desc = reader.read_uft8_string(desc_length);
+        // width
+        let width = reader.read_next_u32(true);
+        let height = reader.read_next_u32(true);
+        let color_depth = reader.read_next_u32(true);
+        let indexed_color = reader.read_next_u32(true);
+        let len = reader.read_next_u32(true);
+        let offset = reader.offset();
+        let data = match bytes {
+            Cow::Borrowed(bytes) => Cow::Borrowed(&bytes[offset..]),
+            Cow::Owned(mut bytes) => {
+                bytes.drain(..offset);
+                Cow::Owned(bytes)
+            }
+        };
+        let image = ImageInfo::sniff(&data);
+        Picture {
+            r#type,
+            mime: effective_mime(image.as_ref(), &mime),
+            declared_mime: mime,
+            len,
+            desc,
+            width,
+            height,
+            color_depth,
+            indexed_color,
+            image,
+            data,
+        }
+    }
+    /// Drops the data, the fields read from the image header are kept
+    pub(crate) fn without_data(self) -> Self {
+        Self {
+            data: Cow::Borrowed(&[]),
+            ..self
+        }
+    }
+    pub(crate) fn into_owned(self) -> Picture<'static> {
+        Picture {
+            r#type: self.r#type,
+            mime: self.mime,
+            declared_mime: self.declared_mime,
+            desc: self.desc,
+            len: self.len,
+            width: self.width,
+            height: self.height,
+            color_depth: self.color_depth,
+            indexed_color: self.indexed_color,
+            image: self.image,
+            data: Cow::Owned(self.data.into_owned()),
+        }
+    }
+    pub fn picture_type(&self) -> PictureType {
+        self.r#type
+    }
+    /// MIME of the image format, falls back to the normalized declared MIME, e.g. `image/jpg`
+    /// and `JPG` become `image/jpeg`. `-->` means the data is a link to the image.
+    pub fn mime(&self) -> &str {
+        &self.mime
+    }
+    /// MIME as written by the tagger
+    pub fn declared_mime(&self) -> &str {
+        &self.declared_mime
+    }
+    pub fn description(&self) -> &str {
+        &self.desc
+    }
+    /// Width in pixels of the image header, falls back to the declared width, 0 if unknown
+    pub fn width(&self) -> u32 {
+        self.image.as_ref().map(|it| it.width).unwrap_or(self.width)
+    }
+    /// Height in pixels of the image header, falls back to the declared height, 0 if unknown
+    pub fn height(&self) -> u32 {
+        self.image
+            .as_ref()
+            .map(|it| it.height)
+            .unwrap_or(self.height)
+    }
+    /// Bits per pixel of the image header, falls back to the declared color depth, 0 if unknown
+    pub fn color_depth(&self) -> u32 {
+        match &self.image {
+            Some(image) if image.bit_depth > 0 => image.bit_depth,
+            _ => self.color_depth,
+        }
+    }
+    /// Colors of an indexed-color image, e.g. GIF, 0 for other images
+    pub fn indexed_color(&self) -> u32 {
+        self.indexed_color
+    }
+    /// Format and dimensions read from the image header, None if the format is not recognized
+    pub fn image(&self) -> Option<&ImageInfo> {
+        self.image.as_ref()
+    }
+    /// Empty when parsed with [`crate::ParseOptions::skip_picture_data`]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Bmp,
+    Avif,
+}
+
+impl ImageFormat {
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::WebP => "image/webp",
+            ImageFormat::Bmp => "image/bmp",
+            ImageFormat::Avif => "image/avif",
+        }
+    }
+}
+
+/// Format and dimensions read from the image header, the pixels are not decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageInfo {
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    /// Bits per pixel, 0 if unknown
+    bit_depth: u32,
+}
+
+impl ImageInfo {
+    /// Reads the header of a PNG, JPEG, GIF, WebP, BMP or AVIF image
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Self::png(bytes),
+            [0xFF, 0xD8, ..] => Self::jpeg(bytes),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Self::gif(bytes),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Self::webp(bytes),
+            [b'B', b'M', ..] => Self::bmp(bytes),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..] => Self::avif(bytes),
+            _ => None,
+        }
+    }
+    /// `IHDR` must be the first chunk
+    ///
+    /// ```text
+    /// | signature | length | "IHDR" | width | height | bit depth | color type |
+    /// |    8 B    |  4 B   |  4 B   |  4 B  |  4 B   |    1 B    |    1 B     |
+    /// ```
+    fn png(bytes: &[u8]) -> Option<Self> {
+        if bytes.get(12..16)? != b"IHDR" || bytes.len() < 26 {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(bytes, 16);
+        let width = reader.read_next_u32(true);
+        let height = reader.read_next_u32(true);
+        let bit_depth = reader.read_next_u8() as u32;
+        let samples = match reader.read_next_u8() {
+            // grayscale and indexed
+            0 | 3 => 1,
+            // grayscale with alpha
+            4 => 2,
+            2 => 3,
+            6 => 4,
+            _ => 0,
+        };
+        Some(Self {
+            format: ImageFormat::Png,
+            width,
+            height,
+            bit_depth: bit_depth * samples,
+        })
+    }
+    /// Walks the marker segments until the start of frame, every segment but the standalone
+    /// markers is `| 0xFF | marker | length 2 B, including itself | data |`
+    ///
+    /// ```text
+    /// SOF
+    /// | precision | height | width | components |
+    /// |    1 B    |  2 B   |  2 B  |    1 B     |
+    /// ```
+    fn jpeg(bytes: &[u8]) -> Option<Self> {
+        let mut offset = 2;
+        loop {
+            // fill bytes before the marker
+            while *bytes.get(offset)? == 0xFF && *bytes.get(offset + 1)? == 0xFF {
+                offset += 1;
+            }
+            if bytes[offset] != 0xFF {
+                return None;
+            }
+            let marker = bytes[offset + 1];
+            offset += 2;
+            match marker {
+                0x01 | 0xD0..=0xD7 => continue,
+                // end of image or start of scan before any frame
+                0xD9 | 0xDA => return None,
+                // DHT, JPG and DAC share the range of the SOF markers
+                0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                    let frame = bytes.get(offset + 2..offset + 8)?;
+                    let mut reader = ByteReader::new(frame);
+                    let precision = reader.read_next_u8() as u32;
+                    let height = reader.read_next_u16(true) as u32;
+                    let width = reader.read_next_u16(true) as u32;
+                    let components = reader.read_next_u8() as u32;
+                    return Some(Self {
+                        format: ImageFormat::Jpeg,
+                        width,
+                        height,
+                        bit_depth: precision * components,
+                    });
+                }
+                _ => {
+                    let length = bytes.get(offset..offset + 2)?;
+                    offset += u16::from_be_bytes([length[0], length[1]]) as usize;
+                }
+            }
+        }
+    }
+    /// ```text
+    /// | "GIF87a" or "GIF89a" | width | height | flags |
+    /// |         6 B          | 2 B   |  2 B   |  1 B  |
+    /// ```
+    /// The lowest 3 bits of the flags are the size of the global color table
+    fn gif(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 11 {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(bytes, 6);
+        Some(Self {
+            format: ImageFormat::Gif,
+            width: reader.read_next_u16(false) as u32,
+            height: reader.read_next_u16(false) as u32,
+            bit_depth: (reader.read_next_u8() & 0x07) as u32 + 1,
+        })
+    }
+    /// The first chunk after `| "RIFF" | size | "WEBP" |` tells the encoding, all fields are
+    /// little-endian
+    ///
+    /// ```text
+    /// "VP8 " lossy
+    /// | chunk header | frame tag | 0x9D 0x01 0x2A | width, 14 b | height, 14 b |
+    /// |     8 B      |    3 B    |      3 B       |     2 B     |     2 B      |
+    /// "VP8L" lossless
+    /// | chunk header | 0x2F | width - 1, 14 b | height - 1, 14 b | alpha, 1 b |
+    /// "VP8X" extended
+    /// | chunk header | flags | reserved | canvas width - 1 | canvas height - 1 |
+    /// |     8 B      |  1 B  |   3 B    |       3 B        |        3 B        |
+    /// ```
+    fn webp(bytes: &[u8]) -> Option<Self> {
+        let (width, height, bit_depth) = match bytes.get(12..16)? {
+            b"VP8 " => {
+                if bytes.get(23..26)? != [0x9D, 0x01, 0x2A] || bytes.len() < 30 {
+                    return None;
+                }
+                let mut reader = ByteReader::with_offset(bytes, 26);
+                let width = reader.read_next_u16(false) & 0x3FFF;
+                let height = reader.read_next_u16(false) & 0x3FFF;
+                (width as u32, height as u32, 24)
+            }
+            b"VP8L" => {
+                if *bytes.get(20)? != 0x2F || bytes.len() < 25 {
+                    return None;
+                }
+                let bits = ByteReader::with_offset(bytes, 21).read_next_u32(false);
+                let alpha = bits >> 28 & 0x01 != 0;
+                (
+                    (bits & 0x3FFF) + 1,
+                    (bits >> 14 & 0x3FFF) + 1,
+                    if alpha { 32 } else { 24 },
+                )
+            }
+            b"VP8X" => {
+                if bytes.len() < 30 {
+                    return None;
+                }
+                let alpha = bytes[20] & 0x10 != 0;
+                let mut reader = ByteReader::with_offset(bytes, 24);
+                let width = reader.read_next_u24(false) + 1;
+                let height = reader.read_next_u24(false) + 1;
+                (width, height, if alpha { 32 } else { 24 })
+            }
+            _ => return None,
+        };
+        Some(Self {
+            format: ImageFormat::WebP,
+            width,
+            height,
+            bit_depth,
+        })
+    }
+    /// All fields are little-endian, the DIB header follows the 14 bytes file header
+    ///
+    /// ```text
+    /// BITMAPCOREHEADER
+    /// | size (12) | width | height | planes | bits per pixel |
+    /// |    4 B    |  2 B  |  2 B   |  2 B   |      2 B       |
+    /// BITMAPINFOHEADER and later
+    /// | size | width | height, negative when top-down | planes | bits per pixel |
+    /// | 4 B  |  4 B  |              4 B               |  2 B   |      2 B       |
+    /// ```
+    fn bmp(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 26 {
+            return None;
+        }
+        let mut reader = ByteReader::with_offset(bytes, 14);
+        let (width, height, bit_depth) = match reader.read_next_u32(false) {
+            12 => {
+                let width = reader.read_next_u16(false) as u32;
+                let height = reader.read_next_u16(false) as u32;
+                reader.skip(2);
+                (width, height, reader.read_next_u16(false))
+            }
+            size if size >= 40 && bytes.len() >= 30 => {
+                let width = reader.read_next_i32(false).unsigned_abs();
+                let height = reader.read_next_i32(false).unsigned_abs();
+                reader.skip(2);
+                (width, height, reader.read_next_u16(false))
+            }
+            _ => return None,
+        };
+        Some(Self {
+            format: ImageFormat::Bmp,
+            width,
+            height,
+            bit_depth: bit_depth as u32,
+        })
+    }
+    /// HEIF boxes, the properties live in `meta/iprp/ipco`
+    ///
+    /// ```text
+    /// ispe
+    /// | version/flags | width | height |
+    /// |      4 B      |  4 B  |  4 B   |
+    /// pixi
+    /// | version/flags | channels | bits per channel * |
+    /// |      4 B      |   1 B    |     1 B each       |
+    /// ```
+    /// A grid image stores each tile as an item with its own `ispe`, the largest one is taken.
+    fn avif(bytes: &[u8]) -> Option<Self> {
+        let mut atoms = Atoms::new(bytes);
+        let ftyp = atoms.next()?;
+        // major brand, minor version, then the compatible brands
+        let is_avif = ftyp
+            .data
+            .chunks_exact(4)
+            .enumerate()
+            .any(|(i, brand)| i != 1 && matches!(brand, b"avif" | b"avis"));
+        if !is_avif {
+            return None;
+        }
+        let meta = atoms.find(|it| &it.name == b"meta")?;
+        let iprp = Atoms::new(meta.data.get(4..)?).find(|it| &it.name == b"iprp")?;
+        let ipco = Atoms::new(iprp.data).find(|it| &it.name == b"ipco")?;
+        let mut image = Self {
+            format: ImageFormat::Avif,
+            width: 0,
+            height: 0,
+            bit_depth: 0,
+        };
+        for property in Atoms::new(ipco.data) {
+            match &property.name {
+                b"ispe" if property.data.len() >= 12 => {
+                    let mut reader = ByteReader::with_offset(property.data, 4);
+                    let width = reader.read_next_u32(true);
+                    let height = reader.read_next_u32(true);
+                    if width as u64 * height as u64 > image.width as u64 * image.height as u64 {
+                        image.width = width;
+                        image.height = height;
+                    }
+                }
+                b"pixi" if image.bit_depth == 0 && property.data.len() >= 5 => {
+                    let channels = property.data[4] as usize;
+                    image.bit_depth = property
+                        .data
+                        .get(5..5 + channels)
+                        .map(|it| it.iter().map(|it| *it as u32).sum())
+                        .unwrap_or(0);
+                }
+                _ => (),
+            }
+        }
+        (image.width > 0).then_some(image)
+    }
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    /// Bits per pixel, 0 if unknown
+    pub fn bit_depth(&self) -> u32 {
+        self.bit_depth
+    }
+}
+
+/// The best front cover, ranked by picture type, then pixel area and data size, the earlier
+/// picture wins a tie
+pub(crate) fn front_cover<'a>(pictures: Vec<Picture<'a>>) -> Option<Picture<'a>> {
+    pictures
+        .into_iter()
+        .enumerate()
+        .min_by_key(|(index, it)| {
+            (
+                it.r#type.cover_rank(),
+                Reverse(it.width() as u64 * it.height() as u64),
+                Reverse(it.data.len()),
+                *index,
+            )
+        })
+        .map(|(_, it)| it)
+}
+
+fn effective_mime(image: Option<&ImageInfo>, declared: &str) -> String {
+    match image {
+        Some(image) => image.format.mime().to_string(),
+        None => normalize_mime(declared),
+    }
+}
+
+/// Normalizes the MIME declared by taggers, e.g. `image/jpg`, `JPG` (ID3v2.2) or `jpeg`
+pub(crate) fn normalize_mime(mime: &str) -> String {
+    let mime = mime.trim().to_ascii_lowercase();
+    let subtype = mime.strip_prefix("image/").unwrap_or(&mime);
+    let normalized = match subtype {
+        "jpg" | "jpeg" | "pjpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" | "x-bmp" | "x-ms-bmp" => "image/bmp",
+        "avif" => "image/avif",
+        "" => return String::new(),
+        // the link MIME of ID3, or an already complete MIME
+        _ if mime == "-->" || mime.contains('/') => return mime,
+        subtype => return format!("image/{}", subtype),
+    };
+    normalized.to_string()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Header of a square RGB PNG
+    pub(crate) fn png(size: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\x0D\x0A\x1A\x0A\x00\x00\x00\x0DIHDR".to_vec();
+        bytes.extend(size.to_be_bytes());
+        bytes.extend(size.to_be_bytes());
+        bytes.extend([8, 2, 0, 0, 0]);
+        bytes
+    }
+
+    fn atom(name: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = ((data.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend(name);
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_sniff() {
+        // 600 x 400 RGBA
+        let mut png = b"\x89PNG\x0D\x0A\x1A\x0A\x00\x00\x00\x0DIHDR".to_vec();
+        png.extend(600u32.to_be_bytes());
+        png.extend(400u32.to_be_bytes());
+        png.extend([8, 6, 0, 0, 0]);
+        let image = ImageInfo::sniff(&png).unwrap();
+        assert_eq!(image.format(), ImageFormat::Png);
+        assert_eq!(
+            (image.width(), image.height(), image.bit_depth()),
+            (600, 400, 32)
+        );
+
+        // APP0 then a progressive SOF2 of 1000 x 800 YCbCr
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend([0xFF, 0xC4, 0x00, 0x02]);
+        jpeg.extend([0xFF, 0xC2, 0x00, 0x11, 0x08, 0x03, 0x20, 0x03, 0xE8, 0x03]);
+        let image = ImageInfo::sniff(&jpeg).unwrap();
+        assert_eq!(image.format(), ImageFormat::Jpeg);
+        assert_eq!(
+            (image.width(), image.height(), image.bit_depth()),
+            (1000, 800, 24)
+        );
+
+        let gif = b"GIF89a\x20\x03\x58\x02\xF7\x00\x00";
+        let image = ImageInfo::sniff(gif).unwrap();
+        assert_eq!(
+            (image.width(), image.height(), image.bit_depth()),
+            (800, 600, 8)
+        );
+
+        let mut webp = b"RIFF\x00\x00\x00\x00WEBPVP8L\x00\x00\x00\x00\x2F".to_vec();
+        // 500 x 300 without alpha
+        webp.extend((499u32 | 299 << 14).to_le_bytes());
+        let image = ImageInfo::sniff(&webp).unwrap();
+        assert_eq!(image.format(), ImageFormat::WebP);
+        assert_eq!(
+            (image.width(), image.height(), image.bit_depth()),
+            (500, 300, 24)
+        );
+
+        let mut bmp = b"BM".to_vec();
+        bmp.extend([0x00; 12]);
+        bmp.extend(40u32.to_le_bytes());
+        bmp.extend(640i32.to_le_bytes());
+        bmp.extend((-480i32).to_le_bytes());
+        bmp.extend(1u16.to_le_bytes());
+        bmp.extend(24u16.to_le_bytes());
+        let image = ImageInfo::sniff(&bmp).unwrap();
+        assert_eq!(
+            (image.width(), image.height(), image.bit_depth()),
+            (640, 480, 24)
+        );
+
+        let mut ispe = vec![0x00; 4];
+        ispe.extend(1200u32.to_be_bytes());
+        ispe.extend(1200u32.to_be_bytes());
+        let ipco = [
+            atom(b"ispe", &ispe),
+            atom(b"pixi", &[0x00, 0x00, 0x00, 0x00, 3, 10, 10, 10]),
+        ]
+        .concat();
+        let mut meta = vec![0x00; 4];
+        meta.extend(atom(b"iprp", &atom(b"ipco", &ipco)));
+        let avif = [
+            atom(b"ftyp", b"avif\x00\x00\x00\x00mif1miaf"),
+            atom(b"meta", &meta),
+        ]
+        .concat();
+        let image = ImageInfo::sniff(&avif).unwrap();
+        assert_eq!(image.format(), ImageFormat::Avif);
+        assert_eq!(
+            (image.width(), image.height(), image.bit_depth()),
+            (1200, 1200, 30)
+        );
+
+        assert_eq!(ImageInfo::sniff(b"-->"), None);
+    }
+
+    #[test]
+    fn test_picture() {
+        let png = png(300);
+        // declared as a 0 x 0 JPEG
+        let image = ImageInfo::sniff(&png);
+        let picture = Picture::new(PictureType::FrontCover, "image/jpg", "", &png, image);
+        assert_eq!(picture.mime(), "image/png");
+        assert_eq!(picture.declared_mime(), "image/jpg");
+        assert_eq!((picture.width(), picture.height()), (300, 300));
+        assert_eq!(picture.color_depth(), 24);
+
+        let picture = Picture::new(PictureType::FrontCover, "JPG", "", &[0x00; 4], None);
+        assert_eq!(picture.image(), None);
+        assert_eq!(picture.mime(), "image/jpeg");
+        assert_eq!(normalize_mime("-->"), "-->");
+        assert_eq!(normalize_mime("image/x-ms-bmp"), "image/bmp");
+    }
+
+    #[test]
+    fn test_front_cover() {
+        let (large, medium, small) = (png(1000), png(800), png(200));
+        let picture = |r#type, description, data| {
+            Picture::new(r#type, "", description, data, ImageInfo::sniff(data))
+        };
+        let pictures = vec![
+            picture(PictureType::BackCover, "back", &large),
+            picture(PictureType::Other, "other", &medium),
+            picture(PictureType::FrontCover, "thumbnail", &small),
+            picture(PictureType::FrontCover, "front", &medium),
+            picture(PictureType::FrontCover, "copy", &medium),
+        ];
+        assert_eq!(front_cover(pictures.clone()).unwrap().description(), "front");
+        let pictures = pictures.into_iter().take(2).collect();
+        assert_eq!(front_cover(pictures).unwrap().description(), "other");
+        assert!(front_cover(Vec::new()).is_none());
+        assert_eq!(PictureType::new(3), PictureType::FrontCover);
+        assert_eq!(PictureType::new(21), PictureType::Other);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::aac::Aac;
+use crate::ac3::Ac3;
+use crate::aiff::Aiff;
+use crate::caf::Caf;
+use crate::dsd::{Dff, Dsf};
+use crate::flac::Flac;
+use crate::id3::{id3v2_length, ID3};
+use crate::matroska::Matroska;
+use crate::monkeys_audio::MonkeysAudio;
+use crate::mp4::Mp4;
+use crate::mpeg::MpegAudio;
+use crate::ogg::Ogg;
+use crate::reader::Reader;
+use crate::tta::Tta;
+use crate::wav::Wav;
+use crate::wavpack::WavPack;
+use std::time::Duration;
+
+/// Container format of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Flac,
+    Ogg,
+    /// MPEG audio frames, optionally wrapped by ID3 tags
+    Mpeg,
+    Mp4,
+    Wav,
+    Aiff,
+    Matroska,
+    WebM,
+    Dsf,
+    Dff,
+    WavPack,
+    MonkeysAudio,
+    Tta,
+    Adts,
+    Adif,
+    Ac3,
+    Caf,
+}
+
+/// Audio stream properties shared by the readers
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioProperties {
+    container: Container,
+    codec: String,
+    duration: Option<Duration>,
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: Option<u16>,
+    bitrate: Option<u32>,
+    lossless: bool,
+}
+
+impl AudioProperties {
+    pub(crate) fn new(container: Container, codec: impl Into<String>) -> Self {
+        Self {
+            container,
+            codec: codec.into(),
+            duration: None,
+            sample_rate: 0,
+            channels: 0,
+            bits_per_sample: None,
+            bitrate: None,
+            lossless: false,
+        }
+    }
+    pub(crate) fn with_duration(mut self, duration: Option<Duration>) -> Self {
+        self.duration = duration;
+        self
+    }
+    pub(crate) fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+    pub(crate) fn with_channels(mut self, channels: u16) -> Self {
+        self.channels = channels;
+        self
+    }
+    pub(crate) fn with_bits_per_sample(mut self, bits_per_sample: Option<u16>) -> Self {
+        self.bits_per_sample = bits_per_sample;
+        self
+    }
+    pub(crate) fn with_bitrate(mut self, bitrate: Option<u32>) -> Self {
+        self.bitrate = bitrate;
+        self
+    }
+    pub(crate) fn with_lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+    pub fn container(&self) -> Container {
+        self.container
+    }
+    /// Codec name, e.g. `FLAC`, `MP3`, `AAC`, `Opus`, `PCM`
+    pub fn codec(&self) -> &str {
+        &self.codec
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+    /// 0 if unknown
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// 0 if unknown
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    /// Bits per sample of the decoded audio, None for lossy codecs
+    pub fn bits_per_sample(&self) -> Option<u16> {
+        self.bits_per_sample
+    }
+    /// Average bitrate in kbps
+    pub fn bitrate(&self) -> Option<u32> {
+        self.bitrate
+    }
+    pub fn is_lossless(&self) -> bool {
+        self.lossless
+    }
+}
+
+/// Audio properties of any supported file, without knowing its format beforehand
+///
+/// Formats with a signature are checked first, then the raw streams which may be prefixed by
+/// ID3v2 tags, and finally MPEG audio frames. Returns None if the format is not recognized or
+/// the file has no audio stream description.
+pub fn audio_properties(bytes: &[u8]) -> Option<AudioProperties> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    match bytes {
+        bytes if Flac::is(bytes) => Flac::from_bytes(bytes).properties(),
+        bytes if Ogg::is(bytes) => Ogg::from_bytes(bytes).properties(),
+        bytes if Mp4::is(bytes) => Mp4::from_bytes(bytes).properties(),
+        bytes if Wav::is(bytes) => Wav::from_bytes(bytes).properties(),
+        bytes if Aiff::is(bytes) => Aiff::from_bytes(bytes).properties(),
+        bytes if Caf::is(bytes) => Caf::from_bytes(bytes).properties(),
+        bytes if Matroska::is(bytes) => Matroska::from_bytes(bytes).properties(),
+        bytes if Dsf::is(bytes) => Some(Dsf::from_bytes(bytes).properties()),
+        bytes if Dff::is(bytes) => Some(Dff::from_bytes(bytes).properties()),
+        bytes if WavPack::is(bytes) => Some(WavPack::from_bytes(bytes).properties()),
+        bytes if MonkeysAudio::is(bytes) => Some(MonkeysAudio::from_bytes(bytes).properties()),
+        bytes if Tta::is(bytes) => Some(Tta::from_bytes(bytes).properties()),
+        bytes if Aac::is(bytes) => Some(Aac::from_bytes(bytes).properties()),
+        bytes if Ac3::is(bytes) => Some(Ac3::from_bytes(bytes).properties()),
+        bytes if ID3::is(bytes) => {
+            // FLAC files written with a leading ID3v2 tag
+            let offset = id3v2_length(bytes);
+            match &bytes[offset..] {
+                flac if flac.len() >= 4 && Flac::is(flac) => Flac::from_bytes(flac).properties(),
+                _ => ID3::from_bytes(bytes).properties(),
+            }
+        }
+        bytes => MpegAudio::new(bytes, 0, bytes.len(), false).map(|it| it.properties()),
+    }
+}
+
+/// Duration of `samples` at `sample_rate`, None when the rate is unknown
+pub(crate) fn samples_duration(samples: u64, sample_rate: u32) -> Option<Duration> {
+    if sample_rate == 0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(samples as f64 / sample_rate as f64))
+}
+
+/// Average bitrate in kbps of `size` bytes played for `duration`
+pub(crate) fn average_bitrate(size: u64, duration: Option<Duration>) -> Option<u32> {
+    let duration = duration?.as_micros();
+    if duration == 0 {
+        return None;
+    }
+    Some((size as u128 * 8 * 1000 / duration) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_properties() {
+        // 44.1 kHz stereo 16 bits PCM, 1 second
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((36u32 + 176400).to_le_bytes());
+        bytes.extend(b"WAVEfmt ");
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(44100u32.to_le_bytes());
+        bytes.extend(176400u32.to_le_bytes());
+        bytes.extend(4u16.to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend(176400u32.to_le_bytes());
+        bytes.extend(vec![0x00; 176400]);
+        let properties = audio_properties(&bytes).unwrap();
+        assert_eq!(properties.container(), Container::Wav);
+        assert_eq!(properties.codec(), "PCM");
+        assert_eq!(properties.duration(), Some(Duration::from_secs(1)));
+        assert_eq!(properties.sample_rate(), 44100);
+        assert_eq!(properties.channels(), 2);
+        assert_eq!(properties.bits_per_sample(), Some(16));
+        assert_eq!(properties.bitrate(), Some(1411));
+        assert!(properties.is_lossless());
+
+        // MPEG-1 Layer III, 128 kbps, 44.1 kHz joint stereo, without any tag
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x40];
+        frame.resize(417, 0x00);
+        let properties = audio_properties(&frame.repeat(10)).unwrap();
+        assert_eq!(properties.container(), Container::Mpeg);
+        assert_eq!(properties.codec(), "MP3");
+        assert_eq!(properties.sample_rate(), 44100);
+        assert_eq!(properties.channels(), 2);
+        assert_eq!(properties.bitrate(), Some(128));
+        assert!(!properties.is_lossless());
+
+        assert_eq!(audio_properties(b"not audio"), None);
+    }
+}
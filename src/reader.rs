@@ -1,4 +1,22 @@
-pub trait Reader {
-    fn from_bytes(bytes: &[u8]) -> Self;
+use crate::error::Error;
+use crate::options::ParseOptions;
+
+/// Parses a file, the parsed binary payloads (e.g. pictures) borrow from `bytes`
+pub trait Reader<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self;
     fn is(bytes: &[u8]) -> bool;
+    /// Parse with non-default options, readers without optional work ignore them.
+    fn from_bytes_with_options(bytes: &'a [u8], _options: &ParseOptions) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_bytes(bytes)
+    }
+    /// Parse with options, returning the errors found by the strict mode instead of panicking.
+    fn try_from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Ok(Self::from_bytes_with_options(bytes, options))
+    }
 }
@@ -0,0 +1,124 @@
+use crate::id3::id3v2_length;
+use crate::properties::{average_bitrate, samples_duration, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::{crc32, ByteReader};
+use std::time::Duration;
+
+/// TTA (True Audio) file, all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | "TTA1" | format | channels | bits per sample | sample rate | samples | crc32 | seek table |
+/// |  4 B   |  2 B   |   2 B    |       2 B       |     4 B     |   4 B   |  4 B  |            |
+/// ```
+/// The CRC covers the first 18 bytes of the header.
+#[derive(Debug)]
+pub struct Tta {
+    /// 1 PCM, 2 encrypted
+    format: u16,
+    channels: u16,
+    bits_per_sample: u16,
+    sample_rate: u32,
+    /// Samples per channel
+    samples: u32,
+    checksum: u32,
+    computed_checksum: u32,
+    /// Size of the file, excluding the leading tags
+    audio_size: usize,
+}
+
+impl Reader<'_> for Tta {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        if !Tta::is(bytes) {
+            panic!("Invalid tta audio format.");
+        }
+        let offset = id3v2_length(bytes);
+        let mut reader = ByteReader::with_offset(bytes, offset + 4);
+        Self {
+            format: reader.read_next_u16(false),
+            channels: reader.read_next_u16(false),
+            bits_per_sample: reader.read_next_u16(false),
+            sample_rate: reader.read_next_u32(false),
+            samples: reader.read_next_u32(false),
+            checksum: reader.read_next_u32(false),
+            computed_checksum: crc32(&bytes[offset..offset + 18]),
+            audio_size: bytes.len() - offset,
+        }
+    }
+    fn is(bytes: &[u8]) -> bool {
+        let offset = id3v2_length(bytes);
+        bytes.len() >= offset + 22 && bytes[offset..offset + 4] == *b"TTA1"
+    }
+}
+
+impl Tta {
+    /// 1 PCM, 2 encrypted
+    pub fn format(&self) -> u16 {
+        self.format
+    }
+    pub fn is_encrypted(&self) -> bool {
+        self.format == 2
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    pub fn bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// Samples per channel
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+    /// Whether the header matches its CRC
+    pub fn is_valid(&self) -> bool {
+        self.checksum == self.computed_checksum
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        samples_duration(self.samples as u64, self.sample_rate)
+    }
+    pub fn properties(&self) -> AudioProperties {
+        AudioProperties::new(Container::Tta, "TTA")
+            .with_duration(self.duration())
+            .with_sample_rate(self.sample_rate)
+            .with_channels(self.channels)
+            .with_bits_per_sample(Some(self.bits_per_sample))
+            .with_bitrate(average_bitrate(self.audio_size as u64, self.duration()))
+            .with_lossless(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tta() {
+        let mut header = b"TTA1".to_vec();
+        header.extend(1u16.to_le_bytes());
+        header.extend(2u16.to_le_bytes());
+        header.extend(16u16.to_le_bytes());
+        header.extend(44100u32.to_le_bytes());
+        header.extend(132300u32.to_le_bytes());
+        let checksum = crc32(&header);
+        header.extend(checksum.to_le_bytes());
+        // prefixed by an empty ID3v2 tag
+        let mut bytes = b"ID3\x03\x00\x00\x00\x00\x00\x00".to_vec();
+        bytes.extend(header);
+        let tta = Tta::from_bytes(&bytes);
+        assert!(tta.is_valid());
+        assert!(!tta.is_encrypted());
+        assert_eq!(
+            tta.properties(),
+            AudioProperties::new(Container::Tta, "TTA")
+                .with_duration(Some(Duration::from_secs(3)))
+                .with_sample_rate(44100)
+                .with_channels(2)
+                .with_bits_per_sample(Some(16))
+                .with_bitrate(Some(0))
+                .with_lossless(true)
+        );
+    }
+}
@@ -14,10 +14,10 @@ pub(crate) struct ByteReader<'a> {
     bytes: &'a [u8],
 }
 impl<'a> ByteReader<'a> {
-    pub(crate) fn new(bytes: &'a [u8]) -> ByteReader {
+    pub(crate) fn new(bytes: &'a [u8]) -> ByteReader<'a> {
         Self { bytes, offset: 0 }
     }
-    pub(crate) fn with_offset(bytes: &'a [u8], offset: usize) -> ByteReader {
+    pub(crate) fn with_offset(bytes: &'a [u8], offset: usize) -> ByteReader<'a> {
         Self { bytes, offset }
     }
     pub(crate) fn len(&self) -> usize {
@@ -72,6 +72,14 @@ impl<'a> ByteReader<'a> {
             u16::from_le_bytes(self.read(2).try_into().unwrap())
         }
     }
+    pub(crate) fn read_next_u24(&mut self, is_big_endian: bool) -> u32 {
+        let bytes = self.read(3);
+        if is_big_endian {
+            u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]])
+        } else {
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+        }
+    }
     pub(crate) fn read_next_u32(&mut self, is_big_endian: bool) -> u32 {
         if is_big_endian {
             u32::from_be_bytes(self.read(4).try_into().unwrap())
@@ -93,6 +101,9 @@ impl<'a> ByteReader<'a> {
             u64::from_le_bytes(self.read(8).try_into().unwrap())
         }
     }
+    pub(crate) fn read_next_i64(&mut self, is_big_endian: bool) -> i64 {
+        self.read_next_u64(is_big_endian) as i64
+    }
     pub(crate) fn read_uft8_string(&mut self, len: usize) -> String {
         self.read_string(len, CharacterEncoding::Utf8)
     }
@@ -163,10 +174,10 @@ impl<'a> ByteReader<'a> {
         }
     }
 }
-pub(crate) fn debug_vec(vec: &Vec<u8>) -> String {
+pub(crate) fn debug_vec(vec: &[u8]) -> String {
     format!(
         "[{}..]({})",
-        vec[..3]
+        vec[..vec.len().min(3)]
             .iter()
             .map(|it| format!("{:02x},", it))
             .collect::<String>()
@@ -222,6 +233,97 @@ pub(crate) fn crc32(bytes: &[u8]) -> u32 {
     crc32
 }
 
+/// Reads `count` bits MSB first starting at the bit `position`, bits past the end read as 0
+pub(crate) fn read_bits(bytes: &[u8], position: &mut usize, count: u32) -> u32 {
+    let mut value = 0u32;
+    for _ in 0..count {
+        let byte = bytes.get(*position / 8).copied().unwrap_or(0);
+        value = value << 1 | ((byte >> (7 - *position % 8)) & 0x01) as u32;
+        *position += 1;
+    }
+    value
+}
+
+/// CRC-32 with polynomial 0x04C11DB7, MSB first, no reflection, init and final xor 0,
+/// used by Ogg pages.
+pub(crate) fn crc32_ogg(bytes: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for byte in bytes {
+        crc ^= (*byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-8 with polynomial 0x07, used by FLAC frame headers.
+pub(crate) fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for byte in bytes {
+        crc ^= *byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-16 with polynomial 0x8005, MSB first, used by MPEG audio frames (init 0xFFFF)
+/// and FLAC frames (init 0x0000).
+pub(crate) fn crc16(bytes: &[u8], init: u16) -> u16 {
+    let mut crc = init;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-16 of the first `bits` bits of `bytes`, for the MPEG audio ranges ending mid-byte
+pub(crate) fn crc16_bits(bytes: &[u8], bits: usize, init: u16) -> u16 {
+    let mut crc = crc16(&bytes[..bits / 8], init);
+    for position in bits / 8 * 8..bits {
+        let bit = (bytes[position / 8] >> (7 - position % 8)) & 0x01;
+        crc = if (crc >> 15) as u8 ^ bit != 0 {
+            (crc << 1) ^ 0x8005
+        } else {
+            crc << 1
+        };
+    }
+    crc
+}
+
+/// CRC-16/ARC (reflected 0x8005, init 0x0000), used by the LAME tag.
+pub(crate) fn crc16_arc(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in bytes {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 0x0001 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
 pub(crate) fn u8_to_u16(bytes: &[u8], is_big_endian: bool) -> Vec<u16> {
     let len = bytes.len();
     let mut cur = 0;
@@ -271,6 +373,24 @@ mod tests {
         assert_eq!(crc32, expected_crc32);
     }
 
+    #[test]
+    fn test_crc32_ogg() {
+        assert_eq!(crc32_ogg(b"123456789"), 0x89A1897F);
+    }
+
+    #[test]
+    fn test_crc8() {
+        assert_eq!(crc8(b"123456789"), 0xF4);
+    }
+
+    #[test]
+    fn test_crc16() {
+        // check values of the CRC catalogue for "123456789"
+        assert_eq!(crc16(b"123456789", 0x0000), 0xFEE8);
+        assert_eq!(crc16(b"123456789", 0xFFFF), 0xAEE7);
+        assert_eq!(crc16_arc(b"123456789"), 0xBB3D);
+    }
+
     #[test]
     fn test_u8_to_u16() {
         // big endian
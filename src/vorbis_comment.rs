@@ -21,15 +21,16 @@ impl VorbisComment {
         };
         // comment list length
         let length = reader.read_next_u32(false) as usize;
-        let mut comments: Vec<(String, String)> = Vec::with_capacity(length);
-        loop {
+        // every comment takes at least its 4 bytes length, the declared count is untrusted
+        let remaining = reader.len().saturating_sub(reader.offset());
+        let mut comments: Vec<(String, String)> = Vec::with_capacity(length.min(remaining / 4));
+        while comments.len() < length && !reader.is_end() {
             // comment value len
             let len = reader.read_next_u32(false) as usize;
             let str = reader.read_uft8_string(len);
-            let part = str.splitn(2, '=').collect::<Vec<_>>();
-            comments.push((part[0].to_string(), part[1].to_string()));
-            if reader.is_end() || comments.len() == length {
-                break;
+            // a comment without `=` is malformed and skipped
+            if let Some((key, value)) = str.split_once('=') {
+                comments.push((key.to_string(), value.to_string()));
             }
         }
         Self { vendor, comments }
@@ -41,3 +42,20 @@ impl VorbisComment {
         &self.comments
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_count() {
+        let mut bytes = 4u32.to_le_bytes().to_vec();
+        bytes.extend(b"test");
+        bytes.extend(u32::MAX.to_le_bytes());
+        bytes.extend(10u32.to_le_bytes());
+        bytes.extend(b"TITLE=test");
+        let comment = VorbisComment::new(&bytes);
+        assert_eq!(comment.vendor(), "test");
+        assert_eq!(comment.comments(), [("TITLE".to_string(), "test".to_string())]);
+    }
+}
@@ -0,0 +1,584 @@
+use crate::id3::ID3;
+use crate::options::ParseOptions;
+use crate::picture::Picture;
+use crate::properties::{average_bitrate, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::ByteReader;
+use std::time::Duration;
+
+/// Speaker positions of the WAVE_FORMAT_EXTENSIBLE channel mask, from the lowest bit
+const SPEAKERS: [&str; 18] = [
+    "FL", "FR", "FC", "LFE", "BL", "BR", "FLC", "FRC", "BC", "SL", "SR", "TC", "TFL", "TFC", "TFR",
+    "TBL", "TBC", "TBR",
+];
+
+/// RIFF container flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiffFormat {
+    Riff,
+    /// EBU Tech 3306 large file, sizes above 4 GiB are stored in the `ds64` chunk
+    Rf64,
+    /// ITU-R BS.2088 large file, same layout as RF64
+    Bw64,
+}
+
+/// WAV file (RIFF WAVE), all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | "RIFF" | size | "WAVE" | chunk * |
+/// |  4 B   | 4 B  |  4 B   |         |
+/// ```
+/// Each chunk is `| id 4 B | size 4 B | data | pad byte if the size is odd |`. RF64 and BW64
+/// files set the sizes to 0xFFFFFFFF and store the real sizes in the leading `ds64` chunk.
+#[derive(Debug)]
+pub struct Wav<'a> {
+    format: RiffFormat,
+    wave_format: Option<WaveFormat>,
+    /// Offset of the audio samples
+    data_offset: usize,
+    data_size: u64,
+    /// Samples per channel of the `fact` chunk (compressed formats) or `ds64` chunk
+    sample_count: Option<u64>,
+    /// `LIST/INFO` entries, e.g. (`INAM`, title)
+    info: Vec<(String, String)>,
+    id3: Option<ID3<'a>>,
+    bext: Option<BroadcastExtension>,
+}
+
+impl<'a> Reader<'a> for Wav<'a> {
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+    fn from_bytes_with_options(bytes: &'a [u8], options: &ParseOptions) -> Self {
+        if !Wav::is(bytes) {
+            panic!("Invalid wav audio format.");
+        }
+        let format = match &bytes[0..4] {
+            b"RF64" => RiffFormat::Rf64,
+            b"BW64" => RiffFormat::Bw64,
+            _ => RiffFormat::Riff,
+        };
+        let mut wav = Self {
+            format,
+            wave_format: None,
+            data_offset: 0,
+            data_size: 0,
+            sample_count: None,
+            info: Vec::new(),
+            id3: None,
+            bext: None,
+        };
+        // data size of the ds64 chunk
+        let mut ds64_data_size = None;
+        let mut reader = ByteReader::with_offset(bytes, 12);
+        while reader.offset() + 8 <= bytes.len() {
+            let id: [u8; 4] = reader.read(4).try_into().unwrap();
+            let size = reader.read_next_u32(false);
+            let start = reader.offset();
+            let size = match (&id, size) {
+                (b"data", 0xFFFF_FFFF) => ds64_data_size.unwrap_or(u64::MAX),
+                (_, size) => size as u64,
+            };
+            // the last chunk may be truncated
+            let end = (start as u64).saturating_add(size).min(bytes.len() as u64) as usize;
+            let data = &bytes[start..end];
+            match &id {
+                b"ds64" if data.len() >= 24 => {
+                    let mut reader = ByteReader::with_offset(data, 8);
+                    ds64_data_size = Some(reader.read_next_u64(false));
+                    wav.sample_count = Some(reader.read_next_u64(false)).filter(|it| *it != 0);
+                }
+                b"fmt " if data.len() >= 16 => wav.wave_format = Some(WaveFormat::new(data)),
+                b"fact" if data.len() >= 4 && wav.sample_count.is_none() => {
+                    wav.sample_count = Some(ByteReader::new(data).read_next_u32(false) as u64);
+                }
+                b"data" => {
+                    wav.data_offset = start;
+                    wav.data_size = size.min((bytes.len() - start) as u64);
+                }
+                b"LIST" if data.len() >= 4 && data[0..4] == *b"INFO" => {
+                    wav.info = Wav::parse_info(&data[4..]);
+                }
+                b"id3 " | b"ID3 " if data.len() >= 10 && ID3::is(data) => {
+                    wav.id3 = Some(ID3::from_bytes_with_options(data, options));
+                }
+                b"bext" if data.len() >= 602 => wav.bext = Some(BroadcastExtension::new(data)),
+                _ => (),
+            }
+            // chunks are word aligned
+            let next = (start as u64).saturating_add(size).saturating_add(size & 1);
+            if next > bytes.len() as u64 {
+                break;
+            }
+            reader.seek(std::io::SeekFrom::Start(next));
+        }
+        wav
+    }
+    fn is(bytes: &[u8]) -> bool {
+        bytes.len() >= 12
+            && matches!(&bytes[0..4], b"RIFF" | b"RF64" | b"BW64")
+            && bytes[8..12] == *b"WAVE"
+    }
+}
+
+impl<'a> Wav<'a> {
+    /// `LIST/INFO` sub chunks, each value is a null terminated string
+    fn parse_info(bytes: &[u8]) -> Vec<(String, String)> {
+        let mut info = Vec::new();
+        let mut offset = 0;
+        while offset + 8 <= bytes.len() {
+            let id = String::from_utf8_lossy(&bytes[offset..offset + 4]).into_owned();
+            let size =
+                u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            let start = offset + 8;
+            let end = (start + size).min(bytes.len());
+            let value = String::from_utf8_lossy(&bytes[start..end])
+                .trim_end_matches('\0')
+                .to_string();
+            info.push((id, value));
+            offset = start + size + (size & 1);
+        }
+        info
+    }
+    pub fn format(&self) -> RiffFormat {
+        self.format
+    }
+    pub fn wave_format(&self) -> Option<&WaveFormat> {
+        self.wave_format.as_ref()
+    }
+    pub fn data_offset(&self) -> usize {
+        self.data_offset
+    }
+    /// Size of the audio samples
+    pub fn data_size(&self) -> u64 {
+        self.data_size
+    }
+    /// Samples per channel of the `fact` or `ds64` chunk
+    pub fn sample_count(&self) -> Option<u64> {
+        self.sample_count
+    }
+    pub fn info(&self) -> &[(String, String)] {
+        &self.info
+    }
+    /// Value of a `LIST/INFO` entry, e.g. `INAM` title, `IART` artist, `IPRD` album,
+    /// `ICMT` comment
+    pub fn info_text(&self, id: &str) -> Option<&str> {
+        self.info
+            .iter()
+            .find(|(key, _)| key == id)
+            .map(|(_, value)| value.as_str())
+    }
+    /// Tag of the `id3 ` chunk
+    pub fn id3(&self) -> Option<&ID3<'a>> {
+        self.id3.as_ref()
+    }
+    /// Pictures of the ID3 tag
+    pub fn pictures(&self) -> Vec<Picture<'a>> {
+        self.id3.as_ref().map(|it| it.pictures()).unwrap_or_default()
+    }
+    pub fn front_cover(&self) -> Option<Picture<'a>> {
+        self.id3.as_ref().and_then(|it| it.front_cover())
+    }
+    /// Broadcast Wave extension of the `bext` chunk
+    pub fn bext(&self) -> Option<&BroadcastExtension> {
+        self.bext.as_ref()
+    }
+    /// Duration from the `data` size, or the sample count of compressed formats
+    pub fn duration(&self) -> Option<Duration> {
+        let format = self.wave_format.as_ref()?;
+        if format.format_code() != 1 && format.format_code() != 3 {
+            if let Some(samples) = self.sample_count.filter(|_| format.sample_rate != 0) {
+                return Some(Duration::from_micros(
+                    (samples as u128 * 1_000_000 / format.sample_rate as u128) as u64,
+                ));
+            }
+        }
+        if format.byte_rate == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(
+            (self.data_size as u128 * 1_000_000 / format.byte_rate as u128) as u64,
+        ))
+    }
+    /// None if the `fmt ` chunk is missing
+    pub fn properties(&self) -> Option<AudioProperties> {
+        let format = self.wave_format.as_ref()?;
+        let codec = match format.codec() {
+            Some(codec) => codec.to_string(),
+            None => format!("0x{:04X}", format.format_code()),
+        };
+        let lossless = matches!(format.format_code(), 0x0001 | 0x0003);
+        let bits_per_sample = format.valid_bits_per_sample.unwrap_or(format.bits_per_sample);
+        Some(
+            AudioProperties::new(Container::Wav, codec)
+                .with_duration(self.duration())
+                .with_sample_rate(format.sample_rate)
+                .with_channels(format.channels)
+                .with_bits_per_sample(lossless.then_some(bits_per_sample))
+                .with_bitrate(average_bitrate(self.data_size, self.duration()))
+                .with_lossless(lossless),
+        )
+    }
+}
+
+/// `fmt ` chunk
+///
+/// Structure
+/// ```text
+/// | format tag | channels | sample rate | byte rate | block align | bits per sample |
+/// |    2 B     |   2 B    |     4 B     |    4 B    |     2 B     |       2 B       |
+/// | extension size | valid bits per sample | channel mask | sub format GUID |
+/// |      2 B       |          2 B          |     4 B      |      16 B       |
+/// ```
+/// The extension is present for WAVE_FORMAT_EXTENSIBLE (0xFFFE), the first two bytes of the
+/// sub format are the actual format code.
+#[derive(Debug)]
+pub struct WaveFormat {
+    format_tag: u16,
+    channels: u16,
+    sample_rate: u32,
+    byte_rate: u32,
+    block_align: u16,
+    bits_per_sample: u16,
+    valid_bits_per_sample: Option<u16>,
+    channel_mask: Option<u32>,
+    sub_format: Option<[u8; 16]>,
+}
+
+impl WaveFormat {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        let mut format = Self {
+            format_tag: reader.read_next_u16(false),
+            channels: reader.read_next_u16(false),
+            sample_rate: reader.read_next_u32(false),
+            byte_rate: reader.read_next_u32(false),
+            block_align: reader.read_next_u16(false),
+            bits_per_sample: reader.read_next_u16(false),
+            valid_bits_per_sample: None,
+            channel_mask: None,
+            sub_format: None,
+        };
+        if format.format_tag == 0xFFFE && bytes.len() >= 40 {
+            reader.skip(2);
+            format.valid_bits_per_sample = Some(reader.read_next_u16(false));
+            format.channel_mask = Some(reader.read_next_u32(false));
+            format.sub_format = Some(reader.read(16).try_into().unwrap());
+        }
+        format
+    }
+    pub fn format_tag(&self) -> u16 {
+        self.format_tag
+    }
+    pub fn is_extensible(&self) -> bool {
+        self.sub_format.is_some()
+    }
+    /// Format code, taken from the sub format for WAVE_FORMAT_EXTENSIBLE
+    pub fn format_code(&self) -> u16 {
+        match self.sub_format {
+            Some(sub_format) => u16::from_le_bytes([sub_format[0], sub_format[1]]),
+            None => self.format_tag,
+        }
+    }
+    /// Name of common format codes
+    pub fn codec(&self) -> Option<&'static str> {
+        let codec = match self.format_code() {
+            0x0001 => "PCM",
+            0x0002 => "Microsoft ADPCM",
+            0x0003 => "IEEE float",
+            0x0006 => "A-law",
+            0x0007 => "µ-law",
+            0x0011 => "IMA ADPCM",
+            0x0050 => "MPEG",
+            0x0055 => "MPEG Layer III",
+            0x2000 => "AC-3",
+            0x2001 => "DTS",
+            _ => return None,
+        };
+        Some(codec)
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// Bytes per second
+    pub fn byte_rate(&self) -> u32 {
+        self.byte_rate
+    }
+    pub fn block_align(&self) -> u16 {
+        self.block_align
+    }
+    /// Container size of a sample
+    pub fn bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+    /// Significant bits of a sample, WAVE_FORMAT_EXTENSIBLE only
+    pub fn valid_bits_per_sample(&self) -> Option<u16> {
+        self.valid_bits_per_sample
+    }
+    pub fn channel_mask(&self) -> Option<u32> {
+        self.channel_mask
+    }
+    pub fn sub_format(&self) -> Option<&[u8; 16]> {
+        self.sub_format.as_ref()
+    }
+    /// Speaker positions of the channel mask, e.g. `["FL", "FR"]`
+    pub fn speakers(&self) -> Vec<&'static str> {
+        let mask = self.channel_mask.unwrap_or(0);
+        SPEAKERS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, it)| *it)
+            .collect()
+    }
+}
+
+/// Broadcast Wave `bext` chunk (EBU Tech 3285)
+///
+/// Structure
+/// ```text
+/// | description | originator | originator reference | origination date | origination time |
+/// |    256 B    |    32 B    |         32 B         |       10 B       |       8 B        |
+/// | time reference | version | UMID | loudness value, range, max true peak, max momentary,
+/// |   8 B (low, high)   |   2 B   | 64 B | max short term (2 B each, 1/100) | reserved 180 B |
+/// | coding history |
+/// |      nn B      |
+/// ```
+#[derive(Debug)]
+pub struct BroadcastExtension {
+    description: String,
+    originator: String,
+    originator_reference: String,
+    origination_date: String,
+    origination_time: String,
+    time_reference: u64,
+    version: u16,
+    umid: Vec<u8>,
+    loudness_value: Option<i16>,
+    loudness_range: Option<i16>,
+    max_true_peak_level: Option<i16>,
+    max_momentary_loudness: Option<i16>,
+    max_short_term_loudness: Option<i16>,
+    coding_history: String,
+}
+
+impl BroadcastExtension {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut reader = ByteReader::new(bytes);
+        let mut read_string = |len: usize| {
+            String::from_utf8_lossy(reader.read(len))
+                .trim_end_matches('\0')
+                .to_string()
+        };
+        let description = read_string(256);
+        let originator = read_string(32);
+        let originator_reference = read_string(32);
+        let origination_date = read_string(10);
+        let origination_time = read_string(8);
+        let time_reference = reader.read_next_u64(false);
+        let version = reader.read_next_u16(false);
+        let umid = reader.read(64).to_vec();
+        // loudness fields were added in version 2
+        let mut loudness = [None; 5];
+        if version >= 2 {
+            for it in loudness.iter_mut() {
+                *it = Some(reader.read_next_u16(false) as i16);
+            }
+        } else {
+            reader.skip(10);
+        }
+        reader.skip(180);
+        let coding_history =
+            String::from_utf8_lossy(bytes.get(reader.offset()..).unwrap_or_default())
+                .trim_end_matches('\0')
+                .to_string();
+        Self {
+            description,
+            originator,
+            originator_reference,
+            origination_date,
+            origination_time,
+            time_reference,
+            version,
+            umid,
+            loudness_value: loudness[0],
+            loudness_range: loudness[1],
+            max_true_peak_level: loudness[2],
+            max_momentary_loudness: loudness[3],
+            max_short_term_loudness: loudness[4],
+            coding_history,
+        }
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+    pub fn originator(&self) -> &str {
+        &self.originator
+    }
+    pub fn originator_reference(&self) -> &str {
+        &self.originator_reference
+    }
+    /// `yyyy-mm-dd`
+    pub fn origination_date(&self) -> &str {
+        &self.origination_date
+    }
+    /// `hh:mm:ss`
+    pub fn origination_time(&self) -> &str {
+        &self.origination_time
+    }
+    /// Samples since midnight of the first sample
+    pub fn time_reference(&self) -> u64 {
+        self.time_reference
+    }
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+    /// SMPTE UMID, all zero if unset
+    pub fn umid(&self) -> &[u8] {
+        &self.umid
+    }
+    /// Integrated loudness in LUFS, version 2 only
+    pub fn loudness_value(&self) -> Option<f64> {
+        self.loudness_value.map(|it| it as f64 / 100.0)
+    }
+    /// Loudness range in LU, version 2 only
+    pub fn loudness_range(&self) -> Option<f64> {
+        self.loudness_range.map(|it| it as f64 / 100.0)
+    }
+    /// Maximum true peak level in dBTP, version 2 only
+    pub fn max_true_peak_level(&self) -> Option<f64> {
+        self.max_true_peak_level.map(|it| it as f64 / 100.0)
+    }
+    /// Highest momentary loudness in LUFS, version 2 only
+    pub fn max_momentary_loudness(&self) -> Option<f64> {
+        self.max_momentary_loudness.map(|it| it as f64 / 100.0)
+    }
+    /// Highest short term loudness in LUFS, version 2 only
+    pub fn max_short_term_loudness(&self) -> Option<f64> {
+        self.max_short_term_loudness.map(|it| it as f64 / 100.0)
+    }
+    pub fn coding_history(&self) -> &str {
+        &self.coding_history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        if data.len() % 2 == 1 {
+            bytes.push(0x00);
+        }
+        bytes
+    }
+
+    fn riff(signature: &[u8; 4], chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut bytes = signature.to_vec();
+        bytes.extend(((body.len() + 4) as u32).to_le_bytes());
+        bytes.extend(b"WAVE");
+        bytes.extend(body);
+        bytes
+    }
+
+    fn extensible_fmt() -> Vec<u8> {
+        let mut fmt = 0xFFFEu16.to_le_bytes().to_vec();
+        fmt.extend(6u16.to_le_bytes());
+        fmt.extend(48000u32.to_le_bytes());
+        fmt.extend((48000u32 * 6 * 3).to_le_bytes());
+        fmt.extend(18u16.to_le_bytes());
+        fmt.extend(24u16.to_le_bytes());
+        fmt.extend(22u16.to_le_bytes());
+        fmt.extend(24u16.to_le_bytes());
+        fmt.extend(0x3Fu32.to_le_bytes());
+        // KSDATAFORMAT_SUBTYPE_PCM
+        fmt.extend([
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38,
+            0x9B, 0x71,
+        ]);
+        fmt
+    }
+
+    #[test]
+    fn test_wav() {
+        let mut info = b"INFO".to_vec();
+        info.extend(chunk(b"INAM", b"Title\0"));
+        info.extend(chunk(b"IART", b"Artist\0"));
+        let mut bext = vec![0x00; 602];
+        bext[..11].copy_from_slice(b"Interview 1");
+        bext[338..346].copy_from_slice(&48000u64.to_le_bytes());
+        bext[346..348].copy_from_slice(&2u16.to_le_bytes());
+        bext[412..414].copy_from_slice(&(-2300i16).to_le_bytes());
+        bext.extend(b"A=PCM,F=48");
+        let bytes = riff(
+            b"RIFF",
+            &[
+                chunk(b"fmt ", &extensible_fmt()),
+                chunk(b"LIST", &info),
+                chunk(b"bext", &bext),
+                chunk(b"data", &vec![0x00; 48000 * 18]),
+            ],
+        );
+        let wav = Wav::from_bytes(&bytes);
+        assert_eq!(wav.format(), RiffFormat::Riff);
+        let format = wav.wave_format().unwrap();
+        assert!(format.is_extensible());
+        assert_eq!(format.format_code(), 1);
+        assert_eq!(format.codec(), Some("PCM"));
+        assert_eq!(format.valid_bits_per_sample(), Some(24));
+        assert_eq!(format.speakers(), ["FL", "FR", "FC", "LFE", "BL", "BR"]);
+        assert_eq!(wav.info_text("INAM"), Some("Title"));
+        assert_eq!(wav.info_text("IART"), Some("Artist"));
+        assert_eq!(wav.duration(), Some(Duration::from_secs(1)));
+        let bext = wav.bext().unwrap();
+        assert_eq!(bext.description(), "Interview 1");
+        assert_eq!(bext.time_reference(), 48000);
+        assert_eq!(bext.loudness_value(), Some(-23.0));
+        assert_eq!(bext.coding_history(), "A=PCM,F=48");
+    }
+
+    #[test]
+    fn test_rf64() {
+        let mut fmt = 1u16.to_le_bytes().to_vec();
+        fmt.extend(2u16.to_le_bytes());
+        fmt.extend(44100u32.to_le_bytes());
+        fmt.extend(176_400u32.to_le_bytes());
+        fmt.extend(4u16.to_le_bytes());
+        fmt.extend(16u16.to_le_bytes());
+        let mut ds64 = 0u64.to_le_bytes().to_vec();
+        ds64.extend(352_800u64.to_le_bytes());
+        ds64.extend(88200u64.to_le_bytes());
+        ds64.extend(0u32.to_le_bytes());
+        let mut data = b"data".to_vec();
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend(vec![0x00; 352_800]);
+        let mut id3 = b"ID3\x03\x00\x00\x00\x00\x00\x10".to_vec();
+        id3.extend(b"TIT2\x00\x00\x00\x06\x00\x00\x03Title");
+        let bytes = riff(
+            b"RF64",
+            &[
+                chunk(b"ds64", &ds64),
+                chunk(b"fmt ", &fmt),
+                data,
+                chunk(b"id3 ", &id3),
+            ],
+        );
+        let wav = Wav::from_bytes(&bytes);
+        assert_eq!(wav.format(), RiffFormat::Rf64);
+        assert_eq!(wav.data_size(), 352_800);
+        assert_eq!(wav.sample_count(), Some(88200));
+        assert_eq!(wav.duration(), Some(Duration::from_secs(2)));
+        let tags = wav.id3().unwrap().tags();
+        assert!(
+            matches!(&tags[0], crate::ID3ParsedTag::Text((key, value)) if key == "TIT2" && value == "Title")
+        );
+    }
+}
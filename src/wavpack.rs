@@ -0,0 +1,273 @@
+use crate::id3::id3v2_length;
+use crate::properties::{average_bitrate, samples_duration, AudioProperties, Container};
+use crate::reader::Reader;
+use crate::utils::ByteReader;
+use std::time::Duration;
+
+const SAMPLE_RATES: [u32; 15] = [
+    6000, 8000, 9600, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 64000, 88200, 96000,
+    192000,
+];
+
+const MONO: u32 = 0x04;
+const HYBRID: u32 = 0x08;
+const JOINT_STEREO: u32 = 0x10;
+const FLOAT_DATA: u32 = 0x80;
+const INITIAL_BLOCK: u32 = 0x800;
+const FALSE_STEREO: u32 = 0x40000000;
+const DSD: u32 = 0x80000000;
+
+const ID_CHANNEL_INFO: u8 = 0x0D;
+const ID_DSD_BLOCK: u8 = 0x0E;
+const ID_SAMPLE_RATE: u8 = 0x27;
+
+/// WavPack file, made of blocks each starting with a header, all fields are little-endian
+///
+/// Structure
+/// ```text
+/// | "wvpk" | block size - 8 | version | block index (high) | total samples (high) |
+/// |  4 B   |      4 B       |   2 B   |        1 B         |         1 B          |
+/// | total samples | block index | block samples | flags | crc | metadata sub block * | audio |
+/// |      4 B      |     4 B     |      4 B      |  4 B  | 4 B |                      |       |
+/// ```
+/// A stream with more than 2 channels stores every pair of channels in its own block, the first
+/// block of a group has the initial block flag.
+#[derive(Debug)]
+pub struct WavPack {
+    version: u16,
+    /// None when unknown, e.g. written by a pipe
+    total_samples: Option<u64>,
+    flags: u32,
+    sample_rate: u32,
+    channels: u16,
+    /// Shift of the DSD sample rate, each sample is a byte of 8 DSD bits
+    dsd_rate_shift: Option<u8>,
+    /// Size of the blocks, excluding the leading tags
+    audio_size: usize,
+}
+
+impl Reader<'_> for WavPack {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        if !WavPack::is(bytes) {
+            panic!("Invalid wavpack audio format.");
+        }
+        let offset = id3v2_length(bytes);
+        let mut reader = ByteReader::with_offset(bytes, offset + 4);
+        let block_size = reader.read_next_u32(false) as usize;
+        let version = reader.read_next_u16(false);
+        reader.skip(1);
+        let total_samples_high = reader.read_next_u8() as u64;
+        let total_samples_low = reader.read_next_u32(false);
+        reader.skip(8);
+        let flags = reader.read_next_u32(false);
+        let total_samples = if total_samples_low == u32::MAX {
+            None
+        } else {
+            Some((total_samples_high << 32) + total_samples_low as u64 - total_samples_high)
+        };
+        let mut wavpack = Self {
+            version,
+            total_samples,
+            flags,
+            sample_rate: SAMPLE_RATES
+                .get(((flags >> 23) & 0x0F) as usize)
+                .copied()
+                .unwrap_or(0),
+            channels: if flags & (MONO | FALSE_STEREO) == MONO {
+                1
+            } else {
+                2
+            },
+            dsd_rate_shift: None,
+            audio_size: bytes.len() - offset,
+        };
+        let end = (offset + 8 + block_size).min(bytes.len());
+        wavpack.parse_sub_blocks(&bytes[(offset + 32).min(end)..end]);
+        if wavpack.total_samples.is_none() {
+            wavpack.total_samples = Self::count_samples(&bytes[offset..]);
+        }
+        wavpack
+    }
+    fn is(bytes: &[u8]) -> bool {
+        let offset = id3v2_length(bytes);
+        bytes.len() >= offset + 32 && bytes[offset..offset + 4] == *b"wvpk"
+    }
+}
+
+impl WavPack {
+    /// Metadata sub blocks, `| id 1 B | size in words, 1 B or 3 B | data |`
+    fn parse_sub_blocks(&mut self, bytes: &[u8]) {
+        let mut offset = 0;
+        while offset + 2 <= bytes.len() {
+            let id = bytes[offset];
+            let (size, header) = if id & 0x80 != 0 {
+                if offset + 4 > bytes.len() {
+                    break;
+                }
+                let size = ByteReader::with_offset(bytes, offset + 1).read_next_u24(false);
+                (size as usize * 2, 4)
+            } else {
+                (bytes[offset + 1] as usize * 2, 2)
+            };
+            let start = offset + header;
+            let end = (start + size).min(bytes.len());
+            // the last byte is padding
+            let data = if id & 0x40 != 0 {
+                &bytes[start..end.saturating_sub(1).max(start)]
+            } else {
+                &bytes[start..end]
+            };
+            match id & 0x3F {
+                ID_CHANNEL_INFO if !data.is_empty() => self.channels = data[0] as u16,
+                ID_SAMPLE_RATE if data.len() >= 3 => {
+                    self.sample_rate = ByteReader::new(data).read_next_u24(false)
+                        | if data.len() >= 4 {
+                            (data[3] as u32 & 0x7F) << 24
+                        } else {
+                            0
+                        };
+                }
+                ID_DSD_BLOCK if !data.is_empty() => self.dsd_rate_shift = Some(data[0]),
+                _ => (),
+            }
+            offset = start + size;
+        }
+    }
+    /// Sums the samples of the initial blocks when the header doesn't store the total
+    fn count_samples(bytes: &[u8]) -> Option<u64> {
+        let mut offset = 0;
+        let mut samples = 0u64;
+        while offset + 32 <= bytes.len() && bytes[offset..offset + 4] == *b"wvpk" {
+            let mut reader = ByteReader::with_offset(bytes, offset + 4);
+            let block_size = reader.read_next_u32(false) as usize;
+            reader.skip(12);
+            let block_samples = reader.read_next_u32(false) as u64;
+            let flags = reader.read_next_u32(false);
+            if flags & INITIAL_BLOCK != 0 {
+                samples += block_samples;
+            }
+            offset += 8 + block_size;
+        }
+        (samples > 0).then_some(samples)
+    }
+    /// Stream version, 0x402 to 0x410
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+    /// Samples per channel
+    pub fn total_samples(&self) -> Option<u64> {
+        self.total_samples
+    }
+    /// The DSD bit rate for DSD audio
+    pub fn sample_rate(&self) -> u32 {
+        match self.dsd_rate_shift {
+            Some(shift) if self.is_dsd() => self.sample_rate << shift.min(8),
+            _ => self.sample_rate,
+        }
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    pub fn bits_per_sample(&self) -> u16 {
+        if self.is_dsd() {
+            return 1;
+        }
+        let bytes = (self.flags & 0x03) as u16 + 1;
+        let shift = ((self.flags >> 13) & 0x1F) as u16;
+        (bytes * 8).saturating_sub(shift)
+    }
+    pub fn is_mono(&self) -> bool {
+        self.channels == 1
+    }
+    pub fn is_joint_stereo(&self) -> bool {
+        self.flags & JOINT_STEREO != 0
+    }
+    /// Hybrid (lossy) mode, lossless when the correction file (`.wvc`) is available
+    pub fn is_hybrid(&self) -> bool {
+        self.flags & HYBRID != 0
+    }
+    pub fn is_lossless(&self) -> bool {
+        !self.is_hybrid()
+    }
+    pub fn is_float(&self) -> bool {
+        self.flags & FLOAT_DATA != 0
+    }
+    pub fn is_dsd(&self) -> bool {
+        self.flags & DSD != 0
+    }
+    pub fn duration(&self) -> Option<Duration> {
+        // DSD samples and rate are both counted in bytes
+        samples_duration(self.total_samples?, self.sample_rate)
+    }
+    pub fn properties(&self) -> AudioProperties {
+        AudioProperties::new(Container::WavPack, "WavPack")
+            .with_duration(self.duration())
+            .with_sample_rate(self.sample_rate())
+            .with_channels(self.channels)
+            .with_bits_per_sample(Some(self.bits_per_sample()))
+            .with_bitrate(average_bitrate(self.audio_size as u64, self.duration()))
+            .with_lossless(self.is_lossless())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(total_samples: u32, block_samples: u32, flags: u32, sub_blocks: &[u8]) -> Vec<u8> {
+        let mut bytes = b"wvpk".to_vec();
+        bytes.extend(((24 + sub_blocks.len()) as u32).to_le_bytes());
+        bytes.extend(0x410u16.to_le_bytes());
+        bytes.extend([0x00, 0x00]);
+        bytes.extend(total_samples.to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(block_samples.to_le_bytes());
+        bytes.extend(flags.to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(sub_blocks);
+        bytes
+    }
+
+    #[test]
+    fn test_wavpack() {
+        // 16 bits, joint stereo, 44100 Hz
+        let flags = 0x01 | JOINT_STEREO | INITIAL_BLOCK | 9 << 23;
+        let wavpack = WavPack::from_bytes(&block(441000, 22050, flags, &[]));
+        assert_eq!(wavpack.version(), 0x410);
+        assert_eq!(wavpack.sample_rate(), 44100);
+        assert_eq!(wavpack.channels(), 2);
+        assert_eq!(wavpack.bits_per_sample(), 16);
+        assert!(wavpack.is_lossless());
+        assert_eq!(
+            wavpack.properties(),
+            AudioProperties::new(Container::WavPack, "WavPack")
+                .with_duration(Some(Duration::from_secs(10)))
+                .with_sample_rate(44100)
+                .with_channels(2)
+                .with_bits_per_sample(Some(16))
+                .with_bitrate(Some(0))
+                .with_lossless(true)
+        );
+
+        // 24 bits, 6 channels, custom 2 kHz rate, hybrid, unknown total samples
+        let flags = 0x02 | HYBRID | INITIAL_BLOCK | 15 << 23;
+        let sub_blocks = [
+            &[ID_CHANNEL_INFO, 0x02, 0x06, 0x3F, 0x00, 0x00][..],
+            &[ID_SAMPLE_RATE | 0x40, 0x02, 0xD0, 0x07, 0x00, 0x00][..],
+        ]
+        .concat();
+        let mut bytes = block(u32::MAX, 1000, flags, &sub_blocks);
+        bytes.extend(block(u32::MAX, 1000, flags & !INITIAL_BLOCK, &[]));
+        bytes.extend(block(u32::MAX, 3000, flags, &[]));
+        let wavpack = WavPack::from_bytes(&bytes);
+        assert_eq!(wavpack.channels(), 6);
+        assert_eq!(wavpack.sample_rate(), 2000);
+        assert_eq!(wavpack.bits_per_sample(), 24);
+        assert!(wavpack.is_hybrid());
+        assert_eq!(wavpack.total_samples(), Some(4000));
+        assert_eq!(wavpack.duration(), Some(Duration::from_secs(2)));
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt;
 use serde::Serialize;
 use wasm_bindgen::{JsCast};
 use wasm_bindgen::prelude::wasm_bindgen;
-use ptdgrp_audmetap::{Flac, Ogg, ID3, Reader, ID3ParsedTag, FlacParsedBlock, OggParsedPage};
+use ptdgrp_audmetap::{Aiff, Caf, Dff, Dsf, Flac, Matroska, Ogg, ID3, Mp4, Wav, Picture, Reader, ID3ParsedTag, FlacParsedBlock, OggParsedPage};
 
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -46,6 +46,16 @@ impl fmt::Debug for Image {
     }
 }
 
+impl From<Picture<'_>> for Image {
+    fn from(picture: Picture<'_>) -> Self {
+        Self {
+            data: Vec::from(picture.data()),
+            description: String::from(picture.description()),
+            mime: String::from(picture.mime()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct Metadata {
     /// 音频标题
@@ -71,16 +81,7 @@ fn parse(bytes: Vec<u8>) -> Option<Metadata> {
                     _ => map
                 }
             });
-            let cover = parser.blocks().iter().find_map(|it| {
-                match it {
-                    FlacParsedBlock::Picture(picture) => Some(Image {
-                        data: Vec::from(picture.data()),
-                        description: String::from(picture.description()),
-                        mime: String::from(picture.mime()),
-                    }),
-                    _ => None
-                }
-            });
+            let cover = parser.front_cover().map(Image::from);
             Some(Metadata {
                 title: fields.get("TITLE").map(|&it| String::from(it)),
                 artist: fields.get("ARTIST").map(|&it| String::from(it)),
@@ -89,25 +90,22 @@ fn parse(bytes: Vec<u8>) -> Option<Metadata> {
             })
         }
         bytes if Ogg::is(bytes) => {
-            let parser = Ogg::from_bytes(&bytes);
+            let parser = Ogg::from_bytes(bytes);
             let fields = parser.pages().iter().fold(HashMap::new(), |mut map, it| {
                 match it {
-                    OggParsedPage::Comments(comment) => {
+                    OggParsedPage::Comments(comment) | OggParsedPage::FlacBlock(FlacParsedBlock::Comment(comment)) => {
                         map.extend(comment.comments().iter().map(|(a, b)| (a.as_str(), b.as_str())));
                         map
                     }
                     _ => map
                 }
             });
+            let cover = parser.front_cover().map(Image::from);
             Some(Metadata {
                 title: fields.get("TITLE").map(|&it| String::from(it)),
                 artist: fields.get("ARTIST").map(|&it| String::from(it)),
                 album: fields.get("ALBUM").map(|&it| String::from(it)),
-                cover: fields.get("METADATA_BLOCK_PICTURE").map(|&it| Ogg::parse_picture(it)).map(|picture| Image {
-                    data: Vec::from(picture.data()),
-                    description: String::from(picture.description()),
-                    mime: String::from(picture.mime()),
-                }),
+                cover,
             })
         }
         bytes if ID3::is(bytes) => {
@@ -116,14 +114,7 @@ fn parse(bytes: Vec<u8>) -> Option<Metadata> {
                 ID3ParsedTag::Text((key, value)) => Some((key.as_str(), value.as_str())),
                 _ => None
             }).collect::<HashMap<&str, &str>>();
-            let cover = parser.tags().iter().find_map(|it| match it {
-                ID3ParsedTag::AttachedPicture(picture) => Some(Image {
-                    data: Vec::from(picture.data()),
-                    description: String::from(picture.description()),
-                    mime: String::from(picture.mime()),
-                }),
-                _ => None
-            });
+            let cover = parser.front_cover().map(Image::from);
             Some(Metadata {
                 title: fields.get("TIT2").map(|&it| String::from(it)),
                 artist: fields.get("TPE1").map(|&it| String::from(it)),
@@ -131,6 +122,91 @@ fn parse(bytes: Vec<u8>) -> Option<Metadata> {
                 cover,
             })
         }
+        bytes if Mp4::is(bytes) => {
+            let parser = Mp4::from_bytes(bytes);
+            let cover = parser.front_cover().map(Image::from);
+            Some(Metadata {
+                title: parser.text("©nam").map(String::from),
+                artist: parser.text("©ART").map(String::from),
+                album: parser.text("©alb").map(String::from),
+                cover,
+            })
+        }
+        bytes if Wav::is(bytes) => {
+            let parser = Wav::from_bytes(bytes);
+            let fields = parser.id3().map(|id3| id3.tags().iter().filter_map(|it| match it {
+                ID3ParsedTag::Text((key, value)) => Some((key.as_str(), value.as_str())),
+                _ => None
+            }).collect::<HashMap<&str, &str>>()).unwrap_or_default();
+            let cover = parser.front_cover().map(Image::from);
+            Some(Metadata {
+                title: parser.info_text("INAM").or(fields.get("TIT2").copied()).map(String::from),
+                artist: parser.info_text("IART").or(fields.get("TPE1").copied()).map(String::from),
+                album: parser.info_text("IPRD").or(fields.get("TALB").copied()).map(String::from),
+                cover,
+            })
+        }
+        bytes if Aiff::is(bytes) => {
+            let parser = Aiff::from_bytes(bytes);
+            let fields = parser.id3().map(|id3| id3.tags().iter().filter_map(|it| match it {
+                ID3ParsedTag::Text((key, value)) => Some((key.as_str(), value.as_str())),
+                _ => None
+            }).collect::<HashMap<&str, &str>>()).unwrap_or_default();
+            let cover = parser.front_cover().map(Image::from);
+            Some(Metadata {
+                title: fields.get("TIT2").copied().or(parser.name()).map(String::from),
+                artist: fields.get("TPE1").copied().or(parser.author()).map(String::from),
+                album: fields.get("TALB").map(|it| String::from(*it)),
+                cover,
+            })
+        }
+        bytes if Matroska::is(bytes) => {
+            let parser = Matroska::from_bytes(bytes);
+            let cover = parser.front_cover().map(Image::from);
+            Some(Metadata {
+                title: parser.title().or(parser.info().title()).map(String::from),
+                artist: parser.artist().map(String::from),
+                album: parser.album().map(String::from),
+                cover,
+            })
+        }
+        bytes if Dsf::is(bytes) => {
+            let parser = Dsf::from_bytes(bytes);
+            let fields = parser.id3().map(|id3| id3.tags().iter().filter_map(|it| match it {
+                ID3ParsedTag::Text((key, value)) => Some((key.as_str(), value.as_str())),
+                _ => None
+            }).collect::<HashMap<&str, &str>>()).unwrap_or_default();
+            let cover = parser.front_cover().map(Image::from);
+            Some(Metadata {
+                title: fields.get("TIT2").map(|it| String::from(*it)),
+                artist: fields.get("TPE1").map(|it| String::from(*it)),
+                album: fields.get("TALB").map(|it| String::from(*it)),
+                cover,
+            })
+        }
+        bytes if Dff::is(bytes) => {
+            let parser = Dff::from_bytes(bytes);
+            let fields = parser.id3().map(|id3| id3.tags().iter().filter_map(|it| match it {
+                ID3ParsedTag::Text((key, value)) => Some((key.as_str(), value.as_str())),
+                _ => None
+            }).collect::<HashMap<&str, &str>>()).unwrap_or_default();
+            let cover = parser.front_cover().map(Image::from);
+            Some(Metadata {
+                title: parser.title().or(fields.get("TIT2").copied()).map(String::from),
+                artist: parser.artist().or(fields.get("TPE1").copied()).map(String::from),
+                album: fields.get("TALB").map(|it| String::from(*it)),
+                cover,
+            })
+        }
+        bytes if Caf::is(bytes) => {
+            let parser = Caf::from_bytes(bytes);
+            Some(Metadata {
+                title: parser.info_text("title").map(String::from),
+                artist: parser.info_text("artist").map(String::from),
+                album: parser.info_text("album").map(String::from),
+                cover: None,
+            })
+        }
         _ => {
             None
         }